@@ -0,0 +1,13 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Put the linker script of the project where the linker finds it before the one of the
+// HAL, so the pages of flash reserved for the saved states are kept out of the program.
+fn main() {
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::copy("memory.x", out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=memory.x");
+}
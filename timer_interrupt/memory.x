@@ -0,0 +1,11 @@
+/* Linker script for the nRF52833 of the micro:bit v2. It replaces the one of the HAL,
+   which gives the program the whole flash. */
+MEMORY
+{
+  /* NOTE K = KiBi = 1024 bytes */
+  /* The last two pages of the flash (8 KiB, from 0x7e000) are left out, since they hold
+     the journal of saved states (see src/storage.rs). A program that grows into them
+     fails to link, instead of being erased by the first saves. */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 512K - 8K
+  RAM : ORIGIN = 0x20000000, LENGTH = 128K
+}
@@ -0,0 +1,206 @@
+use crate::debounce::Debouncer;
+use crate::timing::{ms_to_polls, DEBOUNCE_MS, DOUBLE_CLICK_MS, LONG_PRESS_MS, REPEAT_INTERVAL_MS};
+use embedded_hal::digital::v2::InputPin;
+
+// Number of consecutive polls that a button must be read with the same value for the
+// change to be accepted (4 polls, about 24 ms).
+const DEBOUNCE_POLLS: u8 = ms_to_polls(DEBOUNCE_MS) as u8;
+
+// Level of the pin of a button while the button is pressed.
+#[derive(Clone, Copy)]
+pub enum ActiveLevel {
+    // The button connects the pin to GND, like the buttons of the micro:bit, which have
+    // external pull-up resistors.
+    Low,
+    // The button connects the pin to the supply voltage, like the external buttons of
+    // the rings with the ring-buttons-3v feature.
+    #[cfg_attr(not(feature = "ring-buttons-3v"), allow(dead_code))]
+    High,
+}
+
+// A button connected to an input pin. It works with any pin that implements the
+// InputPin trait of embedded-hal, and the level of the pin while the button is pressed
+// is configurable. The pull resistor of the pin is configured when the pin is created,
+// e.g., with into_pullup_input(), so the type of the pin says which one it uses.
+pub struct Button<P> {
+    pin: P,
+    active_level: ActiveLevel,
+}
+
+impl<P: InputPin> Button<P> {
+    pub fn new(pin: P, active_level: ActiveLevel) -> Self {
+        Self { pin, active_level }
+    }
+
+    // Returns true if the button is being pressed. The value is not debounced. Reading
+    // the pins of most boards can't fail, so it's safe to default to "not pressed".
+    pub fn is_pressed(&self) -> bool {
+        match self.active_level {
+            ActiveLevel::Low => self.pin.is_low().unwrap_or(false),
+            ActiveLevel::High => self.pin.is_high().unwrap_or(false),
+        }
+    }
+
+    // The pin of the button, e.g., to configure the interrupts of its changes.
+    pub fn pin(&self) -> &P {
+        &self.pin
+    }
+}
+
+// Events generated by a button.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    // The button was pressed and released before LONG_PRESS_MS.
+    ShortPress,
+    // The button has been kept pressed for LONG_PRESS_MS.
+    LongPress,
+    // The button is still pressed after a long press. Generated every
+    // REPEAT_INTERVAL_MS after the long press, until the button is released.
+    Repeat,
+    // The button was pressed and released twice in quick succession. Only generated by
+    // buttons with double click detection.
+    DoubleClick,
+}
+
+// Keeps track of how long a button has been pressed, so that short and long presses
+// can be told apart. A short press is only reported when the button is released, since
+// until then it could still become a long press. A long press is reported as soon as
+// the button has been held long enough, without waiting for it to be released, and if
+// the button is kept pressed, it's followed by repeat events, like the keys of a
+// keyboard.
+// If double click detection is enabled, a short press is not reported until
+// DOUBLE_CLICK_MS after its release, since until then it could still become a
+// double click.
+// A press can also be cancelled, for example when the button is part of a button
+// combination, in which case it doesn't generate any event until it's released.
+// The values read from the button are debounced first, so the bounces of the contacts
+// don't generate extra presses. The times are measured with the uptime, in
+// milliseconds, which is given with every reading.
+pub struct ButtonState {
+    debouncer: Debouncer,
+    // Time at which the current press started, or None if the button is released:
+    pressed_at: Option<u64>,
+    // Number of long press and repeat events generated by the current press:
+    held_events: u32,
+    // Flag set when the current press has been cancelled:
+    cancelled: bool,
+    // Flag set if the button can generate double clicks:
+    double_click: bool,
+    // Time at which a short press that could become a double click was released, or
+    // None if there is no such press:
+    released_at: Option<u64>,
+}
+
+impl ButtonState {
+    pub const fn new() -> Self {
+        Self {
+            debouncer: Debouncer::new(DEBOUNCE_POLLS),
+            pressed_at: None,
+            held_events: 0,
+            cancelled: false,
+            double_click: false,
+            released_at: None,
+        }
+    }
+
+    // Create the state of a button that generates double clicks.
+    pub const fn with_double_click() -> Self {
+        let mut state = Self::new();
+        state.double_click = true;
+        state
+    }
+
+    // Returns true if the button is being pressed and the press has not been cancelled.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed_at.is_some() && !self.cancelled
+    }
+
+    // Returns true if the button is being pressed, even if the press has been cancelled.
+    pub fn is_held(&self) -> bool {
+        self.pressed_at.is_some()
+    }
+
+    // Returns true if the button is released, its debouncer is settled and no short
+    // press is waiting for a possible double click, i.e., updating it with the button
+    // released wouldn't change anything.
+    pub fn is_idle(&self) -> bool {
+        self.pressed_at.is_none() && self.released_at.is_none() && self.debouncer.is_settled()
+    }
+
+    // Cancel the current press, so it generates no events. A previous short press that
+    // was waiting for a possible double click is discarded too.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.released_at = None;
+    }
+
+    // Update the state of the button with the value read in the last poll and the
+    // uptime at which it was read, and return the event that has been generated, if any.
+    pub fn update(&mut self, sample_pressed: bool, now_ms: u64) -> Option<ButtonEvent> {
+        let pressed = self.debouncer.update(sample_pressed);
+        if self.cancelled {
+            // Once a cancelled press is released, the button works normally again.
+            if !pressed {
+                self.pressed_at = None;
+                self.held_events = 0;
+                self.cancelled = false;
+            }
+            return None;
+        }
+
+        if pressed {
+            let pressed_at = *self.pressed_at.get_or_insert(now_ms);
+            // The long press is the first event generated while the button is held, and
+            // the repeats come every REPEAT_INTERVAL_MS after it.
+            let next_event_ms = LONG_PRESS_MS + self.held_events as u64 * REPEAT_INTERVAL_MS;
+            if now_ms - pressed_at < next_event_ms {
+                return None;
+            }
+            self.held_events += 1;
+            if self.held_events == 1 {
+                // A long press is never part of a double click.
+                self.released_at = None;
+                Some(ButtonEvent::LongPress)
+            } else {
+                Some(ButtonEvent::Repeat)
+            }
+        } else {
+            let was_pressed = self.pressed_at.take().is_some();
+            let held_events = core::mem::take(&mut self.held_events);
+            if was_pressed && held_events == 0 {
+                self.short_press_released(now_ms)
+            } else {
+                self.wait_for_double_click(now_ms)
+            }
+        }
+    }
+
+    // Handle the release of a short press.
+    fn short_press_released(&mut self, now_ms: u64) -> Option<ButtonEvent> {
+        if !self.double_click {
+            return Some(ButtonEvent::ShortPress);
+        }
+        match self.released_at.take() {
+            // This is the second click.
+            Some(_) => Some(ButtonEvent::DoubleClick),
+            // This may be the first click of a double click, wait for the second.
+            None => {
+                self.released_at = Some(now_ms);
+                None
+            }
+        }
+    }
+
+    // While the button is released after a short press, check if the second click is
+    // still in time. If it doesn't come in time, the first one is reported as a short
+    // press.
+    fn wait_for_double_click(&mut self, now_ms: u64) -> Option<ButtonEvent> {
+        let released_at = self.released_at?;
+        if now_ms - released_at > DOUBLE_CLICK_MS {
+            self.released_at = None;
+            Some(ButtonEvent::ShortPress)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::button::ButtonState;
+use crate::input::{EventProducer, InputEvent};
+use crate::timing::{POWER_OFF_HOLD_MS, RESET_HOLD_MS};
+
+// The states of the two buttons of the board, and of their chords. It turns the
+// readings of the buttons into input events the same way whether they are polled or
+// read through the GPIOTE, so both ways generate the same events.
+pub struct ButtonPair {
+    a_state: ButtonState,
+    // Button b also detects double clicks:
+    b_state: ButtonState,
+    // Time at which both buttons started to be held, or None if they aren't, and for how
+    // long they had been held at the last poll:
+    chord_at: Option<u64>,
+    chord_held_ms: u64,
+    // Flag set once the long hold of the chord has been generated:
+    long_hold: bool,
+}
+
+impl ButtonPair {
+    pub const fn new() -> Self {
+        Self {
+            a_state: ButtonState::new(),
+            b_state: ButtonState::with_double_click(),
+            chord_at: None,
+            chord_held_ms: 0,
+            long_hold: false,
+        }
+    }
+
+    // Update the states with the values of the buttons read in the last poll and the
+    // uptime at which they were read, and push the events they have generated to the
+    // queue.
+    pub fn update(
+        &mut self,
+        a_pressed: bool,
+        b_pressed: bool,
+        now_ms: u64,
+        events: &mut EventProducer,
+    ) {
+        let a_event = self.a_state.update(a_pressed, now_ms);
+        let b_event = self.b_state.update(b_pressed, now_ms);
+
+        // Pressing both buttons at the same time (a chord) generates a single event.
+        // The presses are cancelled so that the individual buttons don't generate
+        // events when they are released.
+        if self.a_state.is_pressed() && self.b_state.is_pressed() {
+            self.a_state.cancel();
+            self.b_state.cancel();
+            events.enqueue(InputEvent::Chord).ok();
+        }
+        // If the buttons of the chord are kept pressed for RESET_HOLD_MS, a long hold is
+        // generated by the first poll after that time. If they are released before, but
+        // after POWER_OFF_HOLD_MS, a hold is generated when they are released. So only
+        // one of them is generated, and a long hold is never preceded by a hold.
+        if self.a_state.is_held() && self.b_state.is_held() {
+            self.chord_held_ms = now_ms - *self.chord_at.get_or_insert(now_ms);
+            if self.chord_held_ms >= RESET_HOLD_MS && !self.long_hold {
+                self.long_hold = true;
+                events.enqueue(InputEvent::ChordLongHold).ok();
+            }
+        } else if self.chord_at.take().is_some() {
+            if self.chord_held_ms >= POWER_OFF_HOLD_MS && !self.long_hold {
+                events.enqueue(InputEvent::ChordHold).ok();
+            }
+            self.chord_held_ms = 0;
+            self.long_hold = false;
+        }
+        if let Some(event) = a_event {
+            events.enqueue(InputEvent::ButtonA(event)).ok();
+        }
+        if let Some(event) = b_event {
+            events.enqueue(InputEvent::ButtonB(event)).ok();
+        }
+    }
+
+    // Returns true if both buttons are released and settled, and no event is waiting
+    // for more polls, so they don't need to be polled until one of them changes.
+    pub fn is_idle(&self) -> bool {
+        self.a_state.is_idle() && self.b_state.is_idle()
+    }
+}
@@ -0,0 +1,75 @@
+// Colors of the live cells, depending on their age: the cells that have just been born
+// are green, and they turn yellow, orange and finally red as they survive more
+// generations. Each color is used for cells of at least the given age.
+const AGE_COLORS: [(u8, Rgb); 4] = [
+    (1, Rgb::new(0, 255, 0)),
+    (2, Rgb::new(255, 255, 0)),
+    (4, Rgb::new(255, 96, 0)),
+    (8, Rgb::new(255, 0, 0)),
+];
+// Color of the dead cells that are still glowing as part of the death trail.
+const TRAIL_COLOR: Rgb = Rgb::new(0, 64, 255);
+// Color used for everything that is not a cell, e.g., text and graphs.
+const PLAIN_COLOR: Rgb = Rgb::new(255, 255, 255);
+
+// A color, with the intensity of its red, green and blue components.
+#[derive(Clone, Copy)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    // Scale the color to the brightness of an LED, from 0 to 9.
+    fn scaled(self, brightness: u8) -> Self {
+        let scale = |value: u8| (value as u16 * brightness as u16 / 9) as u8;
+        Self::new(scale(self.red), scale(self.green), scale(self.blue))
+    }
+}
+
+// Keeps track of the number of generations that each cell has been alive for.
+pub struct CellAges {
+    ages: [[u8; 5]; 5],
+}
+
+impl CellAges {
+    pub const fn new() -> Self {
+        Self { ages: [[0; 5]; 5] }
+    }
+
+    // Update the ages after a generation, or after the grid has been edited: the cells
+    // that are alive get one generation older and the dead ones are reset.
+    pub fn update(&mut self, matrix: [[bool; 5]; 5]) {
+        for (row_n, row) in matrix.into_iter().enumerate() {
+            for (col_n, alive) in row.into_iter().enumerate() {
+                let age = &mut self.ages[row_n][col_n];
+                *age = if alive { age.saturating_add(1) } else { 0 };
+            }
+        }
+    }
+
+    pub fn ages(&self) -> [[u8; 5]; 5] {
+        self.ages
+    }
+}
+
+// Color of a cell of the game, given its age (0 for dead cells) and the brightness of
+// its LED.
+pub fn cell_color(age: u8, brightness: u8) -> Rgb {
+    let color = AGE_COLORS
+        .iter()
+        .rev()
+        .find(|(min_age, _)| age >= *min_age)
+        .map_or(TRAIL_COLOR, |(_, color)| *color);
+    color.scaled(brightness)
+}
+
+// Color of an LED that doesn't show a cell of the game.
+pub fn plain_color(brightness: u8) -> Rgb {
+    PLAIN_COLOR.scaled(brightness)
+}
@@ -0,0 +1,312 @@
+use crate::patterns::PATTERNS;
+use crate::rle::RlePattern;
+use crate::rules::RULES;
+use crate::stitch::Tile;
+use crate::sync_pulse::SyncPin;
+use crate::telemetry::TelemetryOutput;
+use crate::timing::FASTEST_SPEED;
+use core::fmt;
+
+// Maximum number of columns and rows of tiles of a world shared by several boards.
+const MAX_TILES: u8 = 8;
+
+// Commands that can be sent to the board through the serial interface, one per line.
+#[derive(Clone, Copy)]
+pub enum Command {
+    // "help": list the commands.
+    Help,
+    // "show": show the grid.
+    Show,
+    // "pause": pause the game.
+    Pause,
+    // "resume": resume the game.
+    Resume,
+    // "step": pause the game and advance it one generation.
+    Step,
+    // "speed 3": select one of the speeds, from 0 (slowest) to FASTEST_SPEED.
+    Speed(usize),
+    // "rand 0.3": fill the grid with a random soup with the given density of live cells,
+    // from 0 to 1. It carries the density as a percentage.
+    Random(u8),
+    // "seed 42": fill the grid with the random soup generated from the given seed, which
+    // is always the same for the same seed.
+    Seed(u32),
+    // "load glider": replace the grid with one of the patterns of the library. It
+    // carries the index of the pattern.
+    Load(usize),
+    // "frames on" or "frames off": start or stop streaming every generation as text over
+    // RTT.
+    Frames(bool),
+    // "cpu on" or "cpu off": start or stop reporting the load of the CPU over RTT.
+    CpuLoad(bool),
+    // "telemetry off", "telemetry rtt" or "telemetry serial": select where the frames of
+    // the binary telemetry are sent.
+    Telemetry(TelemetryOutput),
+    // "tile 1 0 3 2": set the tile of the board in a world shared by several boards: its
+    // column and row, and the number of columns and rows of tiles of the world.
+    Tile(Tile),
+    // "key 000102030405060708090a0b0c0d0e0f" or "key off": set the key that the packets of
+    // the radio are encrypted with (32 hexadecimal digits), or stop encrypting them.
+    Key(Option<[u8; 16]>),
+    // "sync 12" or "sync off": select the pin of the edge connector that changes level
+    // with every generation, or stop the sync signal.
+    Sync(Option<SyncPin>),
+    // "save": save the grid and the settings to flash, to be restored when the board
+    // starts.
+    Save,
+    // "rewind 3": go back to one of the states saved to flash, the given number of saves
+    // before the last one.
+    Rewind(usize),
+    // "rle": show the grid in RLE, to paste it in Golly.
+    Rle,
+    // "log": show the last events recorded in the event log.
+    Log,
+    // A pattern copied from Golly and pasted in the shell, in RLE. It replaces the grid,
+    // and selects its rule if it gives one.
+    Paste(RlePattern),
+    // "rule B36/S23" or "rule highlife": select one of the available rules, by its
+    // rulestring or its name. It carries the index of the rule.
+    Rule(usize),
+}
+
+// List of the commands, shown by the help command.
+pub const HELP: &str = "\
+help          list the commands\r
+show          show the grid\r
+pause         pause the game\r
+resume        resume the game\r
+step          pause the game and advance it one generation\r
+speed <n>     select the speed, from 0 (slowest) to 4\r
+rand <d>      fill the grid with a random soup, d is the density, from 0 to 1\r
+seed <n>      fill the grid with the random soup generated from the number n\r
+load <name>   load a pattern: glider, blinker, block, toad or beacon\r
+rule <rule>   select a rule by rulestring or name: B3/S23 (life), B36/S23\r
+              (highlife), B2/S (seeds) or B3678/S34678 (daynight)\r
+frames on|off stream every generation as text over the RTT channel Frames\r
+cpu on|off    report the load of the CPU over RTT every 10 s\r
+telemetry <o> send the binary telemetry to o: off, rtt (channel Telemetry) or serial\r
+tile <x> <y> <columns> <rows>\r
+              set the position of the board in a world of columns x rows boards,\r
+              used by the TILE mode of the radio\r
+save          save the grid, the rule, the speed and the edges to flash\r
+rewind <n>    go back to the state saved to flash n saves before the last one\r
+rle           show the grid in RLE, to paste it in Golly\r
+log           show the last events: inputs, changes of the game and reseeds\r
+x = ...       paste a pattern copied from Golly (RLE, up to 5x5), the lines that\r
+              follow the header are read until the ! that ends it\r
+key <k>|off   encrypt the packets of the radio with the key k, 32 hexadecimal\r
+              digits shared by the boards, or stop encrypting them\r
+sync <p>|off  toggle the pin p of the edge connector with every generation: 0, 1, 2,\r
+              12 or 14, if it isn't used by a feature\r
+";
+
+impl Command {
+    // Returns true if the command changes the grid or shows it, so the grid should be
+    // shown after running it.
+    pub fn shows_grid(self) -> bool {
+        matches!(
+            self,
+            Command::Show
+                | Command::Step
+                | Command::Random(_)
+                | Command::Seed(_)
+                | Command::Load(_)
+                | Command::Paste(_)
+                | Command::Rewind(_)
+        )
+    }
+}
+
+// Human readable description of what a command has done, to answer it.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Help => write!(f, "Commands:"),
+            Command::Show => write!(f, "Current grid:"),
+            Command::Pause => write!(f, "Game paused"),
+            Command::Resume => write!(f, "Game resumed"),
+            Command::Step => write!(f, "Advanced one generation"),
+            Command::Speed(speed) => write!(f, "Speed set to {}", speed),
+            Command::Random(density) => {
+                write!(f, "Grid filled with a random soup ({}% alive)", density)
+            }
+            Command::Seed(seed) => write!(f, "Grid filled with the soup of seed {}", seed),
+            Command::Load(pattern_n) => write!(f, "Loaded {}", PATTERNS[pattern_n].name),
+            Command::Frames(true) => write!(f, "Frame streaming on"),
+            Command::Frames(false) => write!(f, "Frame streaming off"),
+            Command::CpuLoad(true) => write!(f, "CPU load reporting on"),
+            Command::CpuLoad(false) => write!(f, "CPU load reporting off"),
+            Command::Telemetry(TelemetryOutput::Off) => write!(f, "Telemetry off"),
+            Command::Telemetry(TelemetryOutput::Rtt) => write!(f, "Telemetry sent over RTT"),
+            Command::Telemetry(TelemetryOutput::Serial) => {
+                write!(f, "Telemetry sent over the serial interface")
+            }
+            Command::Tile(tile) => write!(
+                f,
+                "Tile set to column {}, row {} of a {}x{} world",
+                tile.x, tile.y, tile.columns, tile.rows
+            ),
+            Command::Key(Some(_)) => write!(f, "The radio packets are encrypted"),
+            Command::Key(None) => write!(f, "The radio packets are no longer encrypted"),
+            Command::Sync(Some(pin)) => write!(f, "Sync signal on pin {}", pin.number()),
+            Command::Sync(None) => write!(f, "Sync signal off"),
+            Command::Save => write!(f, "State saved to flash"),
+            Command::Rewind(n) => write!(f, "Rewound {} saves", n),
+            Command::Rle => write!(f, "Current grid in RLE:"),
+            Command::Log => write!(f, "Recent events:"),
+            Command::Paste(RlePattern {
+                rule: Some(rule_n), ..
+            }) => write!(f, "Pattern pasted, rule set to {}", RULES[rule_n].name),
+            Command::Paste(_) => write!(f, "Pattern pasted"),
+            Command::Rule(rule_n) => {
+                let rule = &RULES[rule_n];
+                write!(f, "Rule set to {} ({})", rule.name, rule.rulestring)
+            }
+        }
+    }
+}
+
+// Reasons why a line is not a valid command.
+#[derive(Clone, Copy)]
+pub enum CommandError {
+    Unknown,
+    MissingArgument,
+    InvalidArgument,
+}
+
+impl CommandError {
+    pub fn message(self) -> &'static str {
+        match self {
+            CommandError::Unknown => "unknown command",
+            CommandError::MissingArgument => "missing argument",
+            CommandError::InvalidArgument => "invalid argument",
+        }
+    }
+}
+
+// Parse a line of text into a command. The name of the command is separated from its
+// argument by spaces, and the names of the patterns and the rules are case insensitive.
+pub fn parse(line: &str) -> Result<Command, CommandError> {
+    let mut words = line.split_whitespace();
+    let name = words.next().ok_or(CommandError::Unknown)?;
+    let argument = words.next();
+    match name {
+        "help" => Ok(Command::Help),
+        "show" => Ok(Command::Show),
+        "pause" => Ok(Command::Pause),
+        "resume" => Ok(Command::Resume),
+        "step" => Ok(Command::Step),
+        "speed" => {
+            let speed = parse_argument::<usize>(argument)?;
+            if speed > FASTEST_SPEED {
+                return Err(CommandError::InvalidArgument);
+            }
+            Ok(Command::Speed(speed))
+        }
+        "rand" => {
+            let density = parse_argument::<f32>(argument)?;
+            if !(0.0..=1.0).contains(&density) {
+                return Err(CommandError::InvalidArgument);
+            }
+            Ok(Command::Random((density * 100.0) as u8))
+        }
+        "seed" => Ok(Command::Seed(parse_argument(argument)?)),
+        "load" => {
+            let argument = argument.ok_or(CommandError::MissingArgument)?;
+            PATTERNS
+                .iter()
+                .position(|pattern| pattern.name.eq_ignore_ascii_case(argument))
+                .map(Command::Load)
+                .ok_or(CommandError::InvalidArgument)
+        }
+        "frames" => match argument.ok_or(CommandError::MissingArgument)? {
+            "on" => Ok(Command::Frames(true)),
+            "off" => Ok(Command::Frames(false)),
+            _ => Err(CommandError::InvalidArgument),
+        },
+        "cpu" => match argument.ok_or(CommandError::MissingArgument)? {
+            "on" => Ok(Command::CpuLoad(true)),
+            "off" => Ok(Command::CpuLoad(false)),
+            _ => Err(CommandError::InvalidArgument),
+        },
+        "telemetry" => match argument.ok_or(CommandError::MissingArgument)? {
+            "off" => Ok(Command::Telemetry(TelemetryOutput::Off)),
+            "rtt" => Ok(Command::Telemetry(TelemetryOutput::Rtt)),
+            "serial" => Ok(Command::Telemetry(TelemetryOutput::Serial)),
+            _ => Err(CommandError::InvalidArgument),
+        },
+        "tile" => {
+            let x = parse_argument(argument)?;
+            let y = parse_argument(words.next())?;
+            let columns = parse_argument(words.next())?;
+            let rows = parse_argument(words.next())?;
+            if !(1..=MAX_TILES).contains(&columns)
+                || !(1..=MAX_TILES).contains(&rows)
+                || x >= columns
+                || y >= rows
+            {
+                return Err(CommandError::InvalidArgument);
+            }
+            Ok(Command::Tile(Tile {
+                x,
+                y,
+                columns,
+                rows,
+            }))
+        }
+        "key" => match argument.ok_or(CommandError::MissingArgument)? {
+            "off" => Ok(Command::Key(None)),
+            key => parse_key(key)
+                .map(|key| Command::Key(Some(key)))
+                .ok_or(CommandError::InvalidArgument),
+        },
+        "sync" => match argument.ok_or(CommandError::MissingArgument)? {
+            "off" => Ok(Command::Sync(None)),
+            number => {
+                let number = number
+                    .parse::<u8>()
+                    .map_err(|_| CommandError::InvalidArgument)?;
+                SyncPin::ALL
+                    .iter()
+                    .find(|pin| pin.number() == number && !pin.is_claimed())
+                    .map(|&pin| Command::Sync(Some(pin)))
+                    .ok_or(CommandError::InvalidArgument)
+            }
+        },
+        "save" => Ok(Command::Save),
+        "rewind" => Ok(Command::Rewind(parse_argument(argument)?)),
+        "rle" => Ok(Command::Rle),
+        "log" => Ok(Command::Log),
+        "rule" => {
+            let argument = argument.ok_or(CommandError::MissingArgument)?;
+            RULES
+                .iter()
+                .position(|rule| {
+                    rule.rulestring.eq_ignore_ascii_case(argument)
+                        || rule.name.eq_ignore_ascii_case(argument)
+                })
+                .map(Command::Rule)
+                .ok_or(CommandError::InvalidArgument)
+        }
+        _ => Err(CommandError::Unknown),
+    }
+}
+
+// Parse a key of 16 bytes written as 32 hexadecimal digits.
+fn parse_key(digits: &str) -> Option<[u8; 16]> {
+    if digits.len() != 32 || !digits.is_ascii() {
+        return None;
+    }
+    let mut key = [0; 16];
+    for (byte, pair) in key.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(key)
+}
+
+fn parse_argument<T: core::str::FromStr>(argument: Option<&str>) -> Result<T, CommandError> {
+    argument
+        .ok_or(CommandError::MissingArgument)?
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument)
+}
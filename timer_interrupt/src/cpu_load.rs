@@ -0,0 +1,65 @@
+use crate::timing::{ms_to_ticks, DISPLAY_TICK_HZ};
+use cortex_m::peripheral::{DCB, DWT};
+
+// Number of CPU cycles in a display tick (the CPU runs at 64 MHz).
+const CYCLES_PER_TICK: u32 = 64_000_000 / DISPLAY_TICK_HZ;
+// Number of display ticks over which the load is measured (10 s). The cycle counter
+// wraps after 67 s, so it must be shorter.
+const WINDOW_TICKS: u32 = ms_to_ticks(10_000);
+
+// Measures the load of the CPU, the fraction of the time that it's awake. The cycle
+// counter of the data watchpoint and trace unit (DWT) only counts while the CPU runs,
+// not while it sleeps waiting for an interrupt, so the load is the number of cycles it
+// counts during a window divided by the number of cycles that the window lasts, which
+// is measured with the display ticks. While a debugger is connected, the CPU may not
+// really sleep, so the load reads higher.
+// The cycle counter needs the trace to be enabled, which keeps the debug power domain
+// on and raises the current drawn, so it's only enabled while the load is measured.
+pub struct CpuLoad {
+    dcb: DCB,
+    dwt: DWT,
+    // The display tick and the value of the cycle counter at the start of the window,
+    // or None if the load isn't being measured:
+    start: Option<(u32, u32)>,
+}
+
+impl CpuLoad {
+    pub fn new(dcb: DCB, dwt: DWT) -> Self {
+        Self {
+            dcb,
+            dwt,
+            start: None,
+        }
+    }
+
+    // Start or stop measuring the load, given the current display tick. Starting it
+    // enables the trace and the cycle counter, and stopping it disables them.
+    pub fn set_enabled(&mut self, enabled: bool, ticks: u32) {
+        if enabled == self.start.is_some() {
+            return;
+        }
+        if enabled {
+            self.dcb.enable_trace();
+            self.dwt.enable_cycle_counter();
+            self.start = Some((ticks, DWT::cycle_count()));
+        } else {
+            self.dwt.disable_cycle_counter();
+            self.dcb.disable_trace();
+            self.start = None;
+        }
+    }
+
+    // Called with the current display tick. Once the window is over, returns the load
+    // measured during it, in hundredths of a percent, and starts the next window.
+    pub fn measure(&mut self, ticks: u32) -> Option<u32> {
+        let (start_tick, start_cycles) = self.start?;
+        let elapsed = ticks.wrapping_sub(start_tick);
+        if elapsed < WINDOW_TICKS {
+            return None;
+        }
+        let cycles = DWT::cycle_count();
+        let busy = cycles.wrapping_sub(start_cycles) as u64;
+        self.start = Some((ticks, cycles));
+        Some((busy * 10_000 / (elapsed as u64 * CYCLES_PER_TICK as u64)) as u32)
+    }
+}
@@ -0,0 +1,50 @@
+// Filters out the bounces of a mechanical button. When a button is pressed or released
+// its contacts bounce for a few milliseconds, so reading it in that time can give both
+// values, and each bounce would look like a new press.
+// This is an integrator debouncer: a counter goes up with every sample in which the
+// button reads as pressed and down with every sample in which it reads as released. The
+// button is only considered pressed once the counter reaches its maximum and released
+// once it reaches 0, so a single noisy sample can't change the state of the button.
+pub struct Debouncer {
+    // Value of the counter at which the button is considered pressed, i.e., the number
+    // of samples it takes for a change to be accepted:
+    samples: u8,
+    integrator: u8,
+    pressed: bool,
+}
+
+impl Debouncer {
+    // Create a debouncer that accepts a change after the given number of consecutive
+    // samples with the new value. The debounce time is that number of samples times the
+    // time between samples.
+    pub const fn new(samples: u8) -> Self {
+        Self {
+            samples,
+            integrator: 0,
+            pressed: false,
+        }
+    }
+
+    // Update the debouncer with a new sample of the button and return the debounced
+    // state.
+    pub fn update(&mut self, sample_pressed: bool) -> bool {
+        if sample_pressed {
+            self.integrator = (self.integrator + 1).min(self.samples);
+        } else {
+            self.integrator = self.integrator.saturating_sub(1);
+        }
+
+        if self.integrator == self.samples {
+            self.pressed = true;
+        } else if self.integrator == 0 {
+            self.pressed = false;
+        }
+        self.pressed
+    }
+
+    // Returns true if the last samples all agree with the debounced state, i.e., the
+    // button is not bouncing.
+    pub fn is_settled(&self) -> bool {
+        self.integrator == 0 || self.integrator == self.samples
+    }
+}
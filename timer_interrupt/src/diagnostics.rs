@@ -0,0 +1,87 @@
+use crate::text::ScrollingText;
+use crate::timing::{BUTTON_PRESCALER, LFCLK_HZ};
+use core::fmt::Write;
+
+// Number of button polls over which the rates are measured (approximately 5 seconds).
+const WINDOW_POLLS: u32 = 5 * LFCLK_HZ / (BUTTON_PRESCALER + 1);
+
+// Measures the rate at which the game advances and the rate at which the display is
+// updated, so that it can be checked that the prescaler and compare values of the
+// real time counters produce the intended timing. The button polls are used as the
+// time reference: the generations and display ticks are counted during WINDOW_POLLS
+// polls, and the rates are computed at the end of each window.
+// The last measured rates are shown as a scrolling text, for example
+// "GEN/S 1.00 FPS 32.00".
+pub struct Diagnostics {
+    polls: u32,
+    generations: u32,
+    display_ticks: u32,
+    // Rates measured in the last window, in hundredths of events per second:
+    generation_rate: u32,
+    display_rate: u32,
+    text: Option<ScrollingText>,
+}
+
+impl Diagnostics {
+    pub const fn new() -> Self {
+        Self {
+            polls: 0,
+            generations: 0,
+            display_ticks: 0,
+            generation_rate: 0,
+            display_rate: 0,
+            text: None,
+        }
+    }
+
+    // Must be called every time the buttons are polled.
+    pub fn count_poll(&mut self) {
+        self.polls += 1;
+        if self.polls == WINDOW_POLLS {
+            self.generation_rate = self.rate(self.generations);
+            self.display_rate = self.rate(self.display_ticks);
+            self.polls = 0;
+            self.generations = 0;
+            self.display_ticks = 0;
+        }
+    }
+
+    // Must be called every time the game advances to the next generation.
+    pub fn count_generation(&mut self) {
+        self.generations += 1;
+    }
+
+    // Must be called every display tick.
+    pub fn count_display_tick(&mut self) {
+        self.display_ticks += 1;
+    }
+
+    // Returns the next frame of the text with the measured rates. Once the text has
+    // been completely shown, it starts again with the latest measurements.
+    pub fn next_frame(&mut self) -> [[u8; 5]; 5] {
+        loop {
+            if let Some(frame) = self.text.as_mut().and_then(|text| text.next()) {
+                return frame;
+            }
+
+            let mut text = ScrollingText::empty();
+            write!(
+                text,
+                "GEN/S {}.{:02} FPS {}.{:02}",
+                self.generation_rate / 100,
+                self.generation_rate % 100,
+                self.display_rate / 100,
+                self.display_rate % 100
+            )
+            .ok();
+            self.text = Some(text);
+        }
+    }
+
+    // Number of events per second, in hundredths, given the number of events counted
+    // during the last window. 64 bit arithmetic is used to avoid overflows.
+    fn rate(&self, count: u32) -> u32 {
+        let window_lfclk_ticks = self.polls as u64 * (BUTTON_PRESCALER as u64 + 1);
+        (count as u64 * 100 * LFCLK_HZ as u64 / window_lfclk_ticks) as u32
+    }
+}
@@ -0,0 +1,62 @@
+use microbit::pac::{P0, P1, PWM0, PWM1, PWM2};
+
+// Pins of port 0 that the rows of the LED display are connected to. A LED is lit while
+// its row is high and its column is low.
+const ROW_PINS: [usize; 5] = [21, 22, 15, 24, 19];
+// Pins that the columns are connected to, as the port (0 or 1) and the pin of the port.
+const COLUMN_PINS: [(usize, usize); 5] = [(0, 28), (0, 11), (0, 31), (1, 5), (0, 30)];
+// Number of CPU cycles that each row is lit for (2 ms at 64 MHz), so a whole image is
+// shown 100 times per second.
+const ROW_CYCLES: u32 = 128_000;
+// Number of times that an image is shown per second.
+pub const SCANS_PER_SECOND: u32 = 100;
+
+pub const CROSS: [[bool; 5]; 5] = [
+    [true, false, false, false, true],
+    [false, true, false, true, false],
+    [false, false, true, false, false],
+    [false, true, false, true, false],
+    [true, false, false, false, true],
+];
+
+// Take over the pins of the LED display, to drive it directly through the registers of
+// the ports, without timers nor interrupts. This is used when the display driver can't
+// be: when the firmware has crashed, and before the driver is started. The PWM
+// peripherals are stopped first, since they drive the columns with the PWM display, and
+// the speaker, which would otherwise keep playing its last note.
+pub fn take_over() {
+    unsafe {
+        (*PWM0::ptr()).enable.reset();
+        (*PWM1::ptr()).enable.reset();
+        (*PWM2::ptr()).enable.reset();
+    }
+    let ports = unsafe { [&*P0::ptr(), &*P1::ptr()] };
+    for &row in ROW_PINS.iter() {
+        ports[0].outclr.write(|w| unsafe { w.bits(1 << row) });
+        ports[0].pin_cnf[row].write(|w| w.dir().output());
+    }
+    for &(port, pin) in COLUMN_PINS.iter() {
+        ports[port].outset.write(|w| unsafe { w.bits(1 << pin) });
+        ports[port].pin_cnf[pin].write(|w| w.dir().output());
+    }
+}
+
+// Multiplex an image on the display, one row at a time, the given number of times. The
+// CPU is busy until it's done, and the display is left dark.
+pub fn show(image: [[bool; 5]; 5], scans: u32) {
+    let ports = unsafe { [&*P0::ptr(), &*P1::ptr()] };
+    for _ in 0..scans {
+        for (&row, cells) in ROW_PINS.iter().zip(image.iter()) {
+            for (&(port, pin), &lit) in COLUMN_PINS.iter().zip(cells.iter()) {
+                if lit {
+                    ports[port].outclr.write(|w| unsafe { w.bits(1 << pin) });
+                } else {
+                    ports[port].outset.write(|w| unsafe { w.bits(1 << pin) });
+                }
+            }
+            ports[0].outset.write(|w| unsafe { w.bits(1 << row) });
+            cortex_m::asm::delay(ROW_CYCLES);
+            ports[0].outclr.write(|w| unsafe { w.bits(1 << row) });
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::game_of_life::LifeState;
+use crate::patterns::PATTERNS;
+use crate::seed::Seeder;
+use crate::timing::ms_to_ticks;
+
+// Number of display ticks between two changes of the cursor (250 ms).
+const CURSOR_BLINK_TICKS: u32 = ms_to_ticks(250);
+
+// On-device pattern editor. A cursor blinks over one of the cells of the grid, it can
+// be moved around and a brush can be applied at its position, so that custom initial
+// states can be drawn without having to reflash the board. The brush either toggles
+// the cell under the cursor, stamps one of the patterns of the library with its top
+// left corner at the cursor or fills the whole grid with a random soup.
+pub struct Editor {
+    row: usize,
+    col: usize,
+    // Selected brush.
+    brush: Brush,
+    // Number of display ticks since the editor was opened, used to blink the cursor.
+    ticks: u32,
+}
+
+// The brushes that the editor can apply.
+#[derive(Clone, Copy)]
+enum Brush {
+    // Toggle a single cell.
+    Cell,
+    // Stamp the pattern of the library with the given index.
+    Pattern(usize),
+    // Fill the whole grid with a random soup.
+    Soup,
+}
+
+impl Editor {
+    pub const fn new() -> Self {
+        Self {
+            row: 0,
+            col: 0,
+            brush: Brush::Cell,
+            ticks: 0,
+        }
+    }
+
+    // Move the cursor one cell to the right. After the last cell of a row, the cursor
+    // moves to the first cell of the next row, and after the last cell of the grid it
+    // goes back to the first one.
+    pub fn move_right(&mut self) {
+        self.col += 1;
+        if self.col == 5 {
+            self.col = 0;
+            self.row = (self.row + 1) % 5;
+        }
+    }
+
+    // Select the next brush: the single cell brush is followed by the patterns of the
+    // library, then by the random soup, and after it goes back to the single cell. It
+    // returns the name of the selected brush, so it can be shown on the display.
+    pub fn next_brush(&mut self) -> &'static str {
+        self.brush = match self.brush {
+            Brush::Cell => Brush::Pattern(0),
+            Brush::Pattern(index) if index + 1 < PATTERNS.len() => Brush::Pattern(index + 1),
+            Brush::Pattern(_) => Brush::Soup,
+            Brush::Soup => Brush::Cell,
+        };
+        match self.brush {
+            Brush::Cell => "CELL",
+            Brush::Pattern(index) => PATTERNS[index].name,
+            Brush::Soup => "SOUP",
+        }
+    }
+
+    // Apply the brush at the position of the cursor: either toggle the cell (a live
+    // cell dies and a dead cell is born), stamp the selected pattern or replace the
+    // grid with a soup from the seeder.
+    pub fn apply_brush(&self, state: &mut LifeState, seeder: &mut Seeder) {
+        match self.brush {
+            Brush::Cell => state.matrix[self.row][self.col] = !state.matrix[self.row][self.col],
+            Brush::Pattern(index) => state.stamp(&PATTERNS[index], self.row, self.col),
+            Brush::Soup => state.matrix = seeder.random_soup(),
+        }
+    }
+
+    // Compute the brightness of the image of the grid being edited. It must be called
+    // every display tick, since it also takes care of blinking the cursor: the LED
+    // under the cursor is inverted every CURSOR_BLINK_TICKS ticks.
+    pub fn brightness_matrix(&mut self, matrix: [[bool; 5]; 5]) -> [[u8; 5]; 5] {
+        self.ticks = self.ticks.wrapping_add(1);
+
+        let mut brightness = matrix.map(|row| row.map(|alive| if alive { 9 } else { 0 }));
+        if (self.ticks / CURSOR_BLINK_TICKS) % 2 == 1 {
+            // Dead cells under the cursor are shown dimmed so that the cursor can be
+            // told apart from a live cell.
+            brightness[self.row][self.col] = if matrix[self.row][self.col] { 0 } else { 4 };
+        }
+        brightness
+    }
+}
@@ -0,0 +1,114 @@
+use crate::button::ButtonEvent;
+use crate::input::InputEvent;
+use crate::telemetry::Event;
+use crate::time;
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+use cortex_m::interrupt::{CriticalSection, Mutex};
+
+// Number of events remembered. Once the log is full, every new event replaces the
+// oldest one.
+const LOG_SIZE: usize = 32;
+
+// Events recorded in the log.
+#[derive(Clone, Copy)]
+pub enum LogEvent {
+    // An input event, before it's handled.
+    Input(InputEvent),
+    // A change of the state of the game: paused, resumed, or a new rule or speed.
+    Game(Event),
+    // The grid has been replaced with a random soup.
+    Reseed,
+}
+
+// An event and the uptime at which it was recorded, in milliseconds.
+#[derive(Clone, Copy)]
+struct Entry {
+    uptime_ms: u64,
+    event: LogEvent,
+}
+
+// Ring buffer with the last events, to reconstruct what happened before a crash. It's
+// kept in RAM, so it's lost with a reset, but it's printed by the panic handler and the
+// hard fault handler before that, and by the log command of the shell on demand.
+struct EventLog {
+    entries: [Option<Entry>; LOG_SIZE],
+    // Position in which the next event will be stored, which is also the position of the
+    // oldest one once the buffer is full:
+    next: usize,
+}
+
+static LOG: Mutex<RefCell<EventLog>> = Mutex::new(RefCell::new(EventLog {
+    entries: [None; LOG_SIZE],
+    next: 0,
+}));
+
+// Record an event, stamped with the current uptime.
+pub fn record(cs: &CriticalSection, event: LogEvent) {
+    let uptime_ms = time::now_ms(cs);
+    let mut log = LOG.borrow(cs).borrow_mut();
+    let next = log.next;
+    log.entries[next] = Some(Entry { uptime_ms, event });
+    log.next = (next + 1) % LOG_SIZE;
+}
+
+// Write the events of the log as text, from the oldest to the newest, a line per event
+// with its uptime in seconds. If the log is being recorded, which can only happen when
+// a panic interrupts the recording, nothing is written.
+pub fn write_log(out: &mut impl Write) -> fmt::Result {
+    let log = cortex_m::interrupt::free(|cs| {
+        let log = LOG.borrow(cs).try_borrow().ok()?;
+        Some((log.entries, log.next))
+    });
+    let Some((entries, next)) = log else {
+        return Ok(());
+    };
+    let oldest_first = entries[next..].iter().chain(entries[..next].iter());
+    for entry in oldest_first.flatten() {
+        write!(
+            out,
+            "{:>6}.{:03} ",
+            entry.uptime_ms / 1000,
+            entry.uptime_ms % 1000
+        )?;
+        write_event(out, entry.event)?;
+        out.write_str("\r\n")?;
+    }
+    Ok(())
+}
+
+fn write_event(out: &mut impl Write, event: LogEvent) -> fmt::Result {
+    match event {
+        LogEvent::Input(InputEvent::ButtonA(event)) => write!(out, "button a {}", name(event)),
+        LogEvent::Input(InputEvent::ButtonB(event)) => write!(out, "button b {}", name(event)),
+        LogEvent::Input(InputEvent::Chord) => write!(out, "chord"),
+        LogEvent::Input(InputEvent::ChordHold) => write!(out, "chord hold"),
+        LogEvent::Input(InputEvent::ChordLongHold) => write!(out, "chord long hold"),
+        LogEvent::Input(InputEvent::Touch(event)) => write!(out, "touch {}", name(event)),
+        LogEvent::Input(InputEvent::Shake(noise)) => write!(out, "shake {}", noise),
+        LogEvent::Input(InputEvent::Clap(level)) => write!(out, "clap {}", level),
+        LogEvent::Input(InputEvent::Tap) => write!(out, "tap"),
+        LogEvent::Input(InputEvent::FreeFall) => write!(out, "free fall"),
+        LogEvent::Input(InputEvent::Flip) => write!(out, "flip"),
+        #[cfg(feature = "ring-buttons")]
+        LogEvent::Input(InputEvent::Ring(ring_n, event)) => {
+            write!(out, "ring {} {}", ring_n, name(event))
+        }
+        #[cfg(feature = "trigger")]
+        LogEvent::Input(InputEvent::Trigger) => write!(out, "trigger"),
+        LogEvent::Game(Event::Paused) => write!(out, "paused"),
+        LogEvent::Game(Event::Resumed) => write!(out, "resumed"),
+        LogEvent::Game(Event::Rule(rule_n)) => write!(out, "rule {}", rule_n),
+        LogEvent::Game(Event::Speed(speed)) => write!(out, "speed {}", speed),
+        LogEvent::Reseed => write!(out, "reseed"),
+    }
+}
+
+fn name(event: ButtonEvent) -> &'static str {
+    match event {
+        ButtonEvent::ShortPress => "short press",
+        ButtonEvent::LongPress => "long press",
+        ButtonEvent::Repeat => "repeat",
+        ButtonEvent::DoubleClick => "double click",
+    }
+}
@@ -0,0 +1,71 @@
+use crate::timing::ms_to_ticks;
+
+// Duration of the transition from one generation to the next, in milliseconds.
+const FADE_DURATION_MS: u32 = 500;
+// Number of images used to go from one generation to the next, one per display tick.
+// The last one is the image of the new generation, the previous ones are a mix of the
+// old and the new.
+const FADE_STEPS: u8 = ms_to_ticks(FADE_DURATION_MS) as u8;
+
+// Smooth transition between the images of two consecutive generations. Instead of
+// snapping from one image to the next, the brightness of each LED is interpolated
+// between its old and its new value over a few display updates, producing a morphing
+// animation.
+pub struct Fade {
+    // Brightness of the image shown when the transition started:
+    from: [[u8; 5]; 5],
+    // Brightness of the image shown when the transition is finished:
+    to: [[u8; 5]; 5],
+    // Number of steps of the transition that have already been shown:
+    step: u8,
+}
+
+impl Fade {
+    pub const fn new() -> Self {
+        Self {
+            from: [[0; 5]; 5],
+            to: [[0; 5]; 5],
+            step: FADE_STEPS,
+        }
+    }
+
+    // Start a new transition from the image that is currently being shown to the
+    // target image. If the previous transition has not finished yet, the new one starts
+    // from wherever the previous one was.
+    pub fn start(&mut self, target: [[u8; 5]; 5]) {
+        self.from = self.current_matrix();
+        self.to = target;
+        self.step = 0;
+    }
+
+    // Start a transition to the same target image, so that it's drawn again. This is
+    // needed after something else has been shown on the display.
+    pub fn refresh(&mut self) {
+        self.start(self.to);
+    }
+
+    // Advance the transition one step and return the brightness of the image that must
+    // be shown. Once the transition is finished it returns None, since the display
+    // already shows the target image.
+    pub fn next_matrix(&mut self) -> Option<[[u8; 5]; 5]> {
+        if self.step >= FADE_STEPS {
+            return None;
+        }
+        self.step += 1;
+        Some(self.current_matrix())
+    }
+
+    // Brightness of the image that corresponds to the current step of the transition.
+    // It's computed as a linear interpolation between the two images.
+    pub fn current_matrix(&self) -> [[u8; 5]; 5] {
+        let mut matrix = [[0; 5]; 5];
+        for (row_n, row) in matrix.iter_mut().enumerate() {
+            for (col_n, element) in row.iter_mut().enumerate() {
+                let from = self.from[row_n][col_n] as i16;
+                let to = self.to[row_n][col_n] as i16;
+                *element = (from + (to - from) * self.step as i16 / FADE_STEPS as i16) as u8;
+            }
+        }
+        matrix
+    }
+}
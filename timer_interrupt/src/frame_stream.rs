@@ -0,0 +1,35 @@
+use crate::shell::write_grid;
+use core::fmt::Write;
+use rtt_target::UpChannel;
+
+// Stream of the generations of the game as text, sent through an RTT channel of its
+// own, so they can be watched and recorded on the host while the LEDs show them, e.g.,
+// with the terminal of probe-rs or cargo-embed. Every frame is a line with the number
+// of the generation followed by a line per row of the grid. The channel doesn't block
+// when the host doesn't read it, so the frames are lost instead of stopping the game.
+pub struct FrameStream {
+    channel: UpChannel,
+    enabled: bool,
+}
+
+impl FrameStream {
+    pub fn new(channel: UpChannel) -> Self {
+        Self {
+            channel,
+            enabled: false,
+        }
+    }
+
+    // Start or stop sending the frames.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    // Send the frame of a generation, if the stream is enabled.
+    pub fn send(&mut self, generation: u32, matrix: [[bool; 5]; 5]) {
+        if self.enabled {
+            write!(self.channel, "Generation {}\r\n", generation).ok();
+            write_grid(&mut self.channel, matrix).ok();
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use microbit::{
+    display::nonblocking::{BitImage, Display, GreyscaleImage},
+    hal::timer::Instance,
+};
+use tiny_led_matrix::Render;
+
+// LED matrices that can show the frames of the frame buffer. Each frame is given as the
+// brightness of each LED, from 0 (off) to 9 (maximum brightness).
+pub trait LedMatrix {
+    fn show_brightness(&mut self, brightness: &[[u8; 5]; 5]);
+}
+
+impl<T: Instance> LedMatrix for Display<T> {
+    fn show_brightness(&mut self, brightness: &[[u8; 5]; 5]) {
+        self.show(&GreyscaleImage::new(brightness));
+    }
+}
+
+// Two LED matrices that show the same frames, e.g., the on-board display mirrored on an
+// external matrix.
+impl<A: LedMatrix, B: LedMatrix> LedMatrix for (&mut A, &mut B) {
+    fn show_brightness(&mut self, brightness: &[[u8; 5]; 5]) {
+        self.0.show_brightness(brightness);
+        self.1.show_brightness(brightness);
+    }
+}
+
+// Clockwise rotation applied to the images before showing them, so that the board can
+// be mounted in any orientation.
+#[derive(Clone, Copy)]
+pub enum Rotation {
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl Rotation {
+    // Returns the position (row, column) of the image that must be shown on the LED at
+    // the given row and column of the display.
+    fn source(self, row: usize, col: usize) -> (usize, usize) {
+        match self {
+            Rotation::Deg0 => (row, col),
+            Rotation::Deg90 => (4 - col, row),
+            Rotation::Deg180 => (4 - row, 4 - col),
+            Rotation::Deg270 => (col, 4 - row),
+        }
+    }
+
+    // Rotate a matrix with the values of each LED.
+    #[cfg_attr(not(feature = "ws2812"), allow(dead_code))]
+    pub fn rotate<T: Copy>(self, matrix: [[T; 5]; 5]) -> [[T; 5]; 5] {
+        let mut rotated = matrix;
+        for (row_n, row) in rotated.iter_mut().enumerate() {
+            for (col_n, value) in row.iter_mut().enumerate() {
+                let (source_row, source_col) = self.source(row_n, col_n);
+                *value = matrix[source_row][source_col];
+            }
+        }
+        rotated
+    }
+}
+
+// Double buffer of display frames. New images are always drawn into the back buffer,
+// which the display never reads, and once an image is complete the buffers are swapped
+// by flipping the index of the front buffer. The display interrupt then hands the front
+// buffer to the display, so it always gets a complete frame, no matter when the image
+// was drawn.
+pub struct FrameBuffer {
+    frames: [[[u8; 5]; 5]; 2],
+    // Index of the frame that the display must show:
+    front: usize,
+    // Flag set when the buffers have been swapped and the display has not received the
+    // new front buffer yet:
+    swapped: bool,
+    // Rotation applied to every image drawn:
+    rotation: Rotation,
+    // Brightness the display shows for a brightness of 9 in the frames. The rest of
+    // brightness values are scaled in proportion:
+    max_brightness: u8,
+}
+
+impl FrameBuffer {
+    pub const fn new(rotation: Rotation) -> Self {
+        Self {
+            frames: [[[0; 5]; 5]; 2],
+            front: 0,
+            swapped: false,
+            rotation,
+            max_brightness: 9,
+        }
+    }
+
+    // Change the rotation applied to the images. It only affects the images drawn after
+    // the change.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    // Rotate a matrix with the values of each LED, like the images are rotated when
+    // they are drawn.
+    #[cfg_attr(not(feature = "ws2812"), allow(dead_code))]
+    pub fn rotate<T: Copy>(&self, matrix: [[T; 5]; 5]) -> [[T; 5]; 5] {
+        self.rotation.rotate(matrix)
+    }
+
+    // Draw an image given as the brightness of each LED, from 0 (off) to 9 (maximum
+    // brightness).
+    pub fn draw_brightness(&mut self, brightness: &[[u8; 5]; 5]) {
+        self.draw(&GreyscaleImage::new(brightness));
+    }
+
+    // Draw an image in which every LED is either off (0) or on (1), like the frames of
+    // the scrolling text.
+    pub fn draw_bits(&mut self, bits: &[[u8; 5]; 5]) {
+        self.draw(&BitImage::new(bits));
+    }
+
+    // Draw an image into the back buffer, rotated as configured, and swap the buffers.
+    fn draw<R: Render>(&mut self, image: &R) {
+        let back = 1 - self.front;
+        for (row_n, row) in self.frames[back].iter_mut().enumerate() {
+            for (col_n, brightness) in row.iter_mut().enumerate() {
+                let (source_row, source_col) = self.rotation.source(row_n, col_n);
+                *brightness = image.brightness_at(source_col, source_row);
+            }
+        }
+        self.front = back;
+        self.swapped = true;
+    }
+
+    // Returns a copy of the front buffer, i.e., the frame that the display is showing.
+    #[cfg_attr(not(any(feature = "ssd1306", feature = "ws2812")), allow(dead_code))]
+    pub fn front_frame(&self) -> [[u8; 5]; 5] {
+        self.frames[self.front]
+    }
+
+    // Returns the maximum brightness of the display, from 1 to 9.
+    pub fn max_brightness(&self) -> u8 {
+        self.max_brightness
+    }
+
+    // Set the maximum brightness of the display, from 1 to 9. The front buffer is sent
+    // to the display again so the change is visible right away.
+    pub fn set_max_brightness(&mut self, max_brightness: u8) {
+        let max_brightness = max_brightness.clamp(1, 9);
+        if max_brightness != self.max_brightness {
+            self.max_brightness = max_brightness;
+            self.swapped = true;
+        }
+    }
+
+    // Give the front buffer to the display if the buffers have been swapped since the
+    // last call. It's meant to be called from the display interrupt.
+    pub fn update_display<D: LedMatrix>(&mut self, display: &mut D) {
+        if self.swapped {
+            let mut frame = self.frames[self.front];
+            for brightness in frame.iter_mut().flatten() {
+                // Scale the brightness, keeping the LEDs that are on at least at 1 so
+                // that dim trails don't disappear in the dark.
+                if *brightness > 0 {
+                    *brightness = (*brightness * self.max_brightness / 9).max(1);
+                }
+            }
+            display.show_brightness(&frame);
+            self.swapped = false;
+        }
+    }
+}
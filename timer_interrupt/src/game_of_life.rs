@@ -1,47 +1,151 @@
+use crate::patterns::Pattern;
+use crate::rules::Rule;
+
+#[derive(Clone, Copy)]
 pub struct LifeState {
     pub matrix: [[bool; 5]; 5],
 }
 
+// Number of cells that have changed in a generation.
+#[derive(Clone, Copy)]
+pub struct Changes {
+    pub births: u8,
+    pub deaths: u8,
+}
+
+impl Changes {
+    // Count the cells that have changed from one grid to another.
+    pub fn between(previous: [[bool; 5]; 5], next: [[bool; 5]; 5]) -> Self {
+        let mut changes = Changes {
+            births: 0,
+            deaths: 0,
+        };
+        for (was_alive, alive) in previous
+            .into_iter()
+            .flatten()
+            .zip(next.into_iter().flatten())
+        {
+            match (was_alive, alive) {
+                (false, true) => changes.births += 1,
+                (true, false) => changes.deaths += 1,
+                _ => {}
+            }
+        }
+        changes
+    }
+}
+
+// What happens at the edges of the grid.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    // The cells outside the grid are always dead.
+    Dead,
+    // The grid wraps around: the cells of the last column are the neighbors of the
+    // cells of the first column, and the same goes for the rows (a torus).
+    Wrap,
+    // The grid is a tile of a world shared with other boards. The cells around it are
+    // the edges of the grids of the tiles around it.
+    Tiled(Border),
+}
+
+// The cells around a grid: the rows above and below it, the columns to its sides and the
+// four cells at its corners (top left, top right, bottom left and bottom right).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Border {
+    pub north: [bool; 5],
+    pub south: [bool; 5],
+    pub west: [bool; 5],
+    pub east: [bool; 5],
+    pub corners: [bool; 4],
+}
+
 impl LifeState {
-    pub fn next_state(&mut self) {
+    // Advance the game one generation and return the number of cells that have been
+    // born and that have died.
+    pub fn next_state(&mut self, rule: &Rule, boundary: Boundary) -> Changes {
         let mut next_state_matrix = [[false; 5]; 5];
+        let mut changes = Changes {
+            births: 0,
+            deaths: 0,
+        };
 
         for (row_n, row) in self.matrix.into_iter().enumerate() {
             for (col_n, element) in row.into_iter().enumerate() {
-                let n_neighbors = count_live_neighbors(self.matrix, row_n, col_n);
-
-                next_state_matrix[row_n][col_n] = match (element, n_neighbors) {
-                    // Cell alive with 2 or 3 neighbors:
-                    (true, 2 | 3) => true,
-                    // Cell dead with 3 neighbors:
-                    (false, 3) => true,
-                    // Any other case:
-                    _ => false,
-                };
+                let n_neighbors = count_live_neighbors(self.matrix, row_n, col_n, boundary);
+                let next_element = rule.next_cell(element, n_neighbors);
+                match (element, next_element) {
+                    (false, true) => changes.births += 1,
+                    (true, false) => changes.deaths += 1,
+                    _ => {}
+                }
+                next_state_matrix[row_n][col_n] = next_element;
             }
         }
         self.matrix = next_state_matrix;
+        changes
+    }
+
+    // Number of live cells.
+    pub fn population(&self) -> u8 {
+        self.matrix.iter().flatten().filter(|&&alive| alive).count() as u8
     }
-    pub fn int_matrix(&self) -> [[u8; 5]; 5] {
-        // To display the matrix using the LEDs, it must be converted to u8.
-        self.matrix.map(|row| row.map(|element| element as u8))
+
+    // Combine a pattern with the current state, placing the top left corner of the
+    // pattern at the given row and column. The live cells of the pattern are added to
+    // the ones already alive (a boolean OR), and the cells of the pattern that fall
+    // outside the grid are discarded.
+    pub fn stamp(&mut self, pattern: &Pattern, row: usize, col: usize) {
+        for (pattern_row, pattern_col) in pattern.cells.iter() {
+            if let Some(cell) = self
+                .matrix
+                .get_mut(row + pattern_row)
+                .and_then(|matrix_row| matrix_row.get_mut(col + pattern_col))
+            {
+                *cell = true;
+            }
+        }
     }
 }
 
-fn count_live_neighbors(matrix: [[bool; 5]; 5], target_row: usize, target_col: usize) -> u8 {
+fn count_live_neighbors(
+    matrix: [[bool; 5]; 5],
+    target_row: usize,
+    target_col: usize,
+    boundary: Boundary,
+) -> u8 {
     // Compute the number of live neighbors that the element row, column of the matrix
     // matrix has. Live neighbor are the ones set to true.
 
     // To avoid having to deal with the special cases of the edges of the matrix, a new
-    // the 5x5 matrix passed to the function is padded with false values to generate
-    // a new 7x7 matrix. We can then operate on this new matrix knowing that the element
-    // to study is never going to be on the edge.
+    // the 5x5 matrix passed to the function is padded to generate a new 7x7 matrix. We
+    // can then operate on this new matrix knowing that the element to study is never
+    // going to be on the edge. With dead boundaries the padding is false, and with
+    // wrapping boundaries it's a copy of the cells on the opposite edge. A tiled grid is
+    // padded with its border.
 
     let mut padded_matrix: [[bool; 7]; 7] = [[false; 7]; 7];
 
-    for (row_n, row) in matrix.into_iter().enumerate() {
-        for (col_n, element) in row.into_iter().enumerate() {
-            padded_matrix[row_n + 1][col_n + 1] = element;
+    for (row_n, row) in padded_matrix.iter_mut().enumerate() {
+        for (col_n, element) in row.iter_mut().enumerate() {
+            *element = match boundary {
+                Boundary::Dead => {
+                    (1..=5).contains(&row_n)
+                        && (1..=5).contains(&col_n)
+                        && matrix[row_n - 1][col_n - 1]
+                }
+                Boundary::Wrap => matrix[(row_n + 4) % 5][(col_n + 4) % 5],
+                Boundary::Tiled(border) => match (row_n, col_n) {
+                    (0, 0) => border.corners[0],
+                    (0, 6) => border.corners[1],
+                    (6, 0) => border.corners[2],
+                    (6, 6) => border.corners[3],
+                    (0, _) => border.north[col_n - 1],
+                    (6, _) => border.south[col_n - 1],
+                    (_, 0) => border.west[row_n - 1],
+                    (_, 6) => border.east[row_n - 1],
+                    _ => matrix[row_n - 1][col_n - 1],
+                },
+            };
         }
     }
 
@@ -0,0 +1,49 @@
+use crate::game_of_life::LifeState;
+
+// The run of the game shared by the interrupts and the main loop: the grid, whether it's
+// paused and how far it has gone since it started. They are kept together in a single
+// global, since most handlers use several of them at once, so they only need one borrow
+// of it.
+pub struct GameRun {
+    // The grid of the current generation:
+    pub state: LifeState,
+    // Flag to keep track of whether or not the game is paused:
+    pub paused: bool,
+    // Number of generations since the start of the current run:
+    pub generation: u32,
+    // Grid with which the current run started, the initial pattern or the last random
+    // soup. Flipping the board restarts the game from it:
+    pub run_start: [[bool; 5]; 5],
+}
+
+impl GameRun {
+    pub const fn new(matrix: [[bool; 5]; 5]) -> Self {
+        Self {
+            state: LifeState { matrix },
+            paused: false,
+            generation: 0,
+            run_start: matrix,
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // Count a new generation and return its number.
+    pub fn next_generation(&mut self) -> u32 {
+        self.generation = self.generation.wrapping_add(1);
+        self.generation
+    }
+
+    // Start a new run from the current grid, after it has been replaced.
+    pub fn start_run(&mut self) {
+        self.run_start = self.state.matrix;
+        self.generation = 0;
+    }
+
+    // Go back to the grid with which the current run started.
+    pub fn restart_run(&mut self) {
+        self.state.matrix = self.run_start;
+    }
+}
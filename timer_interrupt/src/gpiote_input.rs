@@ -0,0 +1,135 @@
+use crate::button_pair::ButtonPair;
+use crate::input::{EventProducer, InputEvent, InputSource};
+use crate::my_board::BoardButton;
+use crate::time;
+#[cfg(feature = "trigger")]
+use microbit::hal::gpio::PullDown;
+use microbit::{
+    hal::{
+        gpio::{Input, Pin, PullUp},
+        gpiote::Gpiote,
+    },
+    pac::GPIOTE,
+};
+
+// Inputs that generate interrupts when their pins change, through the GPIO tasks and
+// events module (GPIOTE). Each channel of the GPIOTE is assigned to one pin: channel 0
+// to the external trigger, channels 1 and 2 to the buttons of the board and channel 3 to
+// the interrupt pin of the accelerometer.
+// The interrupt of the buttons only records that their pins have changed. The pins are
+// then sampled by sample_buttons, from the interrupt that polls the rest of the inputs,
+// until they are released and settled. While they are sampled they go through the same
+// ButtonPair as the polled buttons, so they generate the same events, holds included,
+// but they aren't read at all between presses.
+pub struct GpioteInputs {
+    gpiote: Gpiote,
+    buttons: Option<GpioteButtons>,
+}
+
+// The buttons of the board, when they are read through the GPIOTE.
+struct GpioteButtons {
+    button_a: BoardButton,
+    button_b: BoardButton,
+    states: ButtonPair,
+    // Flag set when the pin of a button has changed, until the buttons are idle:
+    sampling: bool,
+}
+
+impl GpioteInputs {
+    pub fn new(gpiote: GPIOTE) -> Self {
+        Self {
+            gpiote: Gpiote::new(gpiote),
+            buttons: None,
+        }
+    }
+
+    // Generate an event on every rising edge of the trigger pin. The pin has a
+    // pull-down, so it stays low when nothing is connected.
+    #[cfg(feature = "trigger")]
+    pub fn with_trigger(self, trigger_pin: Pin<Input<PullDown>>) -> Self {
+        let channel0 = self.gpiote.channel0();
+        channel0
+            .input_pin(&trigger_pin)
+            .lo_to_hi()
+            .enable_interrupt();
+        channel0.reset_events();
+        self
+    }
+
+    // Generate an event when the accelerometer detects a tap, which pulls its interrupt
+    // pin low.
+    pub fn with_taps(self, interrupt_pin: Pin<Input<PullUp>>) -> Self {
+        let channel3 = self.gpiote.channel3();
+        channel3
+            .input_pin(&interrupt_pin)
+            .hi_to_lo()
+            .enable_interrupt();
+        channel3.reset_events();
+        self
+    }
+
+    // Read the buttons of the board through the GPIOTE. Both the presses and the
+    // releases generate an interrupt.
+    pub fn with_buttons(mut self, button_a: BoardButton, button_b: BoardButton) -> Self {
+        let channel1 = self.gpiote.channel1();
+        channel1
+            .input_pin(button_a.pin())
+            .toggle()
+            .enable_interrupt();
+        channel1.reset_events();
+        let channel2 = self.gpiote.channel2();
+        channel2
+            .input_pin(button_b.pin())
+            .toggle()
+            .enable_interrupt();
+        channel2.reset_events();
+        self.buttons = Some(GpioteButtons {
+            button_a,
+            button_b,
+            states: ButtonPair::new(),
+            sampling: false,
+        });
+        self
+    }
+
+    // Sample the buttons after their pins have changed, and push the events of their
+    // presses to the queue. It must be called periodically, every poll of the inputs,
+    // and it does nothing once the buttons are idle, until their pins change again.
+    pub fn sample_buttons(&mut self, events: &mut EventProducer) {
+        let Some(buttons) = self.buttons.as_mut().filter(|buttons| buttons.sampling) else {
+            return;
+        };
+        let now_ms = cortex_m::interrupt::free(time::now_ms);
+        let a_pressed = buttons.button_a.is_pressed();
+        let b_pressed = buttons.button_b.is_pressed();
+        buttons.states.update(a_pressed, b_pressed, now_ms, events);
+        buttons.sampling = !buttons.states.is_idle();
+    }
+}
+
+impl InputSource for GpioteInputs {
+    fn poll(&mut self, events: &mut EventProducer) {
+        #[cfg(feature = "trigger")]
+        if self.gpiote.channel0().is_event_triggered() {
+            self.gpiote.channel0().reset_events();
+            events.enqueue(InputEvent::Trigger).ok();
+        }
+        if self.gpiote.channel3().is_event_triggered() {
+            self.gpiote.channel3().reset_events();
+            events.enqueue(InputEvent::Tap).ok();
+        }
+
+        // Every press and release of a button raises several interrupts while its
+        // contacts bounce, so the pins are only read once they have been sampled for a
+        // while.
+        if let Some(buttons) = self.buttons.as_mut() {
+            if self.gpiote.channel1().is_event_triggered()
+                || self.gpiote.channel2().is_event_triggered()
+            {
+                self.gpiote.channel1().reset_events();
+                self.gpiote.channel2().reset_events();
+                buttons.sampling = true;
+            }
+        }
+    }
+}
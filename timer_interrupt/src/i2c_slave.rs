@@ -0,0 +1,218 @@
+use crate::command::Command;
+use crate::my_board::I2cPins;
+use crate::rules::RULES;
+use crate::timing::FASTEST_SPEED;
+use core::sync::atomic::{compiler_fence, Ordering};
+use microbit::hal::gpio::{Floating, Input, Pin, Port};
+use microbit::pac::{P0, P1, TWIS1};
+
+// I2C address of the board.
+const ADDRESS: u8 = 0x42;
+// Registers of the board. The grid is a byte per row, from top to bottom, in which the
+// bit n is the cell of the column n, and the numbers are little endian.
+const STATUS: usize = 0x00; // Bit 0: paused (read only).
+const GENERATION: usize = 0x01; // 4 bytes (read only).
+const GRID: usize = 0x05; // 5 bytes.
+const POPULATION: usize = 0x0a; // Read only.
+const SPEED: usize = 0x0b; // From 0 to 4.
+const RULE: usize = 0x0c; // Index of the rule.
+const CONTROL: usize = 0x0d; // Write only: 1 pause, 2 resume, 3 step, 4 random soup.
+const REGISTERS_LEN: usize = 0x0e;
+// Byte sent when the controller reads past the last register.
+const OVER_READ: u8 = 0xff;
+// Density of the random soup of the CONTROL register, in %.
+const SOUP_DENSITY: u8 = 50;
+
+// The state of the game shown by the registers.
+pub struct Registers {
+    pub paused: bool,
+    pub generation: u32,
+    pub matrix: [[bool; 5]; 5],
+    pub speed: u8,
+    pub rule: u8,
+}
+
+impl Registers {
+    fn bytes(&self) -> [u8; REGISTERS_LEN] {
+        let mut bytes = [0; REGISTERS_LEN];
+        bytes[STATUS] = self.paused as u8;
+        bytes[GENERATION..GRID].copy_from_slice(&self.generation.to_le_bytes());
+        for (byte, row) in bytes[GRID..POPULATION].iter_mut().zip(self.matrix) {
+            *byte = row
+                .into_iter()
+                .enumerate()
+                .fold(0, |bits, (col_n, alive)| bits | (alive as u8) << col_n);
+        }
+        bytes[POPULATION] = self.matrix.iter().flatten().filter(|&&alive| alive).count() as u8;
+        bytes[SPEED] = self.speed;
+        bytes[RULE] = self.rule;
+        bytes
+    }
+}
+
+// A change of the game written by the controller.
+pub enum I2cWrite {
+    Command(Command),
+    Grid([[bool; 5]; 5]),
+}
+
+// The board as an I2C peripheral (slave) on the I2C pins of the edge connector, so that
+// an external controller can use it as a Life coprocessor. Every transaction starts with
+// a write of the number of the register it's about, followed by the values written to
+// the registers from that one on, or by a read (after a repeated start) of the
+// registers from that one on. The transfers are driven by the interrupt of the TWIS:
+// the TWIS holds the clock low when a transfer starts, until the buffer for it has been
+// prepared.
+pub struct I2cSlave {
+    twis: TWIS1,
+    received: &'static mut [u8; REGISTERS_LEN + 1],
+    sent: &'static mut [u8; REGISTERS_LEN],
+    // The register that the last write started at:
+    register: usize,
+    // Whether the last transfer was a write, whose data hasn't been handled yet:
+    writing: bool,
+}
+
+impl I2cSlave {
+    pub fn new(twis: TWIS1, pins: I2cPins) -> Self {
+        // The buffers are used by EasyDMA between interrupts, so they must live for the
+        // whole program.
+        let received =
+            cortex_m::singleton!(: [u8; REGISTERS_LEN + 1] = [0; REGISTERS_LEN + 1]).unwrap();
+        let sent = cortex_m::singleton!(: [u8; REGISTERS_LEN] = [0; REGISTERS_LEN]).unwrap();
+        let scl = pins.scl.degrade();
+        let sda = pins.sda.degrade();
+        configure_pin(&scl);
+        configure_pin(&sda);
+        twis.psel
+            .scl
+            .write(|w| unsafe { w.bits(scl.psel_bits()) }.connect().connected());
+        twis.psel
+            .sda
+            .write(|w| unsafe { w.bits(sda.psel_bits()) }.connect().connected());
+        twis.address[0].write(|w| unsafe { w.address().bits(ADDRESS) });
+        twis.config.write(|w| w.address0().enabled());
+        twis.orc.write(|w| unsafe { w.orc().bits(OVER_READ) });
+        // The TWIS waits for the buffers after the start of every transfer.
+        twis.shorts
+            .write(|w| w.write_suspend().enabled().read_suspend().enabled());
+        twis.intenset
+            .write(|w| w.write().set().read().set().stopped().set());
+        twis.enable.write(|w| w.enable().enabled());
+        Self {
+            twis,
+            received,
+            sent,
+            register: 0,
+            writing: false,
+        }
+    }
+
+    // Handle the events of the TWIS, given the current state of the game. Returns the
+    // change written by the controller, if a write has ended. It must be called from the
+    // interrupt of the TWIS.
+    pub fn on_interrupt(&mut self, registers: &Registers) -> Option<I2cWrite> {
+        let mut write = None;
+        if self.twis.events_write.read().bits() != 0 {
+            self.twis.events_write.reset();
+            self.twis
+                .rxd
+                .ptr
+                .write(|w| unsafe { w.ptr().bits(self.received.as_mut_ptr() as u32) });
+            self.twis
+                .rxd
+                .maxcnt
+                .write(|w| unsafe { w.maxcnt().bits(self.received.len() as _) });
+            compiler_fence(Ordering::SeqCst);
+            self.twis.tasks_preparerx.write(|w| unsafe { w.bits(1) });
+            self.twis.tasks_resume.write(|w| unsafe { w.bits(1) });
+            self.writing = true;
+        }
+        if self.twis.events_read.read().bits() != 0 {
+            self.twis.events_read.reset();
+            // The register to read from is written right before, without a stop.
+            write = self.end_write();
+            let bytes = registers.bytes();
+            let register = self.register.min(REGISTERS_LEN);
+            let length = REGISTERS_LEN - register;
+            self.sent[..length].copy_from_slice(&bytes[register..]);
+            self.twis
+                .txd
+                .ptr
+                .write(|w| unsafe { w.ptr().bits(self.sent.as_ptr() as u32) });
+            self.twis
+                .txd
+                .maxcnt
+                .write(|w| unsafe { w.maxcnt().bits(length as _) });
+            compiler_fence(Ordering::SeqCst);
+            self.twis.tasks_preparetx.write(|w| unsafe { w.bits(1) });
+            self.twis.tasks_resume.write(|w| unsafe { w.bits(1) });
+        }
+        if self.twis.events_stopped.read().bits() != 0 {
+            self.twis.events_stopped.reset();
+            write = write.or(self.end_write());
+        }
+        self.twis.errorsrc.write(|w| w);
+        write
+    }
+
+    // Handle the data of the last write, if it hasn't been handled yet: the number of a
+    // register, and the values written to the registers from that one on.
+    fn end_write(&mut self) -> Option<I2cWrite> {
+        if !core::mem::take(&mut self.writing) {
+            return None;
+        }
+        compiler_fence(Ordering::SeqCst);
+        let amount = self.twis.rxd.amount.read().bits() as usize;
+        let (&register, values) = self.received[..amount].split_first()?;
+        self.register = register as usize;
+        let (&value, _) = values.split_first()?;
+        match self.register {
+            GRID if values.len() >= 5 => {
+                let mut matrix = [[false; 5]; 5];
+                for (row, bits) in matrix.iter_mut().zip(values) {
+                    for (col_n, alive) in row.iter_mut().enumerate() {
+                        *alive = bits & (1 << col_n) != 0;
+                    }
+                }
+                Some(I2cWrite::Grid(matrix))
+            }
+            SPEED if (value as usize) <= FASTEST_SPEED => {
+                Some(I2cWrite::Command(Command::Speed(value as usize)))
+            }
+            RULE if (value as usize) < RULES.len() => {
+                Some(I2cWrite::Command(Command::Rule(value as usize)))
+            }
+            CONTROL => match value {
+                1 => Some(I2cWrite::Command(Command::Pause)),
+                2 => Some(I2cWrite::Command(Command::Resume)),
+                3 => Some(I2cWrite::Command(Command::Step)),
+                4 => Some(I2cWrite::Command(Command::Random(SOUP_DENSITY))),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+// The pins of the TWIS must be inputs with pull-ups, which only pull down when driven.
+// This configuration is not available through the GPIO API, so it's written to the
+// registers of the pins, which the driver owns.
+fn configure_pin(pin: &Pin<Input<Floating>>) {
+    let pin_cnf = match pin.port() {
+        Port::Port0 => &unsafe { &*P0::ptr() }.pin_cnf[pin.pin() as usize],
+        Port::Port1 => &unsafe { &*P1::ptr() }.pin_cnf[pin.pin() as usize],
+    };
+    pin_cnf.write(|w| {
+        w.dir()
+            .input()
+            .input()
+            .connect()
+            .pull()
+            .pullup()
+            .drive()
+            .s0d1()
+            .sense()
+            .disabled()
+    });
+}
@@ -0,0 +1,75 @@
+use crate::button::ButtonEvent;
+use heapless::spsc::{Producer, Queue};
+
+// Size of the queue of input events. One of the slots is always empty, so it can hold
+// QUEUE_SIZE - 1 events. The main loop empties it after every interrupt, so it only
+// fills up if the main loop is kept busy for a long time, e.g., sending a frame to an
+// external display.
+const QUEUE_SIZE: usize = 16;
+
+// Events generated by the inputs of the board. They are generated by the interrupts
+// that read the inputs and handled by the main loop, which keeps the interrupts short.
+#[derive(Clone, Copy)]
+pub enum InputEvent {
+    // An event of button a.
+    ButtonA(ButtonEvent),
+    // An event of button b.
+    ButtonB(ButtonEvent),
+    // Both buttons have been pressed at the same time. Their presses are cancelled, so
+    // they don't generate events of their own.
+    Chord,
+    // The buttons of a chord have been released after being kept pressed for
+    // POWER_OFF_HOLD_MS, but not for RESET_HOLD_MS.
+    ChordHold,
+    // Both buttons have been kept pressed for RESET_HOLD_MS after a chord.
+    ChordLongHold,
+    // An event of the touch logo.
+    Touch(ButtonEvent),
+    // The board has been shaken. It carries the noisy readings of the accelerometer.
+    Shake(u32),
+    // A loud sound, like a clap, has been heard. It carries the reading of the
+    // microphone.
+    Clap(u32),
+    // The board has been tapped.
+    Tap,
+    // The board is falling.
+    FreeFall,
+    // The board has been turned face down and back up.
+    Flip,
+    // An event of one of the external buttons of the rings, with the number of its ring.
+    #[cfg(feature = "ring-buttons")]
+    Ring(usize, ButtonEvent),
+    // A rising edge of the external trigger.
+    #[cfg(feature = "trigger")]
+    Trigger,
+}
+
+// Single producer single consumer queue of input events. The interrupts push the events
+// with the producer and the main loop pops them with the consumer.
+pub type EventQueue = Queue<InputEvent, QUEUE_SIZE>;
+pub type EventProducer = Producer<'static, InputEvent, QUEUE_SIZE>;
+
+// How the buttons of the board are read. The rest of the inputs are always read the
+// same way.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InputStrategy {
+    // Polled from the interrupt of a real time counter, with the rest of the polled
+    // inputs.
+    Polled,
+    // Sampled after the interrupts of their pins, through the GPIOTE.
+    Gpiote,
+    // Polled by the main loop itself, between its other jobs, like the first version of
+    // the game did. A long job of the main loop, like sending a frame to an external
+    // display, delays them.
+    Blocking,
+}
+
+// A source of input events. The rest of the firmware only handles the events in the
+// queue, so it doesn't depend on how the inputs are read: polling them periodically or
+// reacting to the interrupts of their pins.
+pub trait InputSource {
+    // Read the inputs and push the events that they have generated to the queue. It's
+    // called from the interrupt that drives the source, or from the main loop. If the
+    // queue is full the events are lost, which is better than blocking the interrupt.
+    fn poll(&mut self, events: &mut EventProducer);
+}
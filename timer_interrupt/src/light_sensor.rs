@@ -0,0 +1,70 @@
+use embedded_hal::adc::{Channel, OneShot};
+use microbit::{hal::saadc::Saadc, pac::P0};
+
+// Pin numbers (in port 0) of the rows of the LED matrix and of the column used to sense
+// the light. The column 3 is connected to P0.31, which is the analog input 7.
+const ROW_PINS: [usize; 5] = [21, 22, 15, 24, 19];
+const SENSE_COL_PIN: usize = 31;
+// Time the column is left floating before measuring its voltage, in CPU cycles (the
+// CPU runs at 64 MHz, so this is 1 ms).
+const DISCHARGE_CYCLES: u32 = 64_000;
+// Readings of the ADC that correspond to a bright room and to a dark room. The more
+// light, the faster the LEDs discharge, so the lower the reading. These values are
+// approximate and may need to be adjusted for each board.
+const BRIGHT_READING: i16 = 200;
+const DARK_READING: i16 = 1800;
+// Brightness used in a dark room. In a bright room the maximum (9) is used.
+const MIN_BRIGHTNESS: u8 = 3;
+
+// The analog input 7 of the SAADC. It's used instead of the P0_31 pin type because the
+// pin is owned by the display.
+struct SenseChannel;
+
+impl Channel<Saadc> for SenseChannel {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        7
+    }
+}
+
+// Uses the LEDs of the matrix as a light sensor, the same way the micro:bit runtime
+// does. The LEDs are reverse biased (row low, column high) so their junction behaves
+// like a small charged capacitor. Then the column is left floating: the light that
+// reaches the LEDs produces a small current that discharges it, and after a short time
+// its voltage is measured with the ADC. The more light, the lower the voltage.
+// The sensor has no state of its own: the pins are owned by the display and the SAADC
+// is shared with the microphone.
+
+// Measure the ambient light and return the raw ADC reading. It takes the display pins
+// over for about 1 ms, so it must be called inside a critical section, where the
+// display interrupt can't run. The display sets the pins again the next time it
+// switches rows.
+pub fn read(saadc: &mut Saadc) -> i16 {
+    // The pins are owned by the display, so the GPIO registers are accessed
+    // directly, like the display driver itself does.
+    let p0 = unsafe { &*P0::ptr() };
+    let row_bits = ROW_PINS.iter().fold(0, |bits, pin| bits | (1 << pin));
+
+    // Reverse bias the LEDs of the column.
+    p0.outclr.write(|w| unsafe { w.bits(row_bits) });
+    p0.outset.write(|w| unsafe { w.bits(1 << SENSE_COL_PIN) });
+
+    // Leave the column floating and let the light discharge it.
+    p0.pin_cnf[SENSE_COL_PIN].write(|w| w.dir().input().input().disconnect());
+    cortex_m::asm::delay(DISCHARGE_CYCLES);
+
+    let reading = saadc.read(&mut SenseChannel).unwrap_or(DARK_READING);
+
+    // Give the column back to the display.
+    p0.pin_cnf[SENSE_COL_PIN].write(|w| w.dir().output().input().disconnect());
+    reading
+}
+
+// Maximum brightness of the display for a given reading of the light sensor. The
+// brightness goes linearly from MIN_BRIGHTNESS in a dark room to 9 in a bright room.
+pub fn brightness_for_reading(reading: i16) -> u8 {
+    let darkness = (reading.clamp(BRIGHT_READING, DARK_READING) - BRIGHT_READING) as i32;
+    let range = (DARK_READING - BRIGHT_READING) as i32;
+    (9 - darkness * (9 - MIN_BRIGHTNESS as i32) / range) as u8
+}
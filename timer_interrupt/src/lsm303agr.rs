@@ -0,0 +1,161 @@
+use crate::my_board::InternalI2cPins;
+use microbit::{
+    hal::twim::{self, Frequency, Twim},
+    pac::TWIM0,
+};
+
+// I2C address of the accelerometer of the LSM303AGR.
+const ACCEL_ADDRESS: u8 = 0x19;
+// Registers of the accelerometer.
+const CTRL_REG1_A: u8 = 0x20;
+const CTRL_REG3_A: u8 = 0x22;
+const CTRL_REG4_A: u8 = 0x23;
+const CTRL_REG5_A: u8 = 0x24;
+const CTRL_REG6_A: u8 = 0x25;
+const OUT_X_L_A: u8 = 0x28;
+const INT1_CFG_A: u8 = 0x30;
+const INT1_SRC_A: u8 = 0x31;
+const INT1_THS_A: u8 = 0x32;
+const INT1_DURATION_A: u8 = 0x33;
+const CLICK_CFG_A: u8 = 0x38;
+const CLICK_SRC_A: u8 = 0x39;
+const CLICK_THS_A: u8 = 0x3a;
+const TIME_LIMIT_A: u8 = 0x3b;
+// I2C address of the magnetometer of the LSM303AGR.
+const MAG_ADDRESS: u8 = 0x1e;
+// Registers of the magnetometer.
+const CFG_REG_A_M: u8 = 0x60;
+const CFG_REG_C_M: u8 = 0x62;
+const OUTX_L_REG_M: u8 = 0x68;
+// Setting the most significant bit of the register address makes the sensor increment
+// the address after every byte, so several registers can be read in one go.
+const AUTO_INCREMENT: u8 = 0x80;
+
+// Output data rate of 50 Hz with the three axes enabled.
+const CTRL_REG1_A_VALUE: u8 = 0x47;
+// Block data update (the two bytes of a reading always belong to the same sample), a
+// range of ±4 g and high resolution mode (12 bit readings).
+const CTRL_REG4_A_VALUE: u8 = 0x98;
+// In high resolution mode with a range of ±4 g, each unit of a reading is 2 mg. The
+// readings are left aligned in 16 bits, so they must be shifted 4 bits to the right.
+const MG_PER_UNIT: i32 = 2;
+// Route the click (tap) interrupt to the INT1 pin.
+const CTRL_REG3_A_VALUE: u8 = 0x80;
+// Make the interrupt pins active low. The interrupt line of the micro:bit is shared with
+// other chips, which also pull it low.
+const CTRL_REG6_A_VALUE: u8 = 0x02;
+// Detect single taps along any of the three axes.
+const CLICK_CFG_A_VALUE: u8 = 0x15;
+// Latch the tap interrupt until CLICK_SRC_A is read, with a threshold of 40 units of
+// 32 mg (1.28 g).
+const CLICK_THS_A_VALUE: u8 = 0x80 | 40;
+// Maximum duration of a tap, in samples (2 samples at 50 Hz are 40 ms). Longer
+// accelerations, like moving the board around, are not taps.
+const TIME_LIMIT_A_VALUE: u8 = 2;
+// Continuous mode at 10 Hz, with temperature compensation.
+const CFG_REG_A_M_VALUE: u8 = 0x80;
+// Block data update.
+const CFG_REG_C_M_VALUE: u8 = 0x10;
+// Each unit of a reading of the magnetometer is 1.5 mG (milligauss).
+const MILLIGAUSS_PER_2_UNITS: i32 = 3;
+// Latch the interrupt generator 1 until INT1_SRC_A is read. It's not routed to any pin,
+// it's only polled.
+const CTRL_REG5_A_VALUE: u8 = 0x08;
+// Interrupt generator 1 detects free fall: the acceleration of the three axes is low at
+// the same time.
+const INT1_CFG_A_VALUE: u8 = 0x95;
+// A falling board measures no acceleration, the threshold is 11 units of 32 mg (350 mg).
+const INT1_THS_A_VALUE: u8 = 11;
+// The acceleration must stay low for 5 samples (100 ms), so that passing through zero
+// while the board is moved doesn't count as a fall.
+const INT1_DURATION_A_VALUE: u8 = 5;
+// Bit of INT1_SRC_A that is set when the interrupt generator 1 has been triggered.
+const INT1_SRC_A_ACTIVE: u8 = 0x40;
+
+// The LSM303AGR accelerometer and magnetometer of the micro:bit v2, connected to the
+// internal I2C bus (SCL on P0.08 and SDA on P0.16). This is a minimal driver that only
+// does what the game needs.
+pub struct Lsm303agr {
+    twim: Twim<TWIM0>,
+}
+
+impl Lsm303agr {
+    pub fn new(twim0: TWIM0, pins: InternalI2cPins) -> Self {
+        let twim = Twim::new(
+            twim0,
+            twim::Pins {
+                scl: pins.scl.degrade(),
+                sda: pins.sda.degrade(),
+            },
+            Frequency::K400,
+        );
+        let mut sensor = Self { twim };
+        // If the sensor doesn't answer, the readings fail and the features that use it
+        // do nothing.
+        sensor.write_register(ACCEL_ADDRESS, CTRL_REG1_A, CTRL_REG1_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, CTRL_REG4_A, CTRL_REG4_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, CTRL_REG5_A, CTRL_REG5_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, CTRL_REG6_A, CTRL_REG6_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, INT1_THS_A, INT1_THS_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, INT1_DURATION_A, INT1_DURATION_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, INT1_CFG_A, INT1_CFG_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, CLICK_CFG_A, CLICK_CFG_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, CLICK_THS_A, CLICK_THS_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, TIME_LIMIT_A, TIME_LIMIT_A_VALUE);
+        sensor.write_register(ACCEL_ADDRESS, CTRL_REG3_A, CTRL_REG3_A_VALUE);
+        sensor.write_register(MAG_ADDRESS, CFG_REG_A_M, CFG_REG_A_M_VALUE);
+        sensor.write_register(MAG_ADDRESS, CFG_REG_C_M, CFG_REG_C_M_VALUE);
+        sensor
+    }
+
+    // Read the acceleration along the x, y and z axes, in mg. Returns None if the
+    // sensor can't be read.
+    pub fn acceleration(&mut self) -> Option<[i32; 3]> {
+        let readings = self.read_axes(ACCEL_ADDRESS, OUT_X_L_A)?;
+        Some(readings.map(|reading| (reading >> 4) as i32 * MG_PER_UNIT))
+    }
+
+    // Read the magnetic field along the x, y and z axes, in mG. Returns None if the
+    // sensor can't be read. The readings are not calibrated, so they include the field
+    // of the board itself.
+    pub fn magnetic_field(&mut self) -> Option<[i32; 3]> {
+        let readings = self.read_axes(MAG_ADDRESS, OUTX_L_REG_M)?;
+        Some(readings.map(|reading| reading as i32 * MILLIGAUSS_PER_2_UNITS / 2))
+    }
+
+    // Read the three consecutive 16 bit readings, one per axis, that start at the given
+    // register.
+    fn read_axes(&mut self, address: u8, register: u8) -> Option<[i16; 3]> {
+        let mut bytes = [0; 6];
+        self.twim
+            .write_then_read(address, &[register | AUTO_INCREMENT], &mut bytes)
+            .ok()?;
+        let mut readings = [0; 3];
+        for (reading, pair) in readings.iter_mut().zip(bytes.chunks(2)) {
+            *reading = i16::from_le_bytes([pair[0], pair[1]]);
+        }
+        Some(readings)
+    }
+
+    // Release the tap interrupt, which stays latched until the source of the tap is
+    // read.
+    pub fn clear_tap(&mut self) {
+        let mut source = [0];
+        self.twim
+            .write_then_read(ACCEL_ADDRESS, &[CLICK_SRC_A], &mut source)
+            .ok();
+    }
+
+    // Returns true if the board has been in free fall since the last call.
+    pub fn free_fall(&mut self) -> bool {
+        let mut source = [0];
+        self.twim
+            .write_then_read(ACCEL_ADDRESS, &[INT1_SRC_A], &mut source)
+            .is_ok()
+            && source[0] & INT1_SRC_A_ACTIVE != 0
+    }
+
+    fn write_register(&mut self, address: u8, register: u8, value: u8) {
+        self.twim.write(address, &[register, value]).ok();
+    }
+}
@@ -1,33 +1,211 @@
 #![no_main]
 #![no_std]
 
+mod cpu_load;
+use cpu_load::CpuLoad;
+
+mod crc;
+
 mod game_of_life;
-use game_of_life::LifeState;
+use game_of_life::{Boundary, Changes, LifeState};
+
+mod game_run;
+use game_run::GameRun;
+
+mod menu;
+use menu::SettingsMenu;
+
+mod command;
+use command::Command;
+
+#[cfg(feature = "max7219")]
+mod max7219;
+#[cfg(feature = "max7219")]
+use max7219::Max7219;
+
+#[cfg(feature = "ssd1306")]
+mod ssd1306;
+#[cfg(feature = "ssd1306")]
+use {framebuffer::LedMatrix, ssd1306::Ssd1306};
+
+#[cfg(feature = "ws2812")]
+mod color;
+#[cfg(feature = "ws2812")]
+mod ws2812;
+#[cfg(feature = "ws2812")]
+use {color::CellAges, ws2812::Ws2812};
+
+#[cfg(not(feature = "pwm-display"))]
+mod light_sensor;
+
+#[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+mod i2c_slave;
+#[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+use i2c_slave::{I2cSlave, I2cWrite, Registers};
+
+mod lsm303agr;
+
+mod microphone;
+use microphone::{Microphone, MicrophoneMode};
+
+mod morse;
+use morse::{Morse, MorseOutput};
+
+mod motion;
+use motion::Motion;
 
 mod my_board;
 use my_board::MyBoard;
 
+mod patterns;
+use patterns::{Diagonal, PATTERNS};
+
+mod polled_input;
+use polled_input::{PolledButtons, PolledInputs};
+
+mod direct_display;
+
+mod panic;
+
+mod self_test;
+
+mod stack;
+
+mod power_fail;
+use power_fail::PowerFail;
+
+mod power_off;
+use power_off::Shutdown;
+
+mod population;
+use population::PopulationHistory;
+
+mod radio;
+use radio::{Payload, Radio};
+
+mod radio_cipher;
+use radio_cipher::RadioCipher;
+
+mod radio_protocol;
+use radio_protocol::{Link, RadioMessage, RadioRole, RemoteCommand};
+
+mod radio_text;
+
+mod rtt_terminal;
+
+#[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+mod spi_recorder;
+use rtt_terminal::RttTerminal;
+#[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+use spi_recorder::SpiRecorder;
+
+mod rle;
+
+mod rules;
+use rules::RULES;
+
+mod seed;
+use seed::Seeder;
+
+mod serial;
+use serial::{Serial, SerialReceiver};
+
+mod shell;
+use shell::{Shell, Snapshot, Terminal};
+
+mod settings;
+use settings::{Setting, Settings};
+
+mod stitch;
+use stitch::{Stitch, Tile};
+
+mod storage;
+use storage::{SavedState, Storage};
+
+mod sync_pulse;
+use sync_pulse::{SyncPin, SyncPulse};
+
+mod speaker;
+use speaker::{Sound, Speaker};
+
+#[cfg(feature = "pwm-display")]
+mod pwm_display;
+
+mod button;
+use button::ButtonEvent;
+
+mod button_pair;
+
+mod debounce;
+
+mod diagnostics;
+use diagnostics::Diagnostics;
+
+mod editor;
+use editor::Editor;
+
+mod fade;
+use fade::Fade;
+
+mod frame_stream;
+use frame_stream::FrameStream;
+mod gpiote_input;
+use gpiote_input::GpioteInputs;
+
+mod input;
+use input::{EventProducer, EventQueue, InputEvent, InputSource, InputStrategy};
+
+mod event_log;
+use event_log::LogEvent;
+
+mod framebuffer;
+use framebuffer::{FrameBuffer, Rotation};
+
+mod telemetry;
+use telemetry::{Event, GameStatus, Message, MessageProducer, MessageQueue, TelemetryOutput};
+
+mod text;
+use text::ScrollingText;
+
+mod time;
+
+mod timing;
+use timing::{
+    generation_period_ticks, BUTTON_PRESCALER, DEFAULT_SPEED, DISPLAY_PRESCALER, FASTEST_SPEED,
+};
+
+mod thermometer;
+use thermometer::Thermometer;
+
+mod touch;
+use touch::TouchLogo;
+
+mod trail;
+use trail::DeathTrail;
+
+mod watchdog;
+use watchdog::GameHandle;
+
 use core::cell::RefCell;
-use cortex_m::interrupt::Mutex;
+use core::fmt::Write;
+use cortex_m::interrupt::{CriticalSection, Mutex};
 use cortex_m_rt::entry;
+use embedded_hal::timer::CountDown;
 use microbit::{
-    display::nonblocking::{BitImage, Display},
     hal::{
         clocks::Clocks,
-        gpio::{
-            p0::{P0_14, P0_23},
-            Floating, Input,
-        },
-        prelude::InputPin,
+        ppi,
+        rng::Rng,
         rtc::{Rtc, RtcCompareReg, RtcInterrupt},
+        saadc::{Saadc, SaadcConfig},
+        timer::Timer,
     },
     // The interrupts are imported from the PAC. Since interrupts are chip-specific,
     // they need to be imported from a chip-specific create, such as the PAC (instead of
     // the cortex_m or cortex_m_rt creates).
-    pac::{self, interrupt, RTC0, RTC1, TIMER0},
+    pac::{self, interrupt, RTC0, RTC1, TIMER1},
 };
-use panic_rtt_target as _;
-use rtt_target::rtt_init_print;
+use rtt_target::{rprintln, rtt_init, set_print_channel};
 
 // These Mutex are a wrapper that protects the data inside from being accessed by
 // multiple threads at the same time. If one thread wants to access the data inside the
@@ -51,37 +229,496 @@ use rtt_target::rtt_init_print;
 // Option can be placed inside the RefCell. The None variant acts then as a placeholder
 // until a value is placed in the RefCell.
 
-// Real-time counter that is used to poll the state of the buttons.
-static BUTTON_COUNTER: Mutex<RefCell<Option<Rtc<RTC0>>>> = Mutex::new(RefCell::new(None));
-// Real-time counter that is used to update the figure shown in the display.
-static DISPLAY_COUNTER: Mutex<RefCell<Option<Rtc<RTC1>>>> = Mutex::new(RefCell::new(None));
+// The global variables are grouped by the part of the firmware that owns them, and each
+// group is a single Mutex, so an interrupt borrows the groups it works with instead of
+// each of their variables. The variables inside a group keep their own RefCell, so
+// borrowing one of them doesn't keep the functions called meanwhile from borrowing the
+// others.
 
-// Button a, used to pause/resume the game.
-static BUTTON_A: Mutex<RefCell<Option<P0_14<Input<Floating>>>>> = Mutex::new(RefCell::new(None));
-// Flag to kep track of the previous state of the button.
-static BUTTON_A_WAS_PRESSED: Mutex<RefCell<bool>> = Mutex::new(RefCell::new(false));
+// The run of the game: the grid, whether it's paused, the number of the generation and
+// the grid with which the run started. The board starts with the initial pattern, which
+// is replaced by the state saved in flash, if there is one.
+static GAME: Mutex<RefCell<GameRun>> = Mutex::new(RefCell::new(GameRun::new(INITIAL_STATE_MATRIX)));
+// What the board shows, driven by the RTC1 and TIMER0 interrupts.
+static DISPLAY: Mutex<DisplayState> = Mutex::new(DisplayState::new());
+// The sources of input events and the sensors, read by the RTC0, RTC1, GPIOTE, UARTE0
+// and I2C interrupts.
+static INPUT: Mutex<InputState> = Mutex::new(InputState::new());
+// The menu, the editor and the speed control, which the input events control, and the
+// settings they change.
+static CONTROLS: Mutex<ControlState> = Mutex::new(ControlState::new());
+// The radio and the world shared with other boards, driven by the RADIO and TIMER1
+// interrupts.
+static RADIO: Mutex<RadioState> = Mutex::new(RadioState::new());
+// The state saved in flash and what keeps the board running or switches it off.
+static STORAGE: Mutex<StorageState> = Mutex::new(StorageState::new());
+// The measurements and the streams that report what the game is doing.
+static DIAGNOSTICS: Mutex<DiagnosticsState> = Mutex::new(DiagnosticsState::new());
 
-// Button b, used to update the state of the game if the game is paused.
-static BUTTON_B: Mutex<RefCell<Option<P0_23<Input<Floating>>>>> = Mutex::new(RefCell::new(None));
-// Flag to kep track of the previous state of the button.
-static BUTTON_B_WAS_PRESSED: Mutex<RefCell<bool>> = Mutex::new(RefCell::new(false));
+struct DisplayState {
+    // Real-time counter that is used to update the figure shown in the display.
+    counter: RefCell<Option<Rtc<RTC1>>>,
+    // Number of display ticks since the board started, used to time the display effects.
+    ticks: RefCell<u32>,
+    driver: RefCell<Option<LedDisplay>>,
+    // Frames drawn for the display. The images are never shown on the display directly,
+    // they are drawn into the frame buffer and the display interrupt takes them from
+    // there. The images start without rotation, the rotation is selected in the settings
+    // menu.
+    frames: RefCell<FrameBuffer>,
+    // Brightness of the cells that have recently died. Since its initial value is known,
+    // there is no need to wrap it in an Option.
+    trail: RefCell<DeathTrail>,
+    // Transition between the image of the previous generation and the current one.
+    fade: RefCell<Fade>,
+    // Text scrolling across the display. While there is text, the game is not updated.
+    text: RefCell<Option<ScrollingText>>,
+    // Population of the last generations.
+    population: RefCell<PopulationHistory>,
+    // What the display is showing: the cells of the game or a graph of the population.
+    view: RefCell<View>,
+    // Readout of the generation number in Morse code, if there is one in progress.
+    morse: RefCell<Option<Morse>>,
+    // If the board has been left idle, the maximum brightness the display had before it
+    // was dimmed. It's restored by the next input.
+    idle: RefCell<Option<u8>>,
+    // If the game has been paused because the board fell, the maximum brightness the
+    // display had before it was dimmed. It's restored when the game is resumed.
+    fall_pause: RefCell<Option<u8>>,
+    // External 8x8 matrix that mirrors the on-board display.
+    #[cfg(feature = "max7219")]
+    external_matrix: RefCell<Option<Max7219>>,
+    // Number of generations that each cell has been alive for, used to color the cells
+    // of the WS2812 panel.
+    #[cfg(feature = "ws2812")]
+    ages: RefCell<CellAges>,
+    // Speaker that plays the births and deaths of the cells or the population.
+    speaker: RefCell<Option<Speaker>>,
+}
+
+impl DisplayState {
+    const fn new() -> Self {
+        Self {
+            counter: RefCell::new(None),
+            ticks: RefCell::new(0),
+            driver: RefCell::new(None),
+            frames: RefCell::new(FrameBuffer::new(Rotation::Deg0)),
+            trail: RefCell::new(DeathTrail::new()),
+            fade: RefCell::new(Fade::new()),
+            text: RefCell::new(None),
+            population: RefCell::new(PopulationHistory::new()),
+            view: RefCell::new(View::Cells),
+            morse: RefCell::new(None),
+            idle: RefCell::new(None),
+            fall_pause: RefCell::new(None),
+            #[cfg(feature = "max7219")]
+            external_matrix: RefCell::new(None),
+            #[cfg(feature = "ws2812")]
+            ages: RefCell::new(CellAges::new()),
+            speaker: RefCell::new(None),
+        }
+    }
+}
 
-static DISPLAY: Mutex<RefCell<Option<Display<TIMER0>>>> = Mutex::new(RefCell::new(None));
-static GAME_STATE: Mutex<RefCell<Option<LifeState>>> = Mutex::new(RefCell::new(None));
-// Flag to keep track of whether or not the game is paused.
-static PAUSED: Mutex<RefCell<bool>> = Mutex::new(RefCell::new(false));
+struct InputState {
+    // Real-time counter that is used to poll the state of the buttons.
+    counter: RefCell<Option<Rtc<RTC0>>>,
+    // Producer of the queue of input events. The events are pushed by the interrupts
+    // that read the inputs and handled in the main loop. The interrupts that push events
+    // run at the same priority and only use the producer inside critical sections, so
+    // they can share it.
+    events: RefCell<Option<EventProducer>>,
+    // Inputs read by polling them from the RTC0 interrupt: the buttons (unless they are
+    // read through the GPIOTE), the touch logo and the external buttons of the rings.
+    // Button a pauses and resumes the game, button b steps and randomizes it and the
+    // touch logo opens and closes the menu.
+    polled: RefCell<Option<PolledInputs>>,
+    // Inputs read from the GPIOTE interrupt: the external trigger and, optionally, the
+    // buttons.
+    gpiote: RefCell<Option<GpioteInputs>>,
+    // Gestures detected with the accelerometer, which is read from the RTC1 interrupt.
+    motion: RefCell<Option<Motion>>,
+    // Uptime of the last input event, in milliseconds, to tell when the board has been
+    // left idle.
+    last_input: RefCell<u64>,
+    // Analog to digital converter, shared by the microphone and the light sensor. The
+    // light sensor is used to dim the display in the dark and as a source of entropy for
+    // the seeder. It uses the LEDs of the matrix, so it can't be used with the PWM
+    // display, which doesn't let go of the column pins.
+    saadc: RefCell<Option<Saadc>>,
+    // Microphone used to reseed the grid with a clap.
+    microphone: RefCell<Option<Microphone>>,
+    // Temperature sensor, used as a source of entropy for the seeder and to nudge the
+    // speed of the game.
+    thermometer: RefCell<Option<Thermometer>>,
+    // Generator of the random soups.
+    seeder: RefCell<Seeder>,
+    // The receiving side of the serial interface, driven by the UARTE0 interrupt.
+    serial_receiver: RefCell<Option<SerialReceiver>>,
+    #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+    i2c_slave: RefCell<Option<I2cSlave>>,
+}
+
+impl InputState {
+    const fn new() -> Self {
+        Self {
+            counter: RefCell::new(None),
+            events: RefCell::new(None),
+            polled: RefCell::new(None),
+            gpiote: RefCell::new(None),
+            motion: RefCell::new(None),
+            last_input: RefCell::new(0),
+            saadc: RefCell::new(None),
+            microphone: RefCell::new(None),
+            thermometer: RefCell::new(None),
+            seeder: RefCell::new(Seeder::new()),
+            serial_receiver: RefCell::new(None),
+            #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+            i2c_slave: RefCell::new(None),
+        }
+    }
+}
+
+struct ControlState {
+    // Pattern editor. It's only Some while the editor is open, which can only happen
+    // while the game is paused.
+    editor: RefCell<Option<Editor>>,
+    // Settings menu. It's only Some while the menu is open.
+    menu: RefCell<Option<SettingsMenu>>,
+    // Display tick in which the speed control was last used. It's only Some while the
+    // speed control is open.
+    speed_control: RefCell<Option<u32>>,
+    // Settings selected in the menu.
+    settings: RefCell<Settings>,
+}
+
+impl ControlState {
+    const fn new() -> Self {
+        Self {
+            editor: RefCell::new(None),
+            menu: RefCell::new(None),
+            speed_control: RefCell::new(None),
+            settings: RefCell::new(Settings::new()),
+        }
+    }
+}
+
+struct RadioState {
+    radio: RefCell<Option<Radio>>,
+    link: RefCell<Option<Link>>,
+    cipher: RefCell<Option<RadioCipher>>,
+    // One-shot timer that sends the edges of a stitched world after a random slot.
+    edge_timer: RefCell<Option<Timer<TIMER1>>>,
+    // The state of the world shared with another board.
+    stitch: RefCell<Stitch>,
+    // The tile of the board in a world shared by several boards, set through the shell.
+    tile: RefCell<Tile>,
+}
+
+impl RadioState {
+    const fn new() -> Self {
+        Self {
+            radio: RefCell::new(None),
+            link: RefCell::new(None),
+            cipher: RefCell::new(None),
+            edge_timer: RefCell::new(None),
+            stitch: RefCell::new(Stitch::new()),
+            tile: RefCell::new(Tile::PAIR),
+        }
+    }
+}
+
+struct StorageState {
+    // The state of the game saved in flash.
+    storage: RefCell<Option<Storage>>,
+    // Whether the state is due to be saved by the autosave, which the main loop does.
+    autosave_due: RefCell<bool>,
+    // The power-fail warning, which saves the state before the supply is lost.
+    power_fail: RefCell<Option<PowerFail>>,
+    // The handle of the watchdog pet by the interrupt that advances the game.
+    watchdog: RefCell<Option<GameHandle>>,
+    // Whether the board is being switched off or restarted, which the main loop does
+    // once the goodbye message has scrolled away.
+    shutdown: RefCell<Option<Shutdown>>,
+}
+
+impl StorageState {
+    const fn new() -> Self {
+        Self {
+            storage: RefCell::new(None),
+            autosave_due: RefCell::new(false),
+            power_fail: RefCell::new(None),
+            watchdog: RefCell::new(None),
+            shutdown: RefCell::new(None),
+        }
+    }
+}
+
+struct DiagnosticsState {
+    // Measured generation and display rates.
+    rates: RefCell<Diagnostics>,
+    // Whether the load of the CPU is reported, set by the shell.
+    cpu_load_on: RefCell<bool>,
+    // The stream of the generations sent as text over RTT.
+    frame_stream: RefCell<Option<FrameStream>>,
+    // The producer of the queue of telemetry messages and where they are sent.
+    telemetry: RefCell<Option<MessageProducer>>,
+    telemetry_output: RefCell<TelemetryOutput>,
+    // Signal on a pin of the edge connector that follows the generations.
+    sync_pulse: RefCell<SyncPulse>,
+    // Recorder that the grid of every generation is streamed to over SPI.
+    #[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+    spi_recorder: RefCell<Option<SpiRecorder>>,
+}
+
+impl DiagnosticsState {
+    const fn new() -> Self {
+        Self {
+            rates: RefCell::new(Diagnostics::new()),
+            cpu_load_on: RefCell::new(false),
+            frame_stream: RefCell::new(None),
+            telemetry: RefCell::new(None),
+            telemetry_output: RefCell::new(TelemetryOutput::Off),
+            sync_pulse: RefCell::new(SyncPulse::new()),
+            #[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+            spi_recorder: RefCell::new(None),
+        }
+    }
+}
+
+// Number of display ticks between two changes of the pause indicator (500 ms).
+const PAUSE_BLINK_TICKS: u32 = timing::ms_to_ticks(500);
+// Number of display ticks between two shifts of the scrolling text (125 ms).
+const TEXT_SCROLL_TICKS: u32 = timing::ms_to_ticks(125);
+// Number of display ticks after which the speed control closes if it's not used (3 s).
+const SPEED_CONTROL_TICKS: u32 = timing::ms_to_ticks(3000);
+// Number of display ticks of a unit of the Morse code, the length of a dot (~100 ms).
+const MORSE_UNIT_TICKS: u32 = timing::ms_to_ticks(100);
+// Number of display ticks between two measurements of the temperature (5 s).
+const TEMP_SENSE_TICKS: u32 = timing::ms_to_ticks(5000);
+// Maximum brightness of the display while the game is paused after a fall.
+const FALL_PAUSE_BRIGHTNESS: u8 = 1;
+// Maximum brightness of the display while the board is idle.
+const IDLE_BRIGHTNESS: u8 = 1;
+// Number of generations that a soup runs for in the demo mode before it's replaced,
+// unless it dies out earlier.
+const DEMO_RUN_GENERATIONS: u32 = 60;
+// Number of slots that the edges of a stitched world can be delayed by, and length of a
+// slot, in microseconds, the ticks of the timers (longer than a packet).
+const RADIO_SLOTS: u32 = 6;
+const RADIO_SLOT_US: u32 = 500;
+// Number of display ticks between two measurements of the ambient light (2 s).
+#[cfg(not(feature = "pwm-display"))]
+const LIGHT_SENSE_TICKS: u32 = timing::ms_to_ticks(2000);
+// Number of display ticks between two measurements of the stack usage (1 s), and
+// percentage of the stack above which the usage is warned about on the display.
+const STACK_CHECK_TICKS: u32 = timing::ms_to_ticks(1000);
+const STACK_WARNING_PERCENT: usize = 75;
+
+// Initial state of the game of life. The game goes back to it when it's reset.
+const INITIAL_STATE_MATRIX: [[bool; 5]; 5] = [
+    [false, false, false, false, false],
+    [false, true, true, true, false],
+    [true, true, true, false, false],
+    [false, false, false, false, false],
+    [false, false, false, false, false],
+];
+
+// The different things that the display can show. The view is selected in the settings
+// menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    // The cells of the game.
+    Cells,
+    // A bar graph with the population of the last 5 generations, one per column.
+    PopulationBars,
+    // A scrolling graph with the population of the last 5 generations, one per column.
+    PopulationHistory,
+    // A scrolling text with the measured generation and display rates.
+    Diagnostics,
+}
+
+// Actions that can be assigned to the external buttons of the rings.
+#[cfg(feature = "ring-buttons")]
+#[derive(Clone, Copy)]
+enum RingAction {
+    // Pause or resume the game, like a short press of button a.
+    PauseResume,
+    // Pause the game and advance it one generation. Holding the button keeps stepping.
+    Step,
+    // Reset the game to the pattern selected in the settings.
+    Reset,
+}
+
+// Action of the external buttons of the rings 0, 1 and 2. Change it to match the
+// buttons of your setup.
+#[cfg(feature = "ring-buttons")]
+const RING_ACTIONS: [RingAction; 3] =
+    [RingAction::PauseResume, RingAction::Step, RingAction::Reset];
+
+// The options of the settings that are not just an index, in the order they appear in
+// the settings menu.
+const VIEWS: [View; 4] = [
+    View::Cells,
+    View::PopulationBars,
+    View::PopulationHistory,
+    View::Diagnostics,
+];
+const BOUNDARIES: [Boundary; 2] = [Boundary::Dead, Boundary::Wrap];
+const SOUNDS: [Sound; 3] = [Sound::Off, Sound::Blips, Sound::Pitch];
+// Whether the temperature nudges the speed.
+const TEMPERATURE_NUDGES: [bool; 2] = [false, true];
+const RADIO_ROLES: [RadioRole; 6] = [
+    RadioRole::Off,
+    RadioRole::Send,
+    RadioRole::View,
+    RadioRole::Pair,
+    RadioRole::Tile,
+    RadioRole::Remote,
+];
+const SYNC_PINS: [Option<SyncPin>; 6] = [
+    None,
+    Some(SyncPin::Pin0),
+    Some(SyncPin::Pin1),
+    Some(SyncPin::Pin2),
+    Some(SyncPin::Pin12),
+    Some(SyncPin::Pin14),
+];
+// Number of generations between two saves of the state, or None to never save it.
+const AUTOSAVE_PERIODS: [Option<u32>; 4] = [None, Some(10), Some(100), Some(1000)];
+// Milliseconds without inputs after which the board is idle, or None to never dim the
+// display.
+const IDLE_MS: [Option<u64>; 4] = [None, Some(60_000), Some(300_000), Some(900_000)];
+// Whether the game runs in the demo mode while the board is idle.
+const DEMO_MODES: [bool; 2] = [false, true];
+const MICROPHONE_MODES: [MicrophoneMode; 2] = [MicrophoneMode::Clap, MicrophoneMode::Levels];
+const MORSE_OUTPUTS: [MorseOutput; 3] = [MorseOutput::Led, MorseOutput::Sound, MorseOutput::Both];
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::Deg0,
+    Rotation::Deg90,
+    Rotation::Deg180,
+    Rotation::Deg270,
+];
+
+// Display driver used for the LED matrix. By default it's the non-blocking display of
+// the microbit crate, but the pwm-display feature replaces it with a driver that dims
+// the LEDs using the PWM peripherals. Both are driven by the TIMER0 interrupt.
+#[cfg(not(feature = "pwm-display"))]
+type LedDisplay = microbit::display::nonblocking::Display<pac::TIMER0>;
+#[cfg(feature = "pwm-display")]
+type LedDisplay = pwm_display::PwmDisplay;
 
 #[entry]
 fn main() -> ! {
-    rtt_init_print!();
+    // Paint the stack before it's used, to measure how much of it is used later.
+    stack::paint();
+
+    // Set up RTT with an up channel for the output of the panic handler and the RTT
+    // shell, a down channel for the commands sent to the shell by the host, and up
+    // channels for the stream of frames and the telemetry.
+    let rtt_channels = rtt_init! {
+        up: {
+            0: {
+                size: 1024
+                name: "Terminal"
+            }
+            1: {
+                size: 1024
+                name: "Frames"
+            }
+            2: {
+                size: 1024
+                name: "Telemetry"
+            }
+        }
+        down: {
+            0: {
+                size: 64
+                name: "Terminal"
+            }
+        }
+    };
+    set_print_channel(rtt_channels.up.0);
+    // If the board was reset after a panic or a hard fault, report its message, which
+    // was kept in RAM.
+    let panic_message = panic::take_kept_message();
+    if let Some(message) = panic_message.as_ref() {
+        rprintln!("The board was reset after a crash:\n{}", message);
+    }
+    let mut rtt_shell = Shell::new(RttTerminal::new(rtt_channels.down.0));
+    let frame_stream = FrameStream::new(rtt_channels.up.1);
+    let mut telemetry_channel = rtt_channels.up.2;
+    let telemetry_queue = cortex_m::singleton!(: MessageQueue = MessageQueue::new()).unwrap();
+    let (telemetry_producer, mut telemetry_consumer) = telemetry_queue.split();
+    // The state of the game the last time the telemetry was sent, to report its changes.
+    let mut game_status = None;
+
+    let mut board = MyBoard::take().unwrap();
 
-    let board = MyBoard::take().unwrap();
+    // Starting the low-frequency clock. This is needed for the real timer counters. The
+    // radio needs the external high-frequency oscillator, which is more accurate than
+    // the internal one.
+    Clocks::new(board.clock).enable_ext_hfosc().start_lfclk();
+    // The uptime is measured from here on, with the rtc2.
+    time::start(board.rtc2);
 
-    // Starting the low-frequency clock. This is needed for the real timer counters.
-    Clocks::new(board.clock).start_lfclk();
+    // If button a is held down while the board starts, check the hardware before it's
+    // used.
+    let mut thermometer = Thermometer::new(board.temp);
+    if board.button_a.is_pressed() {
+        self_test::run(
+            &board.button_a,
+            &board.button_b,
+            &board.rng,
+            &mut thermometer,
+            &mut board.motion_sensor,
+        );
+    }
 
     // Create a new display. The timer0 of the board is used to drive the display.
-    let display = Display::new(board.timer0, board.display_pins);
+    #[cfg(not(feature = "pwm-display"))]
+    let display = LedDisplay::new(board.timer0, board.display_pins);
+    #[cfg(feature = "pwm-display")]
+    let display = LedDisplay::new(board.timer0, board.display_pins, board.pwm0, board.pwm1);
+    let saadc = Saadc::new(board.saadc, SaadcConfig::default());
+    let microphone = Microphone::new(board.microphone_pins);
+    let speaker = Speaker::new(board.pwm2, board.speaker_pin);
+    let radio = Radio::new(board.radio);
+    // The packets are told apart by the lowest bits of the identifier of the chip.
+    let radio_id = board.ficr.deviceid[0].read().bits() as u16;
+    let link = Link::new(radio_id);
+    let mut edge_timer = Timer::one_shot(board.timer1);
+    edge_timer.enable_interrupt();
+    let storage = Storage::new(board.nvmc);
+    let power_fail = PowerFail::new(board.power);
+    let mut cpu_load = CpuLoad::new(board.dcb, board.dwt);
+    let cipher = RadioCipher::new(
+        board.ccm,
+        board.aar,
+        radio_id,
+        Rng::new(board.rng).random_u32(),
+    );
+    // The serial interface is only used by the main loop, so it doesn't need to be a
+    // global variable, but its receiver is driven by an interrupt.
+    let (serial, serial_receiver) = Serial::new(board.uarte0, board.uart_pins);
+    let mut shell = Shell::new(serial);
+    #[cfg(feature = "max7219")]
+    let external_matrix = Max7219::new(board.spim3, board.spi_pins);
+    #[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+    let spi_recorder = SpiRecorder::new(board.spim3, board.spi_pins);
+    // Writing a frame to the OLED display takes a few tens of milliseconds, too long to
+    // do it inside an interrupt, so it's done from the main loop.
+    #[cfg(feature = "ssd1306")]
+    let mut oled = Ssd1306::new(board.twim1, board.i2c_pins);
+    #[cfg(feature = "ssd1306")]
+    let mut oled_frame = [[0; 5]; 5];
+    #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+    let i2c_slave = I2cSlave::new(board.twis1, board.i2c_pins);
+    // The same goes for the WS2812 panel.
+    #[cfg(feature = "ws2812")]
+    let mut panel = Ws2812::new(board.spim2, board.ws2812_pins);
+    #[cfg(feature = "ws2812")]
+    let mut panel_frame = ([[0; 5]; 5], None);
 
     // Create and configure the real time counter (RTCs). The rtc0 is used to
     // periodically poll the buttons to check if they have been pressed and the rtc1 is
@@ -90,34 +727,77 @@ fn main() -> ! {
 
     // The counter used to poll the buttons has a frequency of 166.66 Hz and a period
     // of approximately 6ms.
-    let mut button_counter = Rtc::new(board.rtc0, 196).unwrap();
+    let mut button_counter = Rtc::new(board.rtc0, BUTTON_PRESCALER).unwrap();
     button_counter.enable_event(RtcInterrupt::Tick);
     button_counter.enable_interrupt(RtcInterrupt::Tick, None);
     button_counter.enable_counter();
 
-    // The counter used to update the display has a frequency of 8 Hz and a period of
-    // 125 ms. This is maximum value for the period. The Compare value is set to 8,
-    // which means that Compare0 interrupt will be called after 8 periods of time, i.e.,
-    // after 1 second.
-    let mut display_counter = Rtc::new(board.rtc1, 4095).unwrap();
+    // The counter used to update the display ticks DISPLAY_TICK_HZ times per second
+    // (32 Hz, with a period of 31.25 ms). The Tick interrupt is called every period and
+    // it's used to update the image shown on the display: fading from the previous
+    // generation to the new one, scrolling text and blinking indicators. The Compare
+    // value is set to the number of ticks of the generation period, which means that
+    // Compare0 interrupt will be called after that many periods of time. This is when
+    // the game advances to the next generation, and the counter is cleared by a PPI
+    // channel to start the next period.
+    let mut display_counter = Rtc::new(board.rtc1, DISPLAY_PRESCALER).unwrap();
     display_counter
-        .set_compare(RtcCompareReg::Compare0, 8)
+        .set_compare(
+            RtcCompareReg::Compare0,
+            generation_period_ticks(DEFAULT_SPEED),
+        )
         .unwrap();
     display_counter.enable_event(RtcInterrupt::Compare0);
     display_counter.enable_interrupt(RtcInterrupt::Compare0, None);
+    display_counter.enable_event(RtcInterrupt::Tick);
+    display_counter.enable_interrupt(RtcInterrupt::Tick, None);
     display_counter.enable_counter();
+    timing::link_generation_clear(ppi::Parts::new(board.ppi).ppi0);
 
-    // Set the initial state of the game of life.
-    let initial_state_matrix: [[bool; 5]; 5] = [
-        [false, false, false, false, false],
-        [false, true, true, true, false],
-        [true, true, true, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-    ];
-    let initial_state = LifeState {
-        matrix: initial_state_matrix,
+    // Set up the sources of input events. The external trigger generates an event on
+    // every rising edge of its pin. The buttons are polled like the rest of the inputs,
+    // or by the main loop itself with the blocking-input feature, but if button b is held
+    // down while the board starts, they are read through the GPIOTE interrupt instead.
+    let button_a = board.button_a;
+    let button_b = board.button_b;
+    let input_strategy = if button_b.is_pressed() {
+        InputStrategy::Gpiote
+    } else if cfg!(feature = "blocking-input") {
+        InputStrategy::Blocking
+    } else {
+        InputStrategy::Polled
     };
+    let polled_inputs = PolledInputs::new(TouchLogo::new(board.touch_logo));
+    let gpiote_inputs = GpioteInputs::new(board.gpiote).with_taps(board.sensor_interrupt);
+    let (polled_inputs, gpiote_inputs, mut blocking_buttons) = match input_strategy {
+        InputStrategy::Polled => (
+            polled_inputs.with_buttons(PolledButtons::new(button_a, button_b)),
+            gpiote_inputs,
+            None,
+        ),
+        InputStrategy::Gpiote => (
+            polled_inputs,
+            gpiote_inputs.with_buttons(button_a, button_b),
+            None,
+        ),
+        InputStrategy::Blocking => (
+            polled_inputs,
+            gpiote_inputs,
+            Some(PolledButtons::new(button_a, button_b)),
+        ),
+    };
+    #[cfg(feature = "ring-buttons")]
+    let polled_inputs = polled_inputs.with_rings(board.ring_buttons);
+    #[cfg(feature = "trigger")]
+    let gpiote_inputs = gpiote_inputs.with_trigger(board.trigger_pin);
+
+    let motion = Motion::new(board.motion_sensor);
+
+    // Create the queue of input events. It must live for the rest of the program, since
+    // the producer is placed in a global variable, so it's created with the singleton
+    // macro, which returns a &'static mut reference to it.
+    let event_queue = cortex_m::singleton!(: EventQueue = EventQueue::new()).unwrap();
+    let (event_producer, mut event_consumer) = event_queue.split();
 
     // Inside a critical section interrupts are disable. In this case the interrupts
     // are configured inside a critical section to avoid the configuration being
@@ -131,7 +811,15 @@ fn main() -> ! {
         unsafe {
             pac::NVIC::unmask(pac::Interrupt::RTC0);
             pac::NVIC::unmask(pac::Interrupt::RTC1);
+            pac::NVIC::unmask(pac::Interrupt::RTC2);
             pac::NVIC::unmask(pac::Interrupt::TIMER0);
+            pac::NVIC::unmask(pac::Interrupt::TIMER1);
+            pac::NVIC::unmask(pac::Interrupt::GPIOTE);
+            pac::NVIC::unmask(pac::Interrupt::UARTE0_UART0);
+            pac::NVIC::unmask(pac::Interrupt::RADIO);
+            pac::NVIC::unmask(pac::Interrupt::POWER_CLOCK);
+            #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+            pac::NVIC::unmask(pac::Interrupt::SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1);
         }
 
         // A pending interupt is an interrupt which has been raised but has not been
@@ -139,7 +827,15 @@ fn main() -> ! {
         // state.
         pac::NVIC::unpend(pac::Interrupt::RTC0);
         pac::NVIC::unpend(pac::Interrupt::RTC1);
+        pac::NVIC::unpend(pac::Interrupt::RTC2);
         pac::NVIC::unpend(pac::Interrupt::TIMER0);
+        pac::NVIC::unpend(pac::Interrupt::TIMER1);
+        pac::NVIC::unpend(pac::Interrupt::GPIOTE);
+        pac::NVIC::unpend(pac::Interrupt::UARTE0_UART0);
+        pac::NVIC::unpend(pac::Interrupt::RADIO);
+        pac::NVIC::unpend(pac::Interrupt::POWER_CLOCK);
+        #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+        pac::NVIC::unpend(pac::Interrupt::SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1);
 
         // Place the values inside the Mutex that acts as a shared state. Calling the
         // .borrow() method returns the RefCell inside the Mutex, and then calling the
@@ -148,112 +844,1749 @@ fn main() -> ! {
         // called inside a critical section. The contents of a cotex_m::interrupt::Mutex
         // can only be accessed inside a critical section to avoid deadlocks.
 
-        BUTTON_COUNTER.borrow(cs).replace(Some(button_counter));
-        DISPLAY_COUNTER.borrow(cs).replace(Some(display_counter));
+        INPUT.borrow(cs).counter.replace(Some(button_counter));
+        DISPLAY.borrow(cs).counter.replace(Some(display_counter));
+        INPUT.borrow(cs).events.replace(Some(event_producer));
 
-        BUTTON_A.borrow(cs).replace(Some(board.button_a));
-        BUTTON_B.borrow(cs).replace(Some(board.button_b));
+        INPUT.borrow(cs).polled.replace(Some(polled_inputs));
+        INPUT.borrow(cs).motion.replace(Some(motion));
+        INPUT.borrow(cs).gpiote.replace(Some(gpiote_inputs));
 
-        DISPLAY.borrow(cs).replace(Some(display));
-        GAME_STATE.borrow(cs).replace(Some(initial_state))
+        DISPLAY.borrow(cs).driver.replace(Some(display));
+        DISPLAY.borrow(cs).speaker.replace(Some(speaker));
+        INPUT.borrow(cs).saadc.replace(Some(saadc));
+        INPUT.borrow(cs).microphone.replace(Some(microphone));
+        RADIO.borrow(cs).radio.replace(Some(radio));
+        RADIO.borrow(cs).link.replace(Some(link));
+        RADIO.borrow(cs).edge_timer.replace(Some(edge_timer));
+        RADIO.borrow(cs).cipher.replace(Some(cipher));
+        #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+        INPUT.borrow(cs).i2c_slave.replace(Some(i2c_slave));
+        INPUT
+            .borrow(cs)
+            .serial_receiver
+            .replace(Some(serial_receiver));
+        DIAGNOSTICS
+            .borrow(cs)
+            .frame_stream
+            .replace(Some(frame_stream));
+        DIAGNOSTICS
+            .borrow(cs)
+            .telemetry
+            .replace(Some(telemetry_producer));
+        INPUT.borrow(cs).thermometer.replace(Some(thermometer));
+        #[cfg(feature = "max7219")]
+        DISPLAY
+            .borrow(cs)
+            .external_matrix
+            .replace(Some(external_matrix));
+        #[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+        DIAGNOSTICS
+            .borrow(cs)
+            .spi_recorder
+            .replace(Some(spi_recorder));
+        STORAGE.borrow(cs).storage.replace(Some(storage));
+        STORAGE.borrow(cs).power_fail.replace(Some(power_fail));
+        let saved = STORAGE
+            .borrow(cs)
+            .storage
+            .borrow()
+            .as_ref()
+            .and_then(Storage::load);
+        if let Some(state) = saved {
+            restore_state(cs, state);
+        }
+        // Scroll a welcome message before the game starts, or the name of the input
+        // strategy if it's not the default one. After a crash, an error message is
+        // scrolled instead, so it's noticed without a debugger.
+        let message = match input_strategy {
+            _ if panic_message.is_some() => "PANIC",
+            InputStrategy::Polled => "LIFE",
+            InputStrategy::Gpiote => "GPIOTE",
+            InputStrategy::Blocking => "BLOCKING",
+        };
+        DISPLAY
+            .borrow(cs)
+            .text
+            .replace(Some(ScrollingText::new(message)));
     });
+    // The high water mark of the stack and the display tick when it was last measured.
+    let mut stack_used = 0;
+    let mut stack_checked = 0;
+
+    // The watchdog is started last, right before the main loop, so the setup above,
+    // which loads the journal from flash, doesn't count towards its timeout.
+    let (game_watchdog, mut loop_watchdog) = watchdog::start(board.wdt).unzip();
+    cortex_m::interrupt::free(|cs| STORAGE.borrow(cs).watchdog.replace(game_watchdog));
+
+    loop {
+        // The CPU sleeps until the next interrupt is triggered, instead of spinning, since
+        // all the work of the main loop comes from the interrupts. They only push it to
+        // the single producer single consumer queues (input events, telemetry and the
+        // bytes of the serial interface), which are emptied below after waking up. Work
+        // pushed after a queue has been emptied but before the CPU sleeps isn't lost: it
+        // waits until the next interrupt, at most one poll of the buttons (about 6 ms)
+        // away, which also wakes the loop to read the RTT shell, which has no interrupt.
+        // An interrupt that is already pending when WFI is executed wakes the CPU right
+        // away.
+        cortex_m::asm::wfi();
+
+        // The main loop runs after every interrupt, so it pets the watchdog much more
+        // often than needed.
+        if let Some(handle) = loop_watchdog.as_mut() {
+            handle.pet();
+        }
+
+        // With the blocking input strategy, the buttons are read here. The loop wakes up
+        // at least every poll of the rest of the inputs, so they are read as often.
+        if let Some(buttons) = blocking_buttons.as_mut() {
+            cortex_m::interrupt::free(|cs| {
+                if let Some(events) = INPUT.borrow(cs).events.borrow_mut().as_mut() {
+                    buttons.poll(events);
+                }
+            });
+        }
+
+        // Handle the input events pushed by the interrupts. An event pushed right after
+        // the queue is emptied waits until the next interrupt, which is at most one poll
+        // of the buttons away.
+        while let Some(event) = event_consumer.dequeue() {
+            cortex_m::interrupt::free(|cs| handle_input(cs, event));
+        }
+
+        // Switch the board off or restart it once the goodbye message has scrolled away
+        // and the buttons have been released, since button a would wake the board up
+        // again right away, and when the board starts, the buttons select the self-test
+        // and the GPIOTE inputs.
+        let shutdown = cortex_m::interrupt::free(|cs| {
+            let text_shown = DISPLAY.borrow(cs).text.borrow().is_some();
+            STORAGE.borrow(cs).shutdown.borrow().filter(|_| !text_shown)
+        });
+        match shutdown {
+            Some(_) if power_off::buttons_pressed() => {}
+            Some(Shutdown::PowerOff) => power_off::system_off(),
+            Some(Shutdown::Reset) => cortex_m::peripheral::SCB::sys_reset(),
+            None => {}
+        }
+
+        // Run the commands typed in the shells, through the serial interface and RTT.
+        run_shell(&mut shell);
+        run_shell(&mut rtt_shell);
+
+        // Send the telemetry: the messages of the generations pushed by the interrupts
+        // and the events of the changes of the state of the game since the last time,
+        // which are also recorded in the event log.
+        let (output, status) = cortex_m::interrupt::free(|cs| {
+            (
+                *DIAGNOSTICS.borrow(cs).telemetry_output.borrow(),
+                game_status_now(cs),
+            )
+        });
+        let events = match game_status.replace(status) {
+            Some(old_status) => old_status.events(status),
+            None => heapless::Vec::new(),
+        };
+        cortex_m::interrupt::free(|cs| {
+            for &event in events.iter() {
+                event_log::record(cs, LogEvent::Game(event));
+            }
+        });
+        // The state is saved every time the game is paused, so pausing it before
+        // switching the board off keeps it, and every few generations if the autosave
+        // is on.
+        let autosave =
+            cortex_m::interrupt::free(|cs| STORAGE.borrow(cs).autosave_due.replace(false));
+        if autosave || events.contains(&Event::Paused) {
+            cortex_m::interrupt::free(save_state);
+        }
+        let messages = events
+            .into_iter()
+            .map(Message::Event)
+            .chain(core::iter::from_fn(|| telemetry_consumer.dequeue()));
+        for message in messages {
+            let frame = message.encode();
+            match output {
+                TelemetryOutput::Off => {}
+                TelemetryOutput::Rtt => {
+                    telemetry_channel.write(&frame);
+                }
+                TelemetryOutput::Serial => shell.terminal().write_bytes(&frame),
+            }
+        }
+
+        // If there is an OLED display, copy the frame shown on the LEDs to it when it
+        // changes. Only the copy is done inside a critical section, so the interrupts
+        // are not blocked while the frame is sent.
+        #[cfg(feature = "ssd1306")]
+        {
+            let frame =
+                cortex_m::interrupt::free(|cs| DISPLAY.borrow(cs).frames.borrow().front_frame());
+            if frame != oled_frame {
+                oled.show_brightness(&frame);
+                oled_frame = frame;
+            }
+        }
 
-    loop {}
+        // Likewise for the WS2812 panel. When the display shows the cells of the game,
+        // their ages are copied too so the panel can color them.
+        #[cfg(feature = "ws2812")]
+        {
+            let frame = cortex_m::interrupt::free(|cs| {
+                let ages = showing_cells(cs).then(|| {
+                    DISPLAY
+                        .borrow(cs)
+                        .frames
+                        .borrow()
+                        .rotate(DISPLAY.borrow(cs).ages.borrow().ages())
+                });
+                (DISPLAY.borrow(cs).frames.borrow().front_frame(), ages)
+            });
+            if frame != panel_frame {
+                panel.show(&ws2812::frame_colors(frame.0, frame.1));
+                panel_frame = frame;
+            }
+        }
+
+        // Report the load of the CPU over RTT every time it's measured, while the
+        // reports are on.
+        let (ticks, cpu_load_on) = cortex_m::interrupt::free(|cs| {
+            (
+                *DISPLAY.borrow(cs).ticks.borrow(),
+                *DIAGNOSTICS.borrow(cs).cpu_load_on.borrow(),
+            )
+        });
+        cpu_load.set_enabled(cpu_load_on, ticks);
+        if let Some(load) = cpu_load.measure(ticks) {
+            rprintln!("CPU load: {}.{:02}%", load / 100, load % 100);
+        }
+
+        // Every STACK_CHECK_TICKS, measure the high water mark of the stack. When it
+        // grows it's reported over RTT, and when it goes over STACK_WARNING_PERCENT of
+        // the stack, "STACK" is scrolled on the display as a warning.
+        if ticks.wrapping_sub(stack_checked) >= STACK_CHECK_TICKS {
+            stack_checked = ticks;
+            let used = stack::high_water_mark();
+            if used > stack_used {
+                let size = stack::size();
+                rprintln!("Stack: {} of {} bytes used", used, size);
+                let limit = size * STACK_WARNING_PERCENT / 100;
+                if used > limit && stack_used <= limit {
+                    cortex_m::interrupt::free(|cs| {
+                        DISPLAY
+                            .borrow(cs)
+                            .text
+                            .replace(Some(ScrollingText::new("STACK")))
+                    });
+                }
+                stack_used = used;
+            }
+        }
+    }
 }
 
 // This interrupt is used to drive the display. It takes care of updating the LED
-// display and clearing the timer's event registers.
+// display and clearing the timer's event registers. If a new frame has been drawn, it's
+// handed to the display first (and to the external matrix, if there is one).
 #[interrupt]
 fn TIMER0() {
     cortex_m::interrupt::free(|cs| {
-        if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
-            display.handle_display_event();
+        let display = DISPLAY.borrow(cs);
+        if let Some(driver) = display.driver.borrow_mut().as_mut() {
+            let mut frames = display.frames.borrow_mut();
+            #[cfg(feature = "max7219")]
+            if let Some(matrix) = display.external_matrix.borrow_mut().as_mut() {
+                frames.update_display(&mut (&mut *driver, matrix));
+            }
+            #[cfg(not(feature = "max7219"))]
+            frames.update_display(driver);
+            driver.handle_display_event();
         };
     });
 }
 
-// Interrupt used to poll the buttons. It will be called approximately every 6ms.
+// Interrupt used to poll the buttons. It will be called approximately every 6ms. The
+// events of the buttons are pushed to the queue of input events, to be handled by the
+// main loop.
 #[interrupt]
 fn RTC0() {
     cortex_m::interrupt::free(move |cs| {
-        if let Some(button_a) = BUTTON_A.borrow(cs).borrow().as_ref() {
-            if let Ok(a_pressed) = button_a.is_low() {
-                // Check if the button a is being pressed.
-                if a_pressed {
-                    // The game should be paused/ resumed only if the buttons a is
-                    // being pressed and was not being pressed before, this is, the
-                    // game is only paused/resumed on the press on the button, but it's
-                    // not being constantly paused/resumed while the buttons is kept
-                    // pressed. The global mutable variable BUTTON_A_WAS_PRESSED is used
-                    // to keep track of the previous state of the button.
-                    // The .replace() method does two things. First it replaces the old
-                    // value contained in BUTTON_WAS_PRESSED with true, since the button
-                    // is now being pressed. Second, it returns the old value contained
-                    // in BUTTON_WAS_PRESSED, which is used to check if the button has
-                    // just been pressed.
-                    if !BUTTON_A_WAS_PRESSED.borrow(cs).replace(true) {
-                        // If the button has just been pressed, the value inside PAUSED
-                        // is negated.
-                        PAUSED.borrow(cs).replace_with(|&mut old_value| !old_value);
-                    };
-                } else {
-                    // Finally, if the button is not being pressed, the value inside
-                    // BUTTON_A_WAS_PRESSED is set to false.
-                    BUTTON_A_WAS_PRESSED.borrow(cs).replace(false);
-                };
-            };
-        };
-        if let Some(button_b) = BUTTON_B.borrow(cs).borrow().as_ref() {
-            if let Ok(b_pressed) = button_b.is_low() {
-                if b_pressed {
-                    // The same logic is followed as for the button a.
-                    if !BUTTON_B_WAS_PRESSED.borrow(cs).replace(true) {
-                        // Button b will update the game state shown on the screen only
-                        // if the game is paused.
-                        if *PAUSED.borrow(cs).borrow() {
-                            if let Some(game_state) = GAME_STATE.borrow(cs).borrow_mut().as_mut() {
-                                game_state.next_state();
-                                if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
-                                    let image = BitImage::new(&game_state.int_matrix());
-                                    display.show(&image);
-                                };
-                            }
-                        }
-                    };
-                } else {
-                    BUTTON_B_WAS_PRESSED.borrow(cs).replace(false);
-                };
-            };
-        };
+        let input = INPUT.borrow(cs);
+        if let Some(inputs) = input.polled.borrow_mut().as_mut() {
+            poll_inputs(cs, inputs);
+        }
+        // The buttons read through the GPIOTE are sampled here after their pins change.
+        if let (Some(inputs), Some(events)) = (
+            input.gpiote.borrow_mut().as_mut(),
+            input.events.borrow_mut().as_mut(),
+        ) {
+            inputs.sample_buttons(events);
+        }
+        // The serial receiver hands over the bytes received once the line is quiet.
+        if let Some(receiver) = input.serial_receiver.borrow_mut().as_mut() {
+            receiver.poll();
+        }
+        // The microphone is sampled as often as the buttons are polled.
+        if let (Some(microphone), Some(saadc), Some(events)) = (
+            input.microphone.borrow_mut().as_mut(),
+            input.saadc.borrow_mut().as_mut(),
+            input.events.borrow_mut().as_mut(),
+        ) {
+            microphone.poll(saadc, events);
+        }
 
-        if let Some(button_counter) = BUTTON_COUNTER.borrow(cs).borrow_mut().as_mut() {
+        DIAGNOSTICS.borrow(cs).rates.borrow_mut().count_poll();
+        if let Some(button_counter) = input.counter.borrow_mut().as_mut() {
             button_counter.reset_event(RtcInterrupt::Tick);
         }
     });
 }
 
-// Interrupt used to update the display. It will be called approximately every second.
+// Interrupt called when the supply voltage falls below the threshold of the power-fail
+// comparator. The state is saved right away, before the supply collapses.
+#[interrupt]
+fn POWER_CLOCK() {
+    cortex_m::interrupt::free(|cs| {
+        let storage = STORAGE.borrow(cs);
+        let warned = storage
+            .power_fail
+            .borrow_mut()
+            .as_mut()
+            .is_some_and(PowerFail::on_interrupt);
+        if warned {
+            let state = current_state(cs);
+            if let Some(flash) = storage.storage.borrow_mut().as_mut() {
+                flash.save_without_erase(&state);
+            }
+        }
+    });
+}
+
+// Interrupt called by the UARTE when a buffer of bytes has been received through the
+// serial interface. The bytes are pushed to a queue, which the main loop empties.
+#[interrupt]
+fn UARTE0_UART0() {
+    cortex_m::interrupt::free(|cs| {
+        let input = INPUT.borrow(cs);
+        if let Some(receiver) = input.serial_receiver.borrow_mut().as_mut() {
+            receiver.on_interrupt();
+        }
+    });
+}
+
+// Interrupt called by the I2C slave when the external controller starts or ends a
+// transfer. The registers read by the controller show the current state of the game,
+// and the changes it writes are applied like the commands of the shell.
+#[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+#[interrupt]
+fn SPIM1_SPIS1_TWIM1_TWIS1_SPI1_TWI1() {
+    cortex_m::interrupt::free(|cs| {
+        let input = INPUT.borrow(cs);
+        let controls = CONTROLS.borrow(cs);
+        let registers = Registers {
+            paused: GAME.borrow(cs).borrow().paused,
+            generation: GAME.borrow(cs).borrow().generation,
+            matrix: GAME.borrow(cs).borrow().state.matrix,
+            speed: controls.settings.borrow().get(Setting::Speed) as u8,
+            rule: controls.settings.borrow().get(Setting::Rule) as u8,
+        };
+        let write = input
+            .i2c_slave
+            .borrow_mut()
+            .as_mut()
+            .and_then(|i2c_slave| i2c_slave.on_interrupt(&registers));
+        match write {
+            Some(I2cWrite::Command(command)) => run_command(cs, command),
+            Some(I2cWrite::Grid(matrix)) => {
+                GAME.borrow(cs).borrow_mut().state.matrix = matrix;
+                show_new_grid(cs);
+            }
+            None => {}
+        }
+    });
+}
+
+// Interrupt called by the radio when a packet has been received. Besides the messages
+// of this firmware, the boards that run their own game accept the commands of the shell
+// sent as strings by the radio modules of MicroPython and MakeCode.
+#[interrupt]
+fn RADIO() {
+    cortex_m::interrupt::free(|cs| {
+        let radio = RADIO.borrow(cs);
+        let payload = radio
+            .radio
+            .borrow_mut()
+            .as_mut()
+            .and_then(Radio::on_interrupt);
+        let payload = payload.and_then(|payload| {
+            radio
+                .cipher
+                .borrow_mut()
+                .as_mut()
+                .and_then(|cipher| cipher.open(&payload, time::now_ms(cs)))
+        });
+        if let Some(payload) = payload {
+            let message = radio
+                .link
+                .borrow_mut()
+                .as_mut()
+                .map(|link| link.decode(&payload));
+            if let Some(Some(message)) = message {
+                handle_radio_message(cs, message);
+            } else if let Some(command) = radio_text::decode(&payload)
+                .and_then(|text| command::parse(text).ok())
+                .filter(|_| runs_own_game(cs))
+            {
+                run_command(cs, command);
+            }
+        }
+    });
+}
+
+// Interrupt called when the pins read through the GPIOTE change: on every rising edge
+// of the external trigger and on the presses and releases of the buttons.
+#[interrupt]
+fn GPIOTE() {
+    cortex_m::interrupt::free(|cs| {
+        let input = INPUT.borrow(cs);
+        if let Some(inputs) = input.gpiote.borrow_mut().as_mut() {
+            poll_inputs(cs, inputs);
+        }
+    });
+}
+
+// Interrupt called by the edge timer once the slot of the edges of a stitched world has
+// come.
+#[interrupt]
+fn TIMER1() {
+    cortex_m::interrupt::free(|cs| {
+        let radio = RADIO.borrow(cs);
+        let due = radio
+            .edge_timer
+            .borrow_mut()
+            .as_mut()
+            .is_some_and(|timer| timer.wait().is_ok());
+        if due {
+            send_own_edges(cs);
+        }
+    });
+}
+
+// Interrupt called every time the counter of the uptime overflows, every 512 s.
+#[interrupt]
+fn RTC2() {
+    cortex_m::interrupt::free(time::on_overflow);
+}
+
+// Interrupt used to update the display. The Compare0 event is triggered every
+// generation period and the Tick event DISPLAY_TICK_HZ times per second.
 #[interrupt]
 fn RTC1() {
     cortex_m::interrupt::free(move |cs| {
-        if let Some(display) = DISPLAY.borrow(cs).borrow_mut().as_mut() {
-            if let Some(game_state) = GAME_STATE.borrow(cs).borrow_mut().as_mut() {
-                if !*PAUSED.borrow(cs).borrow() {
-                    game_state.next_state();
-                    let image = BitImage::new(&game_state.int_matrix());
-                    display.show(&image);
+        let display = DISPLAY.borrow(cs);
+        let controls = CONTROLS.borrow(cs);
+        if let Some(display_counter) = display.counter.borrow_mut().as_mut() {
+            if display_counter.is_event_triggered(RtcInterrupt::Compare0) {
+                if game_running(cs) {
+                    next_state(cs);
+                }
+                // The period ends every generation, also while the game is paused or
+                // the menu is open, so the watchdog is pet here. If this interrupt stops
+                // being called, the board is reset.
+                if let Some(handle) = STORAGE.borrow(cs).watchdog.borrow_mut().as_mut() {
+                    handle.pet();
                 }
+
+                // This interrupt uses a counter. A the value in the counter is
+                // incremented by one with the frequency of the RTC. When the counter
+                // reaches the value in the compare register, the interrupt is called.
+                // The counter has already been cleared by the PPI, so that it starts
+                // counting from 0 again.
+                display_counter.reset_event(RtcInterrupt::Compare0);
             }
-        };
 
-        if let Some(display_counter) = DISPLAY_COUNTER.borrow(cs).borrow_mut().as_mut() {
-            display_counter.reset_event(RtcInterrupt::Compare0);
-            // This interrupt uses a counter. A the value in the counter is incremented
-            // by one with the frequency of the RTC, in this case every 125 ms. When
-            // the counter reaches the value in the compare register, in this case 8,
-            // the interrupt is called, in this case after 1 second. When this happens
-            // the counter must be cleared so that it starts counting from 0 again.
-            display_counter.clear_counter();
+            if display_counter.is_event_triggered(RtcInterrupt::Tick) {
+                let ticks = display.ticks.replace_with(|&mut old| old.wrapping_add(1));
+                DIAGNOSTICS
+                    .borrow(cs)
+                    .rates
+                    .borrow_mut()
+                    .count_display_tick();
+
+                if let Some(speaker) = display.speaker.borrow_mut().as_mut() {
+                    speaker.tick();
+                }
+
+                let mut frames = display.frames.borrow_mut();
+                poll_sensors(cs, ticks, display_counter, &mut frames);
+                let mut text = display.text.borrow_mut();
+                if let Some(scrolling_text) = text.as_mut() {
+                    // If there is text scrolling, shift it one column every
+                    // TEXT_SCROLL_TICKS. Once the text has completely left the
+                    // display it's removed.
+                    if ticks.is_multiple_of(TEXT_SCROLL_TICKS) {
+                        match scrolling_text.next() {
+                            Some(frame) => frames.draw_bits(&frame),
+                            None => {
+                                *text = None;
+                                display.fade.borrow_mut().refresh();
+                            }
+                        }
+                    }
+                } else if display.morse.borrow().is_some() {
+                    // If the generation is being read out in Morse code, send the next
+                    // unit of the code.
+                    if ticks.is_multiple_of(MORSE_UNIT_TICKS) {
+                        send_morse(cs, &mut frames);
+                    }
+                } else if let Some(menu) = controls.menu.borrow_mut().as_mut() {
+                    // If the menu is open, scroll the name of the selected item.
+                    if ticks.is_multiple_of(TEXT_SCROLL_TICKS) {
+                        frames.draw_bits(&menu.next_frame());
+                    }
+                } else if let Some(last_used) = *controls.speed_control.borrow() {
+                    // If the speed control is open, show the current speed, and close
+                    // it once it hasn't been used for SPEED_CONTROL_TICKS.
+                    if ticks.wrapping_sub(last_used) > SPEED_CONTROL_TICKS {
+                        controls.speed_control.replace(None);
+                        display.fade.borrow_mut().refresh();
+                    } else {
+                        let speed = controls.settings.borrow().get(Setting::Speed);
+                        frames.draw_brightness(&speed_matrix(speed));
+                    }
+                } else if let Some(editor) = controls.editor.borrow_mut().as_mut() {
+                    // If the editor is open, show the grid being edited with the
+                    // blinking cursor.
+                    let matrix = editor.brightness_matrix(GAME.borrow(cs).borrow().state.matrix);
+                    frames.draw_brightness(&matrix);
+                } else if *display.view.borrow() == View::PopulationBars {
+                    // If one of the population graphs has been selected, show it
+                    // instead of the cells.
+                    let matrix = display.population.borrow().bar_graph();
+                    frames.draw_brightness(&matrix);
+                } else if *display.view.borrow() == View::PopulationHistory {
+                    let matrix = display.population.borrow().history_graph();
+                    frames.draw_brightness(&matrix);
+                } else if *display.view.borrow() == View::Diagnostics {
+                    // The game keeps running while the diagnostics are shown, so the
+                    // measured rates are the real ones.
+                    if ticks.is_multiple_of(TEXT_SCROLL_TICKS) {
+                        let frame = DIAGNOSTICS.borrow(cs).rates.borrow_mut().next_frame();
+                        frames.draw_bits(&frame);
+                    }
+                } else {
+                    // Otherwise, show the next step of the transition between
+                    // generations, if there is one in progress. If there is none
+                    // and the game is paused, blink the pause indicator.
+                    let fade_matrix = display.fade.borrow_mut().next_matrix();
+                    if let Some(matrix) = fade_matrix {
+                        frames.draw_brightness(&matrix);
+                    } else if GAME.borrow(cs).borrow().paused {
+                        show_pause_indicator(cs, &mut frames, ticks);
+                    }
+                }
+
+                display_counter.reset_event(RtcInterrupt::Tick);
+            }
         };
     });
 }
+
+// Read the sensors that are polled with the display ticks: the accelerometer, the light
+// sensor and the thermometer. The display is also dimmed here once the board has been
+// left idle.
+fn poll_sensors(
+    cs: &CriticalSection,
+    ticks: u32,
+    display_counter: &mut Rtc<RTC1>,
+    frames: &mut FrameBuffer,
+) {
+    let display = DISPLAY.borrow(cs);
+    let input = INPUT.borrow(cs);
+    let controls = CONTROLS.borrow(cs);
+    // The accelerometer is read every tick, which is often enough to detect the
+    // gestures.
+    if let Some(motion) = input.motion.borrow_mut().as_mut() {
+        poll_inputs(cs, motion);
+    }
+
+    // Once no input has been received for the time of the idle setting, dim the
+    // display.
+    let idle_ms = IDLE_MS[controls.settings.borrow().get(Setting::Idle)];
+    if let Some(idle_ms) = idle_ms {
+        let last_input = *input.last_input.borrow();
+        if time::now_ms(cs) - last_input > idle_ms
+            && display.idle.borrow().is_none()
+            && display.fall_pause.borrow().is_none()
+        {
+            go_idle(cs, frames);
+        }
+    }
+    // Every LIGHT_SENSE_TICKS, measure the ambient light and adapt the brightness of
+    // the display to it. The lowest bits of the reading are mostly noise, so it's also
+    // mixed into the seeder. The display stays dimmed while the game is paused after a
+    // fall and while the board is idle.
+    #[cfg(not(feature = "pwm-display"))]
+    if ticks.is_multiple_of(LIGHT_SENSE_TICKS) {
+        if let Some(saadc) = input.saadc.borrow_mut().as_mut() {
+            let reading = light_sensor::read(saadc);
+            input.seeder.borrow_mut().add_entropy(reading as u32);
+            if display.fall_pause.borrow().is_none() && display.idle.borrow().is_none() {
+                frames.set_max_brightness(light_sensor::brightness_for_reading(reading));
+            }
+        }
+    }
+    // Every TEMP_SENSE_TICKS, measure the temperature. Its lowest bits are mixed into
+    // the seeder, and if the temperature mode is on, the speed is updated when the
+    // temperature has changed enough.
+    if ticks.is_multiple_of(TEMP_SENSE_TICKS) {
+        if let Some(thermometer) = input.thermometer.borrow_mut().as_mut() {
+            if let Some(reading) = thermometer.poll() {
+                input.seeder.borrow_mut().add_entropy(reading as u32);
+                let settings = controls.settings.borrow();
+                if TEMPERATURE_NUDGES[settings.get(Setting::Temperature)]
+                    && thermometer.update_nudge(reading)
+                {
+                    let speed = nudged_speed(settings.get(Setting::Speed), thermometer);
+                    timing::set_generation_speed(display_counter, speed);
+                }
+            }
+        }
+    }
+}
+
+// Open the settings menu, or close it if it was already open.
+fn toggle_menu(cs: &CriticalSection) {
+    let mut menu = CONTROLS.borrow(cs).menu.borrow_mut();
+    if menu.is_some() {
+        *menu = None;
+        // The cells are only drawn when they change, so they must be drawn again.
+        DISPLAY.borrow(cs).fade.borrow_mut().refresh();
+    } else {
+        *menu = Some(SettingsMenu::new());
+    }
+}
+
+// The speed of the game with the nudge of the temperature, within the available speeds.
+fn nudged_speed(speed: usize, thermometer: &Thermometer) -> usize {
+    (speed as i32 + thermometer.nudge()).clamp(0, FASTEST_SPEED as i32) as usize
+}
+
+// Close the menu and start reading out the number of the generation in Morse code.
+fn start_morse(cs: &CriticalSection) {
+    CONTROLS.borrow(cs).menu.replace(None);
+    if let Some(speaker) = DISPLAY.borrow(cs).speaker.borrow_mut().as_mut() {
+        speaker.stop();
+    }
+    let generation = GAME.borrow(cs).borrow().generation;
+    DISPLAY
+        .borrow(cs)
+        .morse
+        .replace(Some(Morse::new(generation)));
+}
+
+// Send the next unit of the Morse code on the outputs selected in the settings: the top
+// right LED, lit over the cells, and the speaker. Once the code is over, the cells are
+// drawn again.
+fn send_morse(cs: &CriticalSection, frames: &mut FrameBuffer) {
+    let output = MORSE_OUTPUTS[CONTROLS.borrow(cs).settings.borrow().get(Setting::Morse)];
+    let key_down = DISPLAY
+        .borrow(cs)
+        .morse
+        .borrow_mut()
+        .as_mut()
+        .and_then(Morse::next);
+    if key_down.is_none() {
+        DISPLAY.borrow(cs).morse.replace(None);
+        DISPLAY.borrow(cs).fade.borrow_mut().refresh();
+    }
+    let key_down = key_down.unwrap_or(false);
+
+    if output != MorseOutput::Sound {
+        let mut matrix = DISPLAY.borrow(cs).fade.borrow().current_matrix();
+        matrix[0][4] = if key_down { 9 } else { 0 };
+        frames.draw_brightness(&matrix);
+    }
+    if output != MorseOutput::Led {
+        if let Some(speaker) = DISPLAY.borrow(cs).speaker.borrow_mut().as_mut() {
+            speaker.set_morse_key(key_down);
+        }
+    }
+}
+
+// Open the speed control, which shows the speed of the game while it's changed with
+// the buttons.
+fn open_speed_control(cs: &CriticalSection) {
+    let ticks = *DISPLAY.borrow(cs).ticks.borrow();
+    CONTROLS.borrow(cs).speed_control.replace(Some(ticks));
+}
+
+fn close_speed_control(cs: &CriticalSection) {
+    CONTROLS.borrow(cs).speed_control.replace(None);
+    // The cells are only drawn when they change, so they must be drawn again.
+    DISPLAY.borrow(cs).fade.borrow_mut().refresh();
+}
+
+// Make the game one step slower or faster, within the available speeds. The new speed
+// is stored in the settings, so the settings menu shows it too.
+fn change_speed(cs: &CriticalSection, faster: bool) {
+    let speed = CONTROLS.borrow(cs).settings.borrow().get(Setting::Speed);
+    let speed = if faster {
+        (speed + 1).min(FASTEST_SPEED)
+    } else {
+        speed.saturating_sub(1)
+    };
+    CONTROLS
+        .borrow(cs)
+        .settings
+        .borrow_mut()
+        .set(Setting::Speed, speed);
+    apply_setting(cs, Setting::Speed, speed);
+    open_speed_control(cs);
+}
+
+// Image shown by the speed control: a staircase with one step per speed, from the
+// slowest on the left to the fastest on the right. The steps up to the current speed are
+// bright and the rest are dim.
+fn speed_matrix(speed: usize) -> [[u8; 5]; 5] {
+    let mut matrix = [[0; 5]; 5];
+    for (row_n, row) in matrix.iter_mut().enumerate() {
+        for (col_n, value) in row.iter_mut().enumerate() {
+            if row_n + col_n >= 4 {
+                *value = if col_n <= speed { 9 } else { 1 };
+            }
+        }
+    }
+    matrix
+}
+
+// While the menu is open, a short press of button a moves to the next item.
+fn handle_menu_button_a(cs: &CriticalSection, event: ButtonEvent) {
+    if event == ButtonEvent::ShortPress {
+        if let Some(menu) = CONTROLS.borrow(cs).menu.borrow_mut().as_mut() {
+            menu.next_item();
+        }
+    }
+}
+
+// While the menu is open, a short press of button b selects the current item: either
+// it opens the options of a setting or it applies the selected option.
+fn handle_menu_button_b(cs: &CriticalSection, event: ButtonEvent) {
+    if event == ButtonEvent::ShortPress {
+        let selection = CONTROLS
+            .borrow(cs)
+            .menu
+            .borrow_mut()
+            .as_mut()
+            .and_then(|menu| menu.select(&CONTROLS.borrow(cs).settings.borrow()));
+        if let Some((setting, value)) = selection {
+            CONTROLS
+                .borrow(cs)
+                .settings
+                .borrow_mut()
+                .set(setting, value);
+            apply_setting(cs, setting, value);
+        }
+    }
+}
+
+// Apply the new value of a setting. The rule and the edges of the grid are read from
+// the settings every generation, so they don't need to be applied.
+fn apply_setting(cs: &CriticalSection, setting: Setting, value: usize) {
+    match setting {
+        Setting::View => {
+            DISPLAY.borrow(cs).view.replace(VIEWS[value]);
+        }
+        Setting::Pattern => reset_game(cs),
+        Setting::Speed => {
+            let speed = match INPUT.borrow(cs).thermometer.borrow().as_ref() {
+                Some(thermometer) => nudged_speed(value, thermometer),
+                None => value,
+            };
+            if let Some(display_counter) = DISPLAY.borrow(cs).counter.borrow_mut().as_mut() {
+                timing::set_generation_speed(display_counter, speed);
+            }
+        }
+        Setting::Temperature => {
+            // The speed goes back to the selected one, and if the mode is on, the
+            // temperature it's nudged from is measured again.
+            if let Some(thermometer) = INPUT.borrow(cs).thermometer.borrow_mut().as_mut() {
+                thermometer.reset_nudge();
+            }
+            let speed = CONTROLS.borrow(cs).settings.borrow().get(Setting::Speed);
+            apply_setting(cs, Setting::Speed, speed);
+        }
+        Setting::Rotation => DISPLAY
+            .borrow(cs)
+            .frames
+            .borrow_mut()
+            .set_rotation(ROTATIONS[value]),
+        Setting::Sound => {
+            // The blips and the tone start with the next generation.
+            if let Some(speaker) = DISPLAY.borrow(cs).speaker.borrow_mut().as_mut() {
+                speaker.stop();
+            }
+        }
+        Setting::Morse => start_morse(cs),
+        Setting::Radio => match RADIO_ROLES[value] {
+            RadioRole::Pair => join_world(cs, Tile::PAIR),
+            RadioRole::Tile => {
+                let tile = *RADIO.borrow(cs).tile.borrow();
+                join_world(cs, tile);
+            }
+            RadioRole::Off | RadioRole::Send | RadioRole::View | RadioRole::Remote => {}
+        },
+        Setting::Sync => {
+            // A pin used by one of the enabled features can't be selected, so the
+            // setting goes back to off.
+            let pin = SYNC_PINS[value].filter(|pin| !pin.is_claimed());
+            if pin.is_none() {
+                CONTROLS
+                    .borrow(cs)
+                    .settings
+                    .borrow_mut()
+                    .set(Setting::Sync, 0);
+            }
+            DIAGNOSTICS.borrow(cs).sync_pulse.borrow_mut().select(pin);
+        }
+        Setting::Rule
+        | Setting::Edge
+        | Setting::Microphone
+        | Setting::Autosave
+        | Setting::Idle
+        | Setting::Demo => {}
+    }
+}
+
+// Button a pauses and resumes the game. A long press while the game is running resets
+// it to its initial state, and while the game is paused it opens the pattern editor.
+// While the editor is open, a short press moves the cursor to the right and a long
+// press closes the editor.
+fn handle_button_a(cs: &CriticalSection, event: ButtonEvent) {
+    let editing = CONTROLS.borrow(cs).editor.borrow().is_some();
+    let paused = GAME.borrow(cs).borrow().paused;
+    match (editing, event) {
+        (true, ButtonEvent::ShortPress) => {
+            if let Some(editor) = CONTROLS.borrow(cs).editor.borrow_mut().as_mut() {
+                editor.move_right();
+            }
+        }
+        (true, ButtonEvent::LongPress) => close_editor(cs),
+        (false, ButtonEvent::ShortPress) => {
+            GAME.borrow(cs).borrow_mut().toggle_pause();
+        }
+        (false, ButtonEvent::LongPress) if paused => {
+            CONTROLS.borrow(cs).editor.replace(Some(Editor::new()));
+        }
+        (false, ButtonEvent::LongPress) => reset_game(cs),
+        // Button a doesn't detect double clicks, and doesn't repeat any action.
+        (_, ButtonEvent::Repeat | ButtonEvent::DoubleClick) => {}
+    }
+}
+
+// Button b updates the game state shown on the screen if the game is paused, and keeps
+// updating it while it's held down. While the game is running, a long press switches
+// to the next rule. A double click fills the grid with a random soup. While the editor
+// is open, a short press selects the next brush, scrolling its name, and a long press
+// applies the brush at the cursor.
+fn handle_button_b(cs: &CriticalSection, event: ButtonEvent) {
+    let editing = CONTROLS.borrow(cs).editor.borrow().is_some();
+    let paused = GAME.borrow(cs).borrow().paused;
+    match (editing, event) {
+        (true, ButtonEvent::ShortPress) => {
+            if let Some(editor) = CONTROLS.borrow(cs).editor.borrow_mut().as_mut() {
+                let name = editor.next_brush();
+                DISPLAY
+                    .borrow(cs)
+                    .text
+                    .replace(Some(ScrollingText::new(name)));
+            }
+        }
+        (true, ButtonEvent::LongPress) => {
+            if let Some(editor) = CONTROLS.borrow(cs).editor.borrow().as_ref() {
+                let mut game = GAME.borrow(cs).borrow_mut();
+                editor.apply_brush(&mut game.state, &mut INPUT.borrow(cs).seeder.borrow_mut());
+            }
+        }
+        // Holding the button while the game is paused keeps stepping through the
+        // generations.
+        (false, ButtonEvent::ShortPress | ButtonEvent::LongPress | ButtonEvent::Repeat)
+            if paused =>
+        {
+            step_game(cs)
+        }
+        (false, ButtonEvent::LongPress) => next_rule(cs),
+        (false, ButtonEvent::DoubleClick) => randomize_game(cs),
+        _ => {}
+    }
+}
+
+// Returns the command that a remote control sends for an input event. Button a pauses
+// and resumes the game, and button b advances it one generation. Holding button a
+// makes the game slower, and holding button b makes it faster. A double click of button
+// b or shaking the board fills the grid with a random soup.
+fn remote_command(event: InputEvent) -> Option<RemoteCommand> {
+    match event {
+        InputEvent::ButtonA(ButtonEvent::ShortPress) => Some(RemoteCommand::TogglePause),
+        InputEvent::ButtonA(ButtonEvent::LongPress | ButtonEvent::Repeat) => {
+            Some(RemoteCommand::Slower)
+        }
+        InputEvent::ButtonB(ButtonEvent::ShortPress) => Some(RemoteCommand::Step),
+        InputEvent::ButtonB(ButtonEvent::LongPress | ButtonEvent::Repeat) => {
+            Some(RemoteCommand::Faster)
+        }
+        InputEvent::ButtonB(ButtonEvent::DoubleClick) | InputEvent::Shake(_) => {
+            Some(RemoteCommand::Seed)
+        }
+        _ => None,
+    }
+}
+
+// Run a command received from a remote control.
+fn run_remote_command(cs: &CriticalSection, command: RemoteCommand) {
+    match command {
+        RemoteCommand::TogglePause => {
+            GAME.borrow(cs).borrow_mut().toggle_pause();
+        }
+        RemoteCommand::Step => {
+            GAME.borrow(cs).borrow_mut().paused = true;
+            step_game(cs);
+        }
+        RemoteCommand::Seed => randomize_game(cs),
+        RemoteCommand::Slower => change_speed(cs, false),
+        RemoteCommand::Faster => change_speed(cs, true),
+    }
+}
+
+// Switch to the next rule of the list, going back to the first one after the last, and
+// scroll its name. The game waits while the name scrolls.
+fn next_rule(cs: &CriticalSection) {
+    let rule_n = (CONTROLS.borrow(cs).settings.borrow().get(Setting::Rule) + 1) % RULES.len();
+    CONTROLS
+        .borrow(cs)
+        .settings
+        .borrow_mut()
+        .set(Setting::Rule, rule_n);
+    DISPLAY
+        .borrow(cs)
+        .text
+        .replace(Some(ScrollingText::new(RULES[rule_n].name)));
+}
+
+// Read the inputs of a source and push their events to the queue, to be handled by the
+// main loop.
+fn poll_inputs(cs: &CriticalSection, inputs: &mut impl InputSource) {
+    if let Some(events) = INPUT.borrow(cs).events.borrow_mut().as_mut() {
+        inputs.poll(events);
+    }
+}
+
+// Handle an input event. While the game is running, the chord opens the speed control,
+// in which button a makes the game slower and button b faster. Otherwise, the chord and
+// the touch logo open and close the menu, except while the editor is open. While the
+// menu is open the buttons control it, otherwise they control the game. The external
+// inputs are ignored while the menu or the editor are open.
+fn handle_input(cs: &CriticalSection, event: InputEvent) {
+    let menu_open = CONTROLS.borrow(cs).menu.borrow().is_some();
+    let editing = CONTROLS.borrow(cs).editor.borrow().is_some();
+    let speed_control_open = CONTROLS.borrow(cs).speed_control.borrow().is_some();
+    let paused = GAME.borrow(cs).borrow().paused;
+    let fall_paused = DISPLAY.borrow(cs).fall_pause.borrow().is_some();
+    let microphone_mode = MICROPHONE_MODES[CONTROLS
+        .borrow(cs)
+        .settings
+        .borrow()
+        .get(Setting::Microphone)];
+    let remote = radio_role(cs) == RadioRole::Remote;
+    let shutdown = *STORAGE.borrow(cs).shutdown.borrow();
+    let idle = DISPLAY.borrow(cs).idle.borrow().is_some();
+    INPUT.borrow(cs).last_input.replace(time::now_ms(cs));
+    event_log::record(cs, LogEvent::Input(event));
+    match event {
+        // Keeping the chord pressed and releasing it switches the board off, and keeping
+        // it pressed for longer restarts it instead. After that, all the inputs are
+        // ignored.
+        _ if shutdown.is_some() => {}
+        InputEvent::ChordHold => start_shutdown(cs, Shutdown::PowerOff),
+        InputEvent::ChordLongHold => start_shutdown(cs, Shutdown::Reset),
+        // The first input after the board has gone idle only brings the display back.
+        _ if idle => wake_up(cs),
+        // Dropping or carrying the board pauses the game and dims the display, and the
+        // next press of a button resumes it.
+        InputEvent::FreeFall if !paused && !menu_open && !editing => pause_after_fall(cs),
+        InputEvent::ButtonA(_) | InputEvent::ButtonB(_) if fall_paused => resume_after_fall(cs),
+        // A remote control sends the presses of its buttons to the other boards. The
+        // settings menu is still opened as usual, to stop being a remote control.
+        InputEvent::Chord | InputEvent::Touch(ButtonEvent::ShortPress) if remote => toggle_menu(cs),
+        _ if remote && !menu_open => {
+            if let Some(command) = remote_command(event) {
+                send_radio_message(cs, RadioMessage::Remote(command));
+            }
+        }
+        InputEvent::Chord if speed_control_open => close_speed_control(cs),
+        InputEvent::Chord if !editing && !menu_open && !paused => open_speed_control(cs),
+        InputEvent::Chord | InputEvent::Touch(ButtonEvent::ShortPress) if !editing => {
+            if speed_control_open {
+                close_speed_control(cs);
+            }
+            toggle_menu(cs)
+        }
+        InputEvent::ButtonA(ButtonEvent::ShortPress) if speed_control_open => {
+            change_speed(cs, false)
+        }
+        InputEvent::ButtonB(ButtonEvent::ShortPress) if speed_control_open => {
+            change_speed(cs, true)
+        }
+        InputEvent::ButtonA(_) | InputEvent::ButtonB(_) if speed_control_open => {}
+        // Shaking the board fills the grid with a random soup, like a double click of
+        // button b. Clapping does the same, unless the microphone is in the
+        // sound-reactive mode.
+        InputEvent::Shake(noise) if !menu_open && !editing => {
+            INPUT.borrow(cs).seeder.borrow_mut().add_entropy(noise);
+            randomize_game(cs);
+        }
+        InputEvent::Clap(noise)
+            if !menu_open && !editing && microphone_mode == MicrophoneMode::Clap =>
+        {
+            INPUT.borrow(cs).seeder.borrow_mut().add_entropy(noise);
+            randomize_game(cs);
+        }
+        // Holding the touch logo launches a glider towards the north.
+        InputEvent::Touch(ButtonEvent::LongPress) if !menu_open && !editing => {
+            if let Some(north) = INPUT
+                .borrow(cs)
+                .motion
+                .borrow_mut()
+                .as_mut()
+                .and_then(Motion::north)
+            {
+                launch_glider(cs, north);
+            }
+        }
+        // Flipping the board over and back restarts the game from the grid it started
+        // with, unlike shaking it, which starts it from a new soup.
+        InputEvent::Flip if !menu_open && !editing => restart_game(cs),
+        // Tapping the board while the game is paused advances it one generation.
+        InputEvent::Tap if paused && !menu_open && !editing => step_game(cs),
+        InputEvent::ButtonA(event) if menu_open => handle_menu_button_a(cs, event),
+        InputEvent::ButtonB(event) if menu_open => handle_menu_button_b(cs, event),
+        InputEvent::ButtonA(event) => handle_button_a(cs, event),
+        InputEvent::ButtonB(event) => handle_button_b(cs, event),
+        #[cfg(feature = "ring-buttons")]
+        InputEvent::Ring(ring_n, event) if !menu_open && !editing => {
+            handle_ring(cs, RING_ACTIONS[ring_n], event)
+        }
+        // Each edge of the trigger advances the game exactly one generation. The game
+        // is paused, so that it's only clocked by the trigger, until it's resumed with
+        // button a.
+        #[cfg(feature = "trigger")]
+        InputEvent::Trigger if !menu_open && !editing => {
+            GAME.borrow(cs).borrow_mut().paused = true;
+            step_game(cs);
+        }
+        _ => {}
+    }
+}
+
+// Run the command typed in a shell, if there is one. The answer is sent outside the
+// critical section, since it blocks until it has been sent.
+fn run_shell(shell: &mut Shell<impl Terminal>) {
+    if let Some(command) = shell.read_command() {
+        let snapshot = cortex_m::interrupt::free(|cs| {
+            run_command(cs, command);
+            snapshot(cs)
+        });
+        shell.answer(command, &snapshot);
+    }
+}
+
+// Run a command typed in one of the shells. The commands that change the grid or the
+// settings behave like the equivalent inputs of the board.
+fn run_command(cs: &CriticalSection, command: Command) {
+    match command {
+        // These commands only show information, which the shell does.
+        Command::Help | Command::Show | Command::Rle | Command::Log => {}
+        Command::Save => save_state(cs),
+        Command::Rewind(n) => {
+            let saved = STORAGE
+                .borrow(cs)
+                .storage
+                .borrow()
+                .as_ref()
+                .and_then(|storage| storage.load_older(n));
+            if let Some(state) = saved {
+                restore_state(cs, state);
+                show_new_grid(cs);
+            }
+        }
+        Command::Pause => {
+            GAME.borrow(cs).borrow_mut().paused = true;
+        }
+        Command::Resume => {
+            GAME.borrow(cs).borrow_mut().paused = false;
+        }
+        Command::Step => {
+            GAME.borrow(cs).borrow_mut().paused = true;
+            step_game(cs);
+        }
+        Command::Speed(speed) => {
+            CONTROLS
+                .borrow(cs)
+                .settings
+                .borrow_mut()
+                .set(Setting::Speed, speed);
+            apply_setting(cs, Setting::Speed, speed);
+        }
+        Command::Random(density) => {
+            GAME.borrow(cs).borrow_mut().state.matrix = INPUT
+                .borrow(cs)
+                .seeder
+                .borrow_mut()
+                .random_soup_with_density(density);
+            event_log::record(cs, LogEvent::Reseed);
+            show_new_grid(cs);
+        }
+        Command::Seed(seed) => {
+            GAME.borrow(cs).borrow_mut().state.matrix = Seeder::with_seed(seed).random_soup();
+            event_log::record(cs, LogEvent::Reseed);
+            show_new_grid(cs);
+        }
+        Command::Load(pattern_n) => {
+            let mut game = GAME.borrow(cs).borrow_mut();
+            game.state.matrix = [[false; 5]; 5];
+            game.state.stamp(&PATTERNS[pattern_n], 1, 1);
+            drop(game);
+            show_new_grid(cs);
+        }
+        Command::Paste(pattern) => {
+            GAME.borrow(cs).borrow_mut().state.matrix = pattern.matrix;
+            if let Some(rule_n) = pattern.rule {
+                CONTROLS
+                    .borrow(cs)
+                    .settings
+                    .borrow_mut()
+                    .set(Setting::Rule, rule_n);
+            }
+            show_new_grid(cs);
+        }
+        Command::Frames(enabled) => {
+            if let Some(frame_stream) = DIAGNOSTICS.borrow(cs).frame_stream.borrow_mut().as_mut() {
+                frame_stream.set_enabled(enabled);
+            }
+        }
+        Command::CpuLoad(enabled) => {
+            DIAGNOSTICS.borrow(cs).cpu_load_on.replace(enabled);
+        }
+        Command::Telemetry(output) => {
+            DIAGNOSTICS.borrow(cs).telemetry_output.replace(output);
+        }
+        Command::Tile(tile) => {
+            RADIO.borrow(cs).tile.replace(tile);
+            if radio_role(cs) == RadioRole::Tile {
+                join_world(cs, tile);
+            }
+        }
+        Command::Key(key) => {
+            if let Some(cipher) = RADIO.borrow(cs).cipher.borrow_mut().as_mut() {
+                cipher.set_key(key);
+            }
+        }
+        Command::Sync(pin) => {
+            let value = SYNC_PINS.iter().position(|&p| p == pin).unwrap_or(0);
+            CONTROLS
+                .borrow(cs)
+                .settings
+                .borrow_mut()
+                .set(Setting::Sync, value);
+            apply_setting(cs, Setting::Sync, value);
+        }
+        Command::Rule(rule_n) => CONTROLS
+            .borrow(cs)
+            .settings
+            .borrow_mut()
+            .set(Setting::Rule, rule_n),
+    }
+}
+
+// Save the grid and the settings that change how it evolves to flash.
+fn save_state(cs: &CriticalSection) {
+    let state = current_state(cs);
+    if let Some(storage) = STORAGE.borrow(cs).storage.borrow_mut().as_mut() {
+        storage.save(&state);
+    }
+}
+
+// Returns the state of the game that is saved to flash.
+fn current_state(cs: &CriticalSection) -> SavedState {
+    let settings = CONTROLS.borrow(cs).settings.borrow();
+    SavedState {
+        matrix: GAME.borrow(cs).borrow().state.matrix,
+        rule: settings.get(Setting::Rule),
+        speed: settings.get(Setting::Speed),
+        edge: settings.get(Setting::Edge),
+    }
+}
+
+// Restore a state saved in flash, when the board starts or to rewind the game.
+// Corrupted records are dropped by the storage, and a record with values out of range
+// is ignored too, so the board starts with the defaults instead.
+fn restore_state(cs: &CriticalSection, state: SavedState) {
+    if state.rule >= RULES.len() || state.speed > FASTEST_SPEED || state.edge >= BOUNDARIES.len() {
+        return;
+    }
+    let mut game = GAME.borrow(cs).borrow_mut();
+    game.state.matrix = state.matrix;
+    game.run_start = state.matrix;
+    drop(game);
+    let mut settings = CONTROLS.borrow(cs).settings.borrow_mut();
+    settings.set(Setting::Rule, state.rule);
+    settings.set(Setting::Edge, state.edge);
+    settings.set(Setting::Speed, state.speed);
+    drop(settings);
+    apply_setting(cs, Setting::Speed, state.speed);
+}
+
+// Returns the state of the game, to be shown by the shell.
+fn snapshot(cs: &CriticalSection) -> Snapshot {
+    Snapshot {
+        matrix: GAME.borrow(cs).borrow().state.matrix,
+        generation: GAME.borrow(cs).borrow().generation,
+        paused: GAME.borrow(cs).borrow().paused,
+        rule: CONTROLS.borrow(cs).settings.borrow().get(Setting::Rule),
+    }
+}
+
+// Save the state, close whatever is open and scroll the goodbye message, after which
+// the main loop switches the board off or restarts it.
+fn start_shutdown(cs: &CriticalSection, shutdown: Shutdown) {
+    GAME.borrow(cs).borrow_mut().paused = true;
+    save_state(cs);
+    CONTROLS.borrow(cs).menu.replace(None);
+    CONTROLS.borrow(cs).editor.replace(None);
+    CONTROLS.borrow(cs).speed_control.replace(None);
+    DISPLAY.borrow(cs).morse.replace(None);
+    if let Some(speaker) = DISPLAY.borrow(cs).speaker.borrow_mut().as_mut() {
+        speaker.stop();
+    }
+    let message = match shutdown {
+        Shutdown::PowerOff => "BYE",
+        Shutdown::Reset => "RESET",
+    };
+    DISPLAY
+        .borrow(cs)
+        .text
+        .replace(Some(ScrollingText::new(message)));
+    STORAGE.borrow(cs).shutdown.replace(Some(shutdown));
+}
+
+// Dim the display after the board has been left idle, keeping the brightness it had to
+// restore it later. In the demo mode, whatever is open is closed and the game runs.
+fn go_idle(cs: &CriticalSection, frames: &mut FrameBuffer) {
+    DISPLAY
+        .borrow(cs)
+        .idle
+        .replace(Some(frames.max_brightness()));
+    frames.set_max_brightness(IDLE_BRIGHTNESS);
+    if DEMO_MODES[CONTROLS.borrow(cs).settings.borrow().get(Setting::Demo)] {
+        CONTROLS.borrow(cs).menu.replace(None);
+        CONTROLS.borrow(cs).editor.replace(None);
+        CONTROLS.borrow(cs).speed_control.replace(None);
+        GAME.borrow(cs).borrow_mut().paused = false;
+        DISPLAY.borrow(cs).fade.borrow_mut().refresh();
+    }
+}
+
+// Restore the brightness of the display when an input is received while idle. The game
+// keeps running in the demo mode.
+fn wake_up(cs: &CriticalSection) {
+    if let Some(max_brightness) = DISPLAY.borrow(cs).idle.replace(None) {
+        DISPLAY
+            .borrow(cs)
+            .frames
+            .borrow_mut()
+            .set_max_brightness(max_brightness);
+    }
+}
+
+// Pause the game and dim the display after the board has fallen, keeping the brightness
+// it had to restore it later.
+fn pause_after_fall(cs: &CriticalSection) {
+    let mut frames = DISPLAY.borrow(cs).frames.borrow_mut();
+    DISPLAY
+        .borrow(cs)
+        .fall_pause
+        .replace(Some(frames.max_brightness()));
+    frames.set_max_brightness(FALL_PAUSE_BRIGHTNESS);
+    GAME.borrow(cs).borrow_mut().paused = true;
+}
+
+// Resume the game paused by a fall and restore the brightness of the display.
+fn resume_after_fall(cs: &CriticalSection) {
+    if let Some(max_brightness) = DISPLAY.borrow(cs).fall_pause.replace(None) {
+        DISPLAY
+            .borrow(cs)
+            .frames
+            .borrow_mut()
+            .set_max_brightness(max_brightness);
+    }
+    GAME.borrow(cs).borrow_mut().paused = false;
+}
+
+// Perform the action assigned to an external button of the rings.
+#[cfg(feature = "ring-buttons")]
+fn handle_ring(cs: &CriticalSection, action: RingAction, event: ButtonEvent) {
+    match (action, event) {
+        (RingAction::PauseResume, ButtonEvent::ShortPress) => {
+            GAME.borrow(cs).borrow_mut().toggle_pause();
+        }
+        (
+            RingAction::Step,
+            ButtonEvent::ShortPress | ButtonEvent::LongPress | ButtonEvent::Repeat,
+        ) => {
+            GAME.borrow(cs).borrow_mut().paused = true;
+            step_game(cs);
+        }
+        (RingAction::Reset, ButtonEvent::ShortPress) => reset_game(cs),
+        _ => {}
+    }
+}
+
+// Close the pattern editor and show the edited grid.
+fn close_editor(cs: &CriticalSection) {
+    CONTROLS.borrow(cs).editor.replace(None);
+    show_new_grid(cs);
+}
+
+// Reset the game to the pattern selected in the settings: either its initial state or
+// one of the patterns of the library, stamped on an empty grid.
+fn reset_game(cs: &CriticalSection) {
+    let mut game = GAME.borrow(cs).borrow_mut();
+    match CONTROLS.borrow(cs).settings.borrow().get(Setting::Pattern) {
+        0 => game.state.matrix = INITIAL_STATE_MATRIX,
+        pattern_n => {
+            game.state.matrix = [[false; 5]; 5];
+            game.state.stamp(&PATTERNS[pattern_n - 1], 1, 1);
+        }
+    }
+    drop(game);
+    show_new_grid(cs);
+}
+
+// Replace the grid with a random soup.
+fn randomize_game(cs: &CriticalSection) {
+    GAME.borrow(cs).borrow_mut().state.matrix = INPUT.borrow(cs).seeder.borrow_mut().random_soup();
+    event_log::record(cs, LogEvent::Reseed);
+    show_new_grid(cs);
+}
+
+// Replace the grid with a single glider, at the corner from which it crosses the whole
+// grid in the given direction.
+fn launch_glider(cs: &CriticalSection, direction: Diagonal) {
+    let mut game = GAME.borrow(cs).borrow_mut();
+    let (row, col) = direction.launch_corner();
+    game.state.matrix = [[false; 5]; 5];
+    game.state.stamp(direction.glider(), row, col);
+    drop(game);
+    show_new_grid(cs);
+}
+
+// Replace the grid with the one the current run started with.
+fn restart_game(cs: &CriticalSection) {
+    GAME.borrow(cs).borrow_mut().restart_run();
+    show_new_grid(cs);
+}
+
+// Start showing a grid that has been replaced, rather than reached by a generation. The
+// trail is cleared, since the cells that were dying before are not relevant anymore,
+// and the display fades to the new grid, which becomes the start of a new run.
+fn show_new_grid(cs: &CriticalSection) {
+    GAME.borrow(cs).borrow_mut().start_run();
+    let game_state = GAME.borrow(cs).borrow().state;
+    send_frame(cs, game_state.matrix);
+    send_telemetry(
+        cs,
+        Message::Grid {
+            generation: 0,
+            matrix: game_state.matrix,
+        },
+    );
+    broadcast(cs, 0, game_state.matrix);
+    let trail = DeathTrail::new();
+    DISPLAY
+        .borrow(cs)
+        .fade
+        .borrow_mut()
+        .start(trail.brightness_matrix(game_state.matrix));
+    DISPLAY.borrow(cs).trail.replace(trail);
+    // The new cells start counting their age from scratch.
+    #[cfg(feature = "ws2812")]
+    {
+        let mut ages = CellAges::new();
+        ages.update(game_state.matrix);
+        DISPLAY.borrow(cs).ages.replace(ages);
+    }
+}
+
+// Advance the game one generation by hand, e.g., with button b while it's paused. The
+// boards of a stitched world advance in lockstep, each after its own generation period,
+// so the manual steps are ignored while the board is stitched, otherwise it would get a
+// generation ahead of the boards around it.
+fn step_game(cs: &CriticalSection) {
+    if !radio_role(cs).is_stitched() {
+        next_state(cs);
+    }
+}
+
+// Advance the game to the next generation and start the transition to its image. The
+// trail of the cells that have just died is updated too, so that they keep glowing for
+// a few generations. It must be called inside a critical section, since it needs the
+// cs token to access the global variables.
+fn next_state(cs: &CriticalSection) {
+    // In the demo mode, the grid is replaced with a new soup once it has died out or
+    // after DEMO_RUN_GENERATIONS, so there is always something to watch.
+    let demo = DISPLAY.borrow(cs).idle.borrow().is_some()
+        && DEMO_MODES[CONTROLS.borrow(cs).settings.borrow().get(Setting::Demo)];
+    if demo {
+        let (population, generation) = {
+            let game = GAME.borrow(cs).borrow();
+            (game.state.population(), game.generation)
+        };
+        if population == 0 || generation >= DEMO_RUN_GENERATIONS {
+            randomize_game(cs);
+            return;
+        }
+    }
+    let settings = CONTROLS.borrow(cs).settings.borrow();
+    // In a stitched world, the cells around the grid are the edges of the boards
+    // around it. The game waits until they have been received, sending the edges of
+    // this board again in case they have been lost, and asking the other boards to
+    // send theirs again.
+    let boundary = if radio_role(cs).is_stitched() {
+        let border = RADIO.borrow(cs).stitch.borrow().border();
+        match border {
+            Some(border) => Boundary::Tiled(border),
+            None => {
+                let (generation, _, _) = RADIO.borrow(cs).stitch.borrow().own_edges();
+                RADIO.borrow(cs).stitch.borrow_mut().wait();
+                send_edges(cs);
+                send_radio_message(cs, RadioMessage::Resend { generation });
+                return;
+            }
+        }
+    } else {
+        BOUNDARIES[settings.get(Setting::Edge)]
+    };
+    // The grid is advanced on a copy, which is stored back at the end, so the
+    // functions called in between can borrow the run of the game.
+    let mut game_state = GAME.borrow(cs).borrow().state;
+    let previous_matrix = game_state.matrix;
+    let generation = GAME.borrow(cs).borrow_mut().next_generation();
+    if let Some(period) = AUTOSAVE_PERIODS[settings.get(Setting::Autosave)] {
+        if generation.is_multiple_of(period) {
+            STORAGE.borrow(cs).autosave_due.replace(true);
+        }
+    }
+    let changes = game_state.next_state(&RULES[settings.get(Setting::Rule)], boundary);
+    DIAGNOSTICS.borrow(cs).sync_pulse.borrow().toggle();
+    // In the sound-reactive mode, each column gets random cells, as many as the
+    // loudness of one of the last time slices.
+    if MICROPHONE_MODES[settings.get(Setting::Microphone)] == MicrophoneMode::Levels {
+        if let Some(microphone) = INPUT.borrow(cs).microphone.borrow().as_ref() {
+            inject_cells(cs, &mut game_state, microphone.levels());
+        }
+    }
+    if let Some(speaker) = DISPLAY.borrow(cs).speaker.borrow_mut().as_mut() {
+        match SOUNDS[settings.get(Setting::Sound)] {
+            Sound::Off => {}
+            Sound::Blips => speaker.play_changes(changes),
+            Sound::Pitch => speaker.play_population(game_state.population()),
+        }
+    }
+    GAME.borrow(cs).borrow_mut().state = game_state;
+    show_generation(cs, &game_state, previous_matrix, changes);
+}
+
+// Start showing the grid of a new generation and report it, given the grid of the
+// previous one and the changes between them.
+fn show_generation(
+    cs: &CriticalSection,
+    game_state: &LifeState,
+    previous_matrix: [[bool; 5]; 5],
+    changes: Changes,
+) {
+    let mut trail = DISPLAY.borrow(cs).trail.borrow_mut();
+    trail.update(previous_matrix, game_state.matrix);
+    #[cfg(feature = "ws2812")]
+    DISPLAY
+        .borrow(cs)
+        .ages
+        .borrow_mut()
+        .update(game_state.matrix);
+    DISPLAY
+        .borrow(cs)
+        .population
+        .borrow_mut()
+        .record(game_state.population());
+    DIAGNOSTICS.borrow(cs).rates.borrow_mut().count_generation();
+    send_frame(cs, game_state.matrix);
+    let generation = GAME.borrow(cs).borrow().generation;
+    send_telemetry(
+        cs,
+        Message::Grid {
+            generation,
+            matrix: game_state.matrix,
+        },
+    );
+    send_telemetry(
+        cs,
+        Message::Stats {
+            generation,
+            population: game_state.population(),
+            changes,
+            uptime_ms: time::now_ms(cs) as u32,
+        },
+    );
+
+    broadcast(cs, generation, game_state.matrix);
+
+    DISPLAY
+        .borrow(cs)
+        .fade
+        .borrow_mut()
+        .start(trail.brightness_matrix(game_state.matrix));
+}
+
+// Returns what the board does with the radio.
+fn radio_role(cs: &CriticalSection) -> RadioRole {
+    RADIO_ROLES[CONTROLS.borrow(cs).settings.borrow().get(Setting::Radio)]
+}
+
+// Send the grid of a new generation over the radio: the whole grid if the board is
+// broadcasting it, or its edges if it's part of a stitched world.
+fn broadcast(cs: &CriticalSection, generation: u32, matrix: [[bool; 5]; 5]) {
+    match radio_role(cs) {
+        RadioRole::Send => {
+            send_radio_message(cs, RadioMessage::Grid { generation, matrix });
+            // The population is also sent to the programs of MicroPython and MakeCode.
+            let population = matrix.iter().flatten().filter(|&&alive| alive).count();
+            let mut text = heapless::String::<16>::new();
+            write!(text, "population {}", population).ok();
+            send_payload(cs, &radio_text::encode_micropython(&text));
+            send_payload(
+                cs,
+                &radio_text::encode_makecode_value("pop", population as i32),
+            );
+        }
+        RadioRole::Pair | RadioRole::Tile => {
+            RADIO
+                .borrow(cs)
+                .stitch
+                .borrow_mut()
+                .record(generation, matrix);
+            send_edges(cs);
+        }
+        RadioRole::Off | RadioRole::View | RadioRole::Remote => {}
+    }
+}
+
+// Send the edges of the current generation of the board to the boards around it in the
+// stitched world.
+fn send_edges(cs: &CriticalSection) {
+    // The boards that are released at the same time send their edges at the same time,
+    // and so do the ones that answer a resend request. Each one waits for a random
+    // number of slots first, so that the packets don't collide. The wait is timed by the
+    // edge timer, whose interrupt sends them, so the rest of the interrupts aren't
+    // blocked. If the edges are already waiting to be sent, the wait starts again, and
+    // the edges sent are the last ones.
+    let slot = INPUT.borrow(cs).seeder.borrow_mut().next_below(RADIO_SLOTS);
+    match RADIO.borrow(cs).edge_timer.borrow_mut().as_mut() {
+        Some(timer) if slot > 0 => timer.start(slot * RADIO_SLOT_US),
+        _ => send_own_edges(cs),
+    }
+}
+
+// Send the edges of the last generation recorded by the stitched world.
+fn send_own_edges(cs: &CriticalSection) {
+    let stitch = RADIO.borrow(cs).stitch.borrow();
+    let (generation, edges, previous_edges) = stitch.own_edges();
+    let tile = stitch.tile();
+    send_radio_message(
+        cs,
+        RadioMessage::Edges {
+            generation,
+            position: (tile.x, tile.y),
+            edges,
+            previous_edges,
+        },
+    );
+}
+
+// Join a world shared by several boards at the given tile. The board starts by sending
+// its edges, so the boards around it can advance.
+fn join_world(cs: &CriticalSection, tile: Tile) {
+    RADIO.borrow(cs).stitch.borrow_mut().set_tile(tile);
+    let (generation, matrix) = {
+        let game = GAME.borrow(cs).borrow();
+        (game.generation, game.state.matrix)
+    };
+    broadcast(cs, generation, matrix);
+}
+
+fn send_radio_message(cs: &CriticalSection, message: RadioMessage) {
+    if let Some(link) = RADIO.borrow(cs).link.borrow_mut().as_mut() {
+        send_payload(cs, &link.encode(&message));
+    }
+}
+
+// Send a packet, encrypted if there is a key.
+fn send_payload(cs: &CriticalSection, payload: &Payload) {
+    let payload = RADIO
+        .borrow(cs)
+        .cipher
+        .borrow_mut()
+        .as_mut()
+        .and_then(|cipher| cipher.seal(payload));
+    if let (Some(radio), Some(payload)) = (RADIO.borrow(cs).radio.borrow_mut().as_mut(), payload) {
+        radio.send(&payload);
+    }
+}
+
+// Handle a message received by the radio. A viewer shows the grids it receives as the
+// generations of its own game, a board of a stitched world keeps the edges of the
+// boards around it, and the boards that run their own game follow the commands of
+// remote controls.
+fn handle_radio_message(cs: &CriticalSection, message: RadioMessage) {
+    match message {
+        RadioMessage::Grid { generation, matrix } if radio_role(cs) == RadioRole::View => {
+            let mut game = GAME.borrow(cs).borrow_mut();
+            let previous_matrix = core::mem::replace(&mut game.state.matrix, matrix);
+            game.generation = generation;
+            let game_state = game.state;
+            drop(game);
+            let changes = Changes::between(previous_matrix, matrix);
+            show_generation(cs, &game_state, previous_matrix, changes);
+        }
+        RadioMessage::Edges {
+            generation,
+            position,
+            edges,
+            previous_edges,
+        } if radio_role(cs).is_stitched() => {
+            // The boards of a pair don't know their positions, so the other board is
+            // always at the other tile.
+            let position = if radio_role(cs) == RadioRole::Pair {
+                (1, 0)
+            } else {
+                position
+            };
+            let jump = RADIO.borrow(cs).stitch.borrow_mut().receive(
+                position,
+                generation,
+                edges,
+                previous_edges,
+            );
+            if let Some(generation) = jump {
+                GAME.borrow(cs).borrow_mut().generation = generation;
+            }
+            // A board that was waiting for these edges advances right away, and its
+            // period starts again, so it keeps in step with the boards around it.
+            if RADIO.borrow(cs).stitch.borrow().is_released() && game_running(cs) {
+                next_state(cs);
+                if let Some(display_counter) = DISPLAY.borrow(cs).counter.borrow_mut().as_mut() {
+                    display_counter.clear_counter();
+                }
+            }
+        }
+        RadioMessage::Remote(command) if runs_own_game(cs) => run_remote_command(cs, command),
+        // The edges of the generation that is asked for are in the packets of that
+        // generation and of the next one.
+        RadioMessage::Resend { generation } if radio_role(cs).is_stitched() => {
+            let (own_generation, _, _) = RADIO.borrow(cs).stitch.borrow().own_edges();
+            if own_generation == generation || own_generation == generation.wrapping_add(1) {
+                send_edges(cs);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Returns true if the board runs its own game, unlike a viewer or a remote control.
+fn runs_own_game(cs: &CriticalSection) -> bool {
+    !matches!(radio_role(cs), RadioRole::View | RadioRole::Remote)
+}
+
+// Returns true if the game advances every period. The game waits while text scrolls or
+// the generation is read out, a viewer only shows the generations it receives, and a
+// remote control doesn't run a game.
+fn game_running(cs: &CriticalSection) -> bool {
+    !GAME.borrow(cs).borrow().paused
+        && DISPLAY.borrow(cs).text.borrow().is_none()
+        && DISPLAY.borrow(cs).morse.borrow().is_none()
+        && runs_own_game(cs)
+}
+
+// Returns the parts of the state of the game that are reported by the telemetry.
+fn game_status_now(cs: &CriticalSection) -> GameStatus {
+    let settings = CONTROLS.borrow(cs).settings.borrow();
+    GameStatus {
+        paused: GAME.borrow(cs).borrow().paused,
+        rule: settings.get(Setting::Rule) as u8,
+        speed: settings.get(Setting::Speed) as u8,
+    }
+}
+
+// Push a message to the queue of telemetry, to be sent by the main loop. If the queue
+// is full, the message is lost.
+fn send_telemetry(cs: &CriticalSection, message: Message) {
+    if let Some(telemetry) = DIAGNOSTICS.borrow(cs).telemetry.borrow_mut().as_mut() {
+        telemetry.enqueue(message).ok();
+    }
+}
+
+// Send a grid as the frame of the current generation to the stream of frames, if it's
+// enabled, and to the SPI recorder, if there is one.
+fn send_frame(cs: &CriticalSection, matrix: [[bool; 5]; 5]) {
+    let generation = GAME.borrow(cs).borrow().generation;
+    if let Some(frame_stream) = DIAGNOSTICS.borrow(cs).frame_stream.borrow_mut().as_mut() {
+        frame_stream.send(generation, matrix);
+    }
+    #[cfg(all(feature = "spi-recorder", not(feature = "max7219")))]
+    if let Some(spi_recorder) = DIAGNOSTICS.borrow(cs).spi_recorder.borrow_mut().as_mut() {
+        spi_recorder.record(generation, matrix);
+    }
+}
+
+// Add random cells to each column of the grid, as many as the given loudness (from 0 to
+// 5) on average.
+fn inject_cells(cs: &CriticalSection, game_state: &mut LifeState, levels: [u8; 5]) {
+    let mut seeder = INPUT.borrow(cs).seeder.borrow_mut();
+    for (col_n, level) in levels.into_iter().enumerate() {
+        let column = seeder.random_column(level);
+        for (row, alive) in game_state.matrix.iter_mut().zip(column) {
+            row[col_n] |= alive;
+        }
+    }
+}
+
+// Returns whether the display is showing the cells of the game, and not text, the
+// editor or one of the other views.
+#[cfg(feature = "ws2812")]
+fn showing_cells(cs: &CriticalSection) -> bool {
+    DISPLAY.borrow(cs).text.borrow().is_none()
+        && CONTROLS.borrow(cs).editor.borrow().is_none()
+        && *DISPLAY.borrow(cs).view.borrow() == View::Cells
+}
+
+// Blink the top right LED while the game is paused, so that it's possible to tell a
+// paused game from a game that has reached a still life. The LED is inverted with
+// respect to the cell it represents every PAUSE_BLINK_TICKS display ticks.
+fn show_pause_indicator(cs: &CriticalSection, frames: &mut FrameBuffer, ticks: u32) {
+    if !ticks.is_multiple_of(PAUSE_BLINK_TICKS) {
+        return;
+    }
+
+    let mut matrix = DISPLAY.borrow(cs).fade.borrow().current_matrix();
+    if (ticks / PAUSE_BLINK_TICKS) % 2 == 1 {
+        matrix[0][4] = if matrix[0][4] > 0 { 0 } else { 9 };
+    }
+    frames.draw_brightness(&matrix);
+}
@@ -0,0 +1,93 @@
+use crate::framebuffer::LedMatrix;
+use crate::my_board::SpiPins;
+use microbit::{
+    hal::{
+        gpio::{Output, Pin, PushPull},
+        spim::{self, Frequency, Spim},
+    },
+    pac::SPIM3,
+};
+
+// Registers of the MAX7219. The rows of the matrix are the digit registers, from
+// DIGIT0 to DIGIT0 + 7, and each bit of a row is one of its LEDs.
+const DIGIT0: u8 = 0x01;
+const DECODE_MODE: u8 = 0x09;
+const INTENSITY: u8 = 0x0a;
+const SCAN_LIMIT: u8 = 0x0b;
+const SHUTDOWN: u8 = 0x0c;
+const DISPLAY_TEST: u8 = 0x0f;
+
+// Intensity of the LEDs, from 0x0 to 0xf. The MAX7219 has no per LED brightness, so
+// this is the brightness of all of them.
+const MATRIX_INTENSITY: u8 = 0x04;
+// The LEDs of the matrix can only be on or off, so the LEDs of the game with at least
+// this brightness are turned on and the rest are turned off. With the default
+// brightness values, the live cells and the brightest step of the death trail are on.
+const MIN_LIT_BRIGHTNESS: u8 = 5;
+// The 5x5 images of the game are shown at the center of the 8x8 matrix, with this
+// number of empty rows and columns above and to the left of them.
+const OFFSET: usize = 1;
+
+// External 8x8 LED matrix driven by a MAX7219, connected to the SPI bus of the edge
+// connector (SCK on pin 13, DIN on pin 15 and CS/LOAD on pin 16). It mirrors the images
+// shown on the on-board display.
+// The MAX7219 receives 16 bit commands, each with the address of a register followed by
+// the value to write in it. It refreshes the LEDs on its own, so the matrix only needs
+// to be written when the image changes.
+pub struct Max7219 {
+    spim: Spim<SPIM3>,
+    cs: Pin<Output<PushPull>>,
+}
+
+impl Max7219 {
+    pub fn new(spim3: SPIM3, pins: SpiPins) -> Self {
+        let spim = Spim::new(
+            spim3,
+            spim::Pins {
+                sck: pins.sck.degrade(),
+                mosi: Some(pins.mosi.degrade()),
+                miso: None,
+            },
+            Frequency::M1,
+            spim::MODE_0,
+            0,
+        );
+        let mut matrix = Self {
+            spim,
+            cs: pins.cs.degrade(),
+        };
+        // Leave the test mode, show the bits of the digit registers as they are, scan
+        // the 8 rows and wake the driver up.
+        matrix.write_register(DISPLAY_TEST, 0);
+        matrix.write_register(DECODE_MODE, 0);
+        matrix.write_register(SCAN_LIMIT, 7);
+        matrix.write_register(INTENSITY, MATRIX_INTENSITY);
+        for row in 0..8 {
+            matrix.write_register(DIGIT0 + row, 0);
+        }
+        matrix.write_register(SHUTDOWN, 1);
+        matrix
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) {
+        // If the transfer fails there is nothing that can be done, the matrix just keeps
+        // showing the previous image.
+        self.spim.write(&mut self.cs, &[register, value]).ok();
+    }
+}
+
+impl LedMatrix for Max7219 {
+    fn show_brightness(&mut self, brightness: &[[u8; 5]; 5]) {
+        for (row_n, row) in brightness.iter().enumerate() {
+            // The most significant bit of a row is its leftmost LED.
+            let bits = row.iter().enumerate().fold(0, |bits, (col_n, &value)| {
+                if value >= MIN_LIT_BRIGHTNESS {
+                    bits | (0x80 >> (col_n + OFFSET))
+                } else {
+                    bits
+                }
+            });
+            self.write_register(DIGIT0 + (row_n + OFFSET) as u8, bits);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::settings::{Setting, Settings, SETTINGS, SETTING_NAMES};
+use crate::text::ScrollingText;
+
+// Menu shown on the display. The name of the selected item scrolls across the display
+// over and over, until another item is selected or the menu is closed. The items are
+// given as a list of names, and the menu only keeps track of which one is selected, so
+// it's up to the caller to act on the selection.
+pub struct Menu {
+    items: &'static [&'static str],
+    // Index of the selected item:
+    selected: usize,
+    text: ScrollingText,
+}
+
+impl Menu {
+    // Create a menu with the given item selected.
+    pub fn new(items: &'static [&'static str], selected: usize) -> Self {
+        Self {
+            items,
+            selected,
+            text: ScrollingText::new(items[selected]),
+        }
+    }
+
+    // Select the next item, going back to the first one after the last.
+    pub fn next_item(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+        self.text = ScrollingText::new(self.items[self.selected]);
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    // Returns the next frame of the scrolling name of the selected item. Once the name
+    // has left the display, it starts scrolling again.
+    pub fn next_frame(&mut self) -> [[u8; 5]; 5] {
+        match self.text.next() {
+            Some(frame) => frame,
+            None => {
+                self.text = ScrollingText::new(self.items[self.selected]);
+                self.text.next().unwrap_or([[0; 5]; 5])
+            }
+        }
+    }
+}
+
+// Two-level settings menu. The first level lists the settings and the second one the
+// options of the selected setting. Selecting a setting opens the list of its options,
+// with its current value selected, and selecting an option goes back to the list of
+// settings.
+pub struct SettingsMenu {
+    // The setting whose options are listed, or None if the settings are listed:
+    setting: Option<Setting>,
+    menu: Menu,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        Self {
+            setting: None,
+            menu: Menu::new(&SETTING_NAMES, 0),
+        }
+    }
+
+    pub fn next_item(&mut self) {
+        self.menu.next_item();
+    }
+
+    // Select the current item. If it's an option, the new value of the setting is
+    // returned so the caller can apply it.
+    pub fn select(&mut self, settings: &Settings) -> Option<(Setting, usize)> {
+        match self.setting {
+            None => {
+                let setting = SETTINGS[self.menu.selected()];
+                self.setting = Some(setting);
+                self.menu = Menu::new(setting.option_names(), settings.get(setting));
+                None
+            }
+            Some(setting) => {
+                let value = self.menu.selected();
+                let setting_n = SETTINGS.iter().position(|&s| s == setting).unwrap_or(0);
+                self.setting = None;
+                self.menu = Menu::new(&SETTING_NAMES, setting_n);
+                Some((setting, value))
+            }
+        }
+    }
+
+    pub fn next_frame(&mut self) -> [[u8; 5]; 5] {
+        self.menu.next_frame()
+    }
+}
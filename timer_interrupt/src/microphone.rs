@@ -0,0 +1,100 @@
+use crate::input::{EventProducer, InputEvent};
+use crate::my_board::MicrophonePins;
+use embedded_hal::adc::OneShot;
+use microbit::hal::saadc::Saadc;
+
+// Difference between a reading and the quiet level of the microphone above which the
+// sound is considered a clap. This value is approximate and may need to be adjusted for
+// each board.
+const CLAP_AMPLITUDE: i32 = 2000;
+// The quiet level follows the readings slowly: each reading moves it 1/16 of the way.
+const LEVEL_SHIFT: u32 = 4;
+// Number of samples after a clap during which no other clap is detected. The microphone
+// is sampled with the buttons, about 166 times per second, so this is about 1 s.
+const CLAP_COOLDOWN_SAMPLES: u8 = 166;
+// Number of time slices whose loudness is kept, one per column of the grid, and number
+// of samples of each slice (about 200 ms).
+const SLICES: usize = 5;
+const SLICE_SAMPLES: u8 = 33;
+// Amplitude that corresponds to each level of loudness, so that a clap is the loudest
+// level (5).
+const AMPLITUDE_PER_LEVEL: i32 = CLAP_AMPLITUDE / SLICES as i32;
+
+// What the microphone is used for. The mode is selected in the settings menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MicrophoneMode {
+    // A clap fills the grid with a random soup.
+    Clap,
+    // Every generation, random cells are added to the grid, more the louder the sound.
+    Levels,
+}
+
+// Detects claps with the microphone of the micro:bit v2. The microphone is read with
+// the SAADC, and a clap is a reading far from the quiet level of the signal. A clap is a
+// short burst of a loud sound, and sampling the signal even a few hundred times per
+// second is enough to catch one of its peaks.
+// It also keeps the loudness of the last time slices, which the sound-reactive mode
+// turns into cells.
+// The SAADC is shared with the light sensor, so it's passed to poll() instead of being
+// owned by the microphone, which is why the microphone is not an InputSource.
+pub struct Microphone {
+    pins: MicrophonePins,
+    // Quiet level of the signal, multiplied by 2^LEVEL_SHIFT to keep some precision. It's
+    // None until the first reading:
+    level: Option<i32>,
+    // Number of samples left before a new clap can be detected:
+    cooldown_samples: u8,
+    // Maximum amplitude of each of the last time slices, from the oldest to the current
+    // one, and number of samples of the current slice:
+    slice_amplitudes: [i32; SLICES],
+    slice_samples: u8,
+}
+
+impl Microphone {
+    pub fn new(pins: MicrophonePins) -> Self {
+        Self {
+            pins,
+            level: None,
+            cooldown_samples: 0,
+            slice_amplitudes: [0; SLICES],
+            slice_samples: 0,
+        }
+    }
+
+    // Returns the loudness of each of the last time slices, from 0 (quiet) to 5 (a
+    // clap), starting with the oldest.
+    pub fn levels(&self) -> [u8; SLICES] {
+        self.slice_amplitudes
+            .map(|amplitude| (amplitude / AMPLITUDE_PER_LEVEL).min(SLICES as i32) as u8)
+    }
+
+    // Read the microphone and push a clap event to the queue if it hears one.
+    pub fn poll(&mut self, saadc: &mut Saadc, events: &mut EventProducer) {
+        let Ok(reading) = saadc.read(&mut self.pins.input) else {
+            return;
+        };
+        let reading = reading as i32;
+        let level = self.level.get_or_insert(reading << LEVEL_SHIFT);
+        let amplitude = (reading - (*level >> LEVEL_SHIFT)).abs();
+        *level += reading - (*level >> LEVEL_SHIFT);
+
+        if self.slice_samples == SLICE_SAMPLES {
+            // Start a new slice, dropping the oldest one.
+            self.slice_amplitudes.rotate_left(1);
+            self.slice_amplitudes[SLICES - 1] = 0;
+            self.slice_samples = 0;
+        }
+        self.slice_samples += 1;
+        let current = &mut self.slice_amplitudes[SLICES - 1];
+        *current = (*current).max(amplitude);
+
+        if self.cooldown_samples > 0 {
+            self.cooldown_samples -= 1;
+        } else if amplitude > CLAP_AMPLITUDE {
+            self.cooldown_samples = CLAP_COOLDOWN_SAMPLES;
+            // The reading of a clap is noisy, so it's passed along to be mixed into the
+            // seeder.
+            events.enqueue(InputEvent::Clap(reading as u32)).ok();
+        }
+    }
+}
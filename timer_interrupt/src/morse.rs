@@ -0,0 +1,109 @@
+// Maximum number of digits of a number, enough for any u32.
+const MAX_DIGITS: usize = 10;
+// Number of symbols (dots and dashes) of the Morse code of a digit.
+const SYMBOLS_PER_DIGIT: u8 = 5;
+// Length of each part of the code, in units. A unit is the length of a dot.
+const DOT_UNITS: u8 = 1;
+const DASH_UNITS: u8 = 3;
+const SYMBOL_GAP_UNITS: u8 = 1;
+const DIGIT_GAP_UNITS: u8 = 3;
+
+// The outputs on which the Morse code can be sent. The output is selected in the
+// settings menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MorseOutput {
+    // The top right LED of the display.
+    Led,
+    // The speaker.
+    Sound,
+    // Both at the same time.
+    Both,
+}
+
+// A number encoded in Morse code. It's read by iterating over it, which gives whether
+// the signal is on or off for each unit of time, so the caller decides how long a unit
+// is and what the signal drives. The iteration ends after the last digit.
+pub struct Morse {
+    // Decimal digits of the number, starting with the most significant:
+    digits: [u8; MAX_DIGITS],
+    length: usize,
+    // Position of the next symbol to send:
+    digit_n: usize,
+    symbol_n: u8,
+    // Units left of the current symbol and of the gap that follows it:
+    on_units: u8,
+    off_units: u8,
+}
+
+impl Morse {
+    pub fn new(number: u32) -> Self {
+        let mut digits = [0; MAX_DIGITS];
+        let mut length = 0;
+        let mut rest = number;
+        loop {
+            digits[length] = (rest % 10) as u8;
+            length += 1;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+        digits[..length].reverse();
+        Self {
+            digits,
+            length,
+            digit_n: 0,
+            symbol_n: 0,
+            on_units: 0,
+            off_units: 0,
+        }
+    }
+}
+
+impl Iterator for Morse {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        loop {
+            if self.on_units > 0 {
+                self.on_units -= 1;
+                return Some(true);
+            }
+            if self.off_units > 0 {
+                self.off_units -= 1;
+                return Some(false);
+            }
+            if self.digit_n == self.length {
+                return None;
+            }
+
+            // Load the next symbol. The last one of a digit is followed by a longer gap.
+            let digit = self.digits[self.digit_n];
+            self.on_units = if is_dash(digit, self.symbol_n) {
+                DASH_UNITS
+            } else {
+                DOT_UNITS
+            };
+            self.symbol_n += 1;
+            self.off_units = if self.symbol_n == SYMBOLS_PER_DIGIT {
+                self.symbol_n = 0;
+                self.digit_n += 1;
+                DIGIT_GAP_UNITS
+            } else {
+                SYMBOL_GAP_UNITS
+            };
+        }
+    }
+}
+
+// Returns true if the given symbol of the code of a digit is a dash. The digits from 1
+// to 5 start with as many dots as their value, followed by dashes (3 is ...--), and the
+// digits from 6 to 9 and 0 start with as many dashes as their value minus 5, followed
+// by dots (7 is --...).
+fn is_dash(digit: u8, symbol_n: u8) -> bool {
+    match digit {
+        1..=5 => symbol_n >= digit,
+        0 => symbol_n < SYMBOLS_PER_DIGIT,
+        _ => symbol_n < digit - 5,
+    }
+}
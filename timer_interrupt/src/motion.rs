@@ -0,0 +1,181 @@
+use crate::input::{EventProducer, InputEvent, InputSource};
+use crate::lsm303agr::Lsm303agr;
+use crate::patterns::Diagonal;
+
+// Acceleration above which a sample is considered part of a shake, in mg. Gravity alone
+// is 1000 mg, so the board must be moved sharply to exceed it.
+const SHAKE_MG: i32 = 1800;
+// Number of samples above SHAKE_MG that make a shake, and number of samples in which
+// they must happen. The sensor is read 32 times per second, so a shake is 4 jolts in
+// half a second.
+const SHAKE_SAMPLES: u8 = 4;
+const SHAKE_WINDOW_SAMPLES: u8 = 16;
+// Number of samples after a shake during which no other shake is detected (1 s), so
+// that a long shake only generates one event.
+const SHAKE_COOLDOWN_SAMPLES: u8 = 32;
+// Acceleration along the z axis above which the board is considered to be lying on one
+// of its faces, in mg.
+const FLAT_MG: i32 = 800;
+// Number of consecutive samples that the board must lie on a face to settle on it
+// (250 ms), so that a face isn't detected while the board is being turned.
+const FLAT_SAMPLES: u8 = 8;
+// Number of samples in which the board must be turned back to its original face to make
+// a flip (3 s). After that, it settles on the new face.
+const FLIP_WINDOW_SAMPLES: u8 = 96;
+
+// Detects gestures with the accelerometer. It's polled from the interrupt of a real time
+// counter, reading the acceleration once per poll.
+pub struct Motion {
+    sensor: Lsm303agr,
+    // Number of samples above SHAKE_MG in the current window:
+    strong_samples: u8,
+    // Number of samples since the first strong sample of the current window:
+    window_samples: u8,
+    // Number of samples left before a new shake can be detected:
+    cooldown_samples: u8,
+    // The face the board has settled on, true if gravity points along the positive z
+    // axis of the sensor:
+    settled_face: Option<bool>,
+    // The face the board is lying on in the last samples, if it's lying flat:
+    current_face: Option<bool>,
+    // Number of consecutive samples on the current face:
+    face_samples: u8,
+    // Number of samples since the board was turned away from the settled face, or 0 if
+    // it hasn't been turned:
+    flipped_samples: u8,
+}
+
+impl Motion {
+    pub fn new(sensor: Lsm303agr) -> Self {
+        Self {
+            sensor,
+            strong_samples: 0,
+            window_samples: 0,
+            cooldown_samples: 0,
+            settled_face: None,
+            current_face: None,
+            face_samples: 0,
+            flipped_samples: 0,
+        }
+    }
+
+    // Returns the diagonal of the display that points closest to the magnetic north, if
+    // the magnetometer can be read. The board must be lying flat, face up, and the x and
+    // y axes of the sensor are assumed to point to the right and to the top of the
+    // display.
+    pub fn north(&mut self) -> Option<Diagonal> {
+        let [x, y, _] = self.sensor.magnetic_field()?;
+        // The horizontal component of the field of the Earth points to the north.
+        Some(match (y > 0, x > 0) {
+            (true, false) => Diagonal::UpLeft,
+            (true, true) => Diagonal::UpRight,
+            (false, false) => Diagonal::DownLeft,
+            (false, true) => Diagonal::DownRight,
+        })
+    }
+
+    // Update the flip detector with a new sample and return true if the board has just
+    // been turned over and back to the face it had settled on.
+    fn detect_flip(&mut self, acceleration: [i32; 3]) -> bool {
+        let z = acceleration[2];
+        let face = if z > FLAT_MG {
+            Some(true)
+        } else if z < -FLAT_MG {
+            Some(false)
+        } else {
+            None
+        };
+        if face == self.current_face {
+            self.face_samples = self.face_samples.saturating_add(1);
+        } else {
+            self.current_face = face;
+            self.face_samples = 1;
+        }
+
+        if self.flipped_samples > 0 {
+            self.flipped_samples += 1;
+            if self.flipped_samples > FLIP_WINDOW_SAMPLES {
+                // The board hasn't been turned back in time, it stays on the new face.
+                self.flipped_samples = 0;
+                self.settled_face = self.settled_face.map(|settled| !settled);
+            }
+        }
+
+        // Only the moment in which the board settles on a face is relevant.
+        if self.face_samples != FLAT_SAMPLES {
+            return false;
+        }
+        let Some(face) = self.current_face else {
+            return false;
+        };
+        match self.settled_face {
+            None => {
+                self.settled_face = Some(face);
+                false
+            }
+            Some(settled) if settled != face && self.flipped_samples == 0 => {
+                self.flipped_samples = 1;
+                false
+            }
+            Some(settled) if settled == face && self.flipped_samples > 0 => {
+                self.flipped_samples = 0;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    // Update the shake detector with a new sample and return true if a shake has just
+    // been completed.
+    fn detect_shake(&mut self, acceleration: [i32; 3]) -> bool {
+        if self.cooldown_samples > 0 {
+            self.cooldown_samples -= 1;
+            return false;
+        }
+        let magnitude_squared: i32 = acceleration.iter().map(|axis| axis * axis).sum();
+        if magnitude_squared > SHAKE_MG * SHAKE_MG {
+            self.strong_samples += 1;
+        }
+        if self.strong_samples > 0 {
+            self.window_samples += 1;
+        }
+
+        if self.strong_samples >= SHAKE_SAMPLES {
+            self.strong_samples = 0;
+            self.window_samples = 0;
+            self.cooldown_samples = SHAKE_COOLDOWN_SAMPLES;
+            true
+        } else {
+            if self.window_samples >= SHAKE_WINDOW_SAMPLES {
+                self.strong_samples = 0;
+                self.window_samples = 0;
+            }
+            false
+        }
+    }
+}
+
+impl InputSource for Motion {
+    fn poll(&mut self, events: &mut EventProducer) {
+        // The taps are reported through the interrupt pin of the sensor, here the
+        // interrupt is just released so that the next tap can trigger it again.
+        self.sensor.clear_tap();
+        // The free fall is detected by the sensor, it only has to be polled.
+        if self.sensor.free_fall() {
+            events.enqueue(InputEvent::FreeFall).ok();
+        }
+        if let Some(acceleration) = self.sensor.acceleration() {
+            if self.detect_shake(acceleration) {
+                // The lowest bits of the readings of a shaking board are noisy, so they
+                // are passed along to be mixed into the seeder.
+                let noise = acceleration
+                    .iter()
+                    .fold(0, |noise: u32, &axis| noise.rotate_left(11) ^ axis as u32);
+                events.enqueue(InputEvent::Shake(noise)).ok();
+            }
+            if self.detect_flip(acceleration) {
+                events.enqueue(InputEvent::Flip).ok();
+            }
+        }
+    }
+}
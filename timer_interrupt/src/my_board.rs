@@ -1,10 +1,38 @@
+use crate::button::{ActiveLevel, Button};
+use crate::lsm303agr::Lsm303agr;
+use cortex_m::peripheral::{DCB, DWT};
+#[cfg(feature = "ring-buttons")]
+use microbit::hal::gpio::Disconnected;
+#[cfg(any(feature = "trigger", feature = "ring-buttons-3v"))]
+use microbit::hal::gpio::PullDown;
+#[cfg(any(feature = "ssd1306", feature = "i2c-slave"))]
+use microbit::hal::gpio::{p0::P0_26, p1::P1_00};
+#[cfg(feature = "ssd1306")]
+use microbit::pac::TWIM1;
+#[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+use microbit::pac::TWIS1;
+use microbit::pac::{
+    AAR, CCM, FICR, GPIOTE, NVMC, POWER, PPI, PWM2, RADIO, RNG, SAADC, TEMP, UARTE0, WDT,
+};
+#[cfg(feature = "pwm-display")]
+use microbit::pac::{PWM0, PWM1};
 use microbit::{
     gpio::DisplayPins,
     hal::gpio::{
-        p0::{Parts, P0_14, P0_23},
-        p1, Floating, Input, Level,
+        p0::{Parts, P0_05, P0_06, P0_08, P0_16, P0_20},
+        p1, Floating, Input, Level, Output, Pin, PullUp, PushPull,
     },
-    pac::{Peripherals, CLOCK, RTC0, RTC1, TIMER0},
+    pac::{Peripherals, CLOCK, RTC0, RTC1, RTC2, TIMER0, TIMER1},
+};
+#[cfg(feature = "ws2812")]
+use microbit::{
+    hal::gpio::{p0::P0_03, p0::P0_04},
+    pac::SPIM2,
+};
+#[cfg(any(feature = "max7219", feature = "spi-recorder"))]
+use microbit::{
+    hal::gpio::{p0::P0_13, p0::P0_17, p1::P1_02},
+    pac::SPIM3,
 };
 
 // A struc that represents the microbit board and contains the peripherals that are
@@ -13,15 +41,163 @@ pub struct MyBoard {
     // Pins that drive the 5x5 LED matrix:
     pub display_pins: DisplayPins,
     // Buttons in the board:
-    pub button_a: P0_14<Input<Floating>>,
-    pub button_b: P0_23<Input<Floating>>,
-    // Two of the real time counters:
+    pub button_a: BoardButton,
+    pub button_b: BoardButton,
+    // Touch logo on the front of the board:
+    pub touch_logo: p1::P1_04<Input<Floating>>,
+    // The accelerometer and magnetometer, already configured and ready to be read. It
+    // uses the I2C master TWIM0 and the pins of the internal I2C bus:
+    pub motion_sensor: Lsm303agr,
+    // The interrupt line of the sensors of the internal I2C bus:
+    pub sensor_interrupt: Pin<Input<PullUp>>,
+    // The speaker and the PWM peripheral that generates its tones:
+    pub speaker_pin: Pin<Output<PushPull>>,
+    pub pwm2: PWM2,
+    // The real time counters:
     pub rtc0: RTC0,
     pub rtc1: RTC1,
+    pub rtc2: RTC2,
     // One of the timers:
     pub timer0: TIMER0,
+    pub timer1: TIMER1,
+    // The programmable peripheral interconnect, which connects the events of the
+    // peripherals to the tasks of others without the CPU:
+    pub ppi: PPI,
     // The clock:
     pub clock: CLOCK,
+    // The analog to digital converter, used by the microphone and to sense the light
+    // with the LEDs:
+    pub saadc: SAADC,
+    // The microphone:
+    pub microphone_pins: MicrophonePins,
+    // The temperature sensor of the microcontroller:
+    pub temp: TEMP,
+    // The UART and the pins that connect it to the interface chip of the board, which
+    // forwards it to the USB port:
+    pub uarte0: UARTE0,
+    pub uart_pins: UartPins,
+    // The 2.4 GHz radio:
+    pub radio: RADIO,
+    // The factory information, which holds the identifier of the chip:
+    pub ficr: FICR,
+    // The AES-CCM encryption, which needs the AAR because they share registers, and the
+    // random number generator:
+    pub ccm: CCM,
+    pub aar: AAR,
+    pub rng: RNG,
+    // The non-volatile memory controller, which writes the flash:
+    pub nvmc: NVMC,
+    // The power management, which warns when the supply is failing:
+    pub power: POWER,
+    // The watchdog timer:
+    pub wdt: WDT,
+    // The debug control block and the data watchpoint and trace unit of the CPU, whose
+    // cycle counter measures the load:
+    pub dcb: DCB,
+    pub dwt: DWT,
+    // Two of the PWM peripherals, used by the PWM display driver:
+    #[cfg(feature = "pwm-display")]
+    pub pwm0: PWM0,
+    #[cfg(feature = "pwm-display")]
+    pub pwm1: PWM1,
+    // The SPI master and the pins of the edge connector used by the external matrix, or
+    // by the recorder of the generations:
+    #[cfg(any(feature = "max7219", feature = "spi-recorder"))]
+    pub spim3: SPIM3,
+    #[cfg(any(feature = "max7219", feature = "spi-recorder"))]
+    pub spi_pins: SpiPins,
+    // The I2C master and the pins of the edge connector used by the OLED display:
+    #[cfg(feature = "ssd1306")]
+    pub twim1: TWIM1,
+    // The I2C slave that makes the board a peripheral of an external controller, on the
+    // same pins:
+    #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+    pub twis1: TWIS1,
+    #[cfg(any(feature = "ssd1306", feature = "i2c-slave"))]
+    pub i2c_pins: I2cPins,
+    // The SPI master and the pins of the edge connector used by the WS2812 panel:
+    #[cfg(feature = "ws2812")]
+    pub spim2: SPIM2,
+    #[cfg(feature = "ws2812")]
+    pub ws2812_pins: Ws2812Pins,
+    // The rings 0, 1 and 2 of the edge connector, used as external buttons. The rings
+    // used by the WS2812 panel are None:
+    #[cfg(feature = "ring-buttons")]
+    pub ring_buttons: [Option<RingButton>; 3],
+    // The GPIO tasks and events module, used by the external trigger and to read the
+    // buttons through interrupts:
+    pub gpiote: GPIOTE,
+    // The pin of the edge connector used as external trigger (pin 12). Change the pin
+    // in take() to use a different one:
+    #[cfg(feature = "trigger")]
+    pub trigger_pin: Pin<Input<PullDown>>,
+}
+
+// The buttons of the board. They have external pull-up resistors, so their pins are
+// floating inputs, and they are active low.
+pub type BoardButton = Button<Pin<Input<Floating>>>;
+
+// External button connected between a ring of the edge connector and GND. The pin has
+// the pull-up enabled, so it's active low. With the ring-buttons-3v feature, the button
+// is connected between the ring and 3V instead, and the pin has the pull-down enabled,
+// so it's active high.
+#[cfg(all(feature = "ring-buttons", not(feature = "ring-buttons-3v")))]
+pub type RingButton = Button<Pin<Input<PullUp>>>;
+#[cfg(feature = "ring-buttons-3v")]
+pub type RingButton = Button<Pin<Input<PullDown>>>;
+
+// Pins of the internal I2C bus, which connects the microcontroller to the
+// accelerometer and magnetometer. It has external pull-up resistors.
+pub struct InternalI2cPins {
+    pub scl: P0_08<Input<Floating>>,
+    pub sda: P0_16<Input<Floating>>,
+}
+
+// Pins of the microphone of the micro:bit v2.
+pub struct MicrophonePins {
+    // The amplified signal of the microphone, the analog input 3:
+    pub input: P0_05<Input<Floating>>,
+    // The power supply of the microphone, which is on while the pin is high. The pin is
+    // only kept so that nothing else can use it:
+    pub _power: P0_20<Output<PushPull>>,
+}
+
+// Pins of the UART between the microcontroller and the interface chip of the board.
+pub struct UartPins {
+    pub tx: P0_06<Output<PushPull>>,
+    pub rx: p1::P1_08<Input<Floating>>,
+}
+
+// Pins of the edge connector used for the SPI bus of the external matrix, or of the
+// recorder of the generations, which uses CS as the frame pulse.
+#[cfg(any(feature = "max7219", feature = "spi-recorder"))]
+pub struct SpiPins {
+    // Pin 13 of the edge connector:
+    pub sck: P0_17<Output<PushPull>>,
+    // Pin 15 of the edge connector:
+    pub mosi: P0_13<Output<PushPull>>,
+    // Pin 16 of the edge connector:
+    pub cs: P1_02<Output<PushPull>>,
+}
+
+// Pins of the edge connector used for the I2C bus of the OLED display, or of the
+// external controller.
+#[cfg(any(feature = "ssd1306", feature = "i2c-slave"))]
+pub struct I2cPins {
+    // Pin 19 of the edge connector:
+    pub scl: P0_26<Input<Floating>>,
+    // Pin 20 of the edge connector:
+    pub sda: P1_00<Input<Floating>>,
+}
+
+// Pins of the edge connector used to drive the WS2812 panel.
+#[cfg(feature = "ws2812")]
+pub struct Ws2812Pins {
+    // Pin 1 of the edge connector, connected to the data input of the panel:
+    pub data: P0_03<Output<PushPull>>,
+    // Pin 2 of the edge connector, used as clock of the SPI master. It must be left
+    // unconnected:
+    pub sck: P0_04<Output<PushPull>>,
 }
 
 impl MyBoard {
@@ -29,8 +205,8 @@ impl MyBoard {
     // This is done to avoid having two variables that control the same hardware
     // components.
     pub fn take() -> Option<Self> {
-        match Peripherals::take() {
-            Some(peripherals) => {
+        match (Peripherals::take(), cortex_m::Peripherals::take()) {
+            (Some(peripherals), Some(core_peripherals)) => {
                 let p0_parts = Parts::new(peripherals.P0);
                 let p1_parts = p1::Parts::new(peripherals.P1);
                 Some(Self {
@@ -46,15 +222,105 @@ impl MyBoard {
                         row4: p0_parts.p0_24.into_push_pull_output(Level::Low),
                         row5: p0_parts.p0_19.into_push_pull_output(Level::Low),
                     },
-                    button_a: p0_parts.p0_14.into_floating_input(),
-                    button_b: p0_parts.p0_23.into_floating_input(),
+                    button_a: Button::new(
+                        p0_parts.p0_14.into_floating_input().degrade(),
+                        ActiveLevel::Low,
+                    ),
+                    button_b: Button::new(
+                        p0_parts.p0_23.into_floating_input().degrade(),
+                        ActiveLevel::Low,
+                    ),
+                    touch_logo: p1_parts.p1_04.into_floating_input(),
+                    motion_sensor: Lsm303agr::new(
+                        peripherals.TWIM0,
+                        InternalI2cPins {
+                            scl: p0_parts.p0_08.into_floating_input(),
+                            sda: p0_parts.p0_16.into_floating_input(),
+                        },
+                    ),
+                    sensor_interrupt: p0_parts.p0_25.into_pullup_input().degrade(),
+                    speaker_pin: p0_parts.p0_00.into_push_pull_output(Level::Low).degrade(),
+                    pwm2: peripherals.PWM2,
                     rtc0: peripherals.RTC0,
                     rtc1: peripherals.RTC1,
+                    rtc2: peripherals.RTC2,
                     timer0: peripherals.TIMER0,
+                    timer1: peripherals.TIMER1,
+                    ppi: peripherals.PPI,
                     clock: peripherals.CLOCK,
+                    saadc: peripherals.SAADC,
+                    temp: peripherals.TEMP,
+                    uarte0: peripherals.UARTE0,
+                    radio: peripherals.RADIO,
+                    ficr: peripherals.FICR,
+                    ccm: peripherals.CCM,
+                    aar: peripherals.AAR,
+                    rng: peripherals.RNG,
+                    nvmc: peripherals.NVMC,
+                    power: peripherals.POWER,
+                    wdt: peripherals.WDT,
+                    dcb: core_peripherals.DCB,
+                    dwt: core_peripherals.DWT,
+                    uart_pins: UartPins {
+                        tx: p0_parts.p0_06.into_push_pull_output(Level::High),
+                        rx: p1_parts.p1_08.into_floating_input(),
+                    },
+                    microphone_pins: MicrophonePins {
+                        input: p0_parts.p0_05.into_floating_input(),
+                        _power: p0_parts.p0_20.into_push_pull_output(Level::High),
+                    },
+                    #[cfg(feature = "pwm-display")]
+                    pwm0: peripherals.PWM0,
+                    #[cfg(feature = "pwm-display")]
+                    pwm1: peripherals.PWM1,
+                    #[cfg(any(feature = "max7219", feature = "spi-recorder"))]
+                    spim3: peripherals.SPIM3,
+                    #[cfg(any(feature = "max7219", feature = "spi-recorder"))]
+                    spi_pins: SpiPins {
+                        sck: p0_parts.p0_17.into_push_pull_output(Level::Low),
+                        mosi: p0_parts.p0_13.into_push_pull_output(Level::Low),
+                        cs: p1_parts.p1_02.into_push_pull_output(Level::High),
+                    },
+                    #[cfg(feature = "ssd1306")]
+                    twim1: peripherals.TWIM1,
+                    #[cfg(all(feature = "i2c-slave", not(feature = "ssd1306")))]
+                    twis1: peripherals.TWIS1,
+                    #[cfg(any(feature = "ssd1306", feature = "i2c-slave"))]
+                    i2c_pins: I2cPins {
+                        scl: p0_parts.p0_26.into_floating_input(),
+                        sda: p1_parts.p1_00.into_floating_input(),
+                    },
+                    #[cfg(feature = "ws2812")]
+                    spim2: peripherals.SPIM2,
+                    #[cfg(feature = "ws2812")]
+                    ws2812_pins: Ws2812Pins {
+                        data: p0_parts.p0_03.into_push_pull_output(Level::Low),
+                        sck: p0_parts.p0_04.into_push_pull_output(Level::Low),
+                    },
+                    #[cfg(all(feature = "ring-buttons", not(feature = "ws2812")))]
+                    ring_buttons: [
+                        Some(ring_button(p0_parts.p0_02.degrade())),
+                        Some(ring_button(p0_parts.p0_03.degrade())),
+                        Some(ring_button(p0_parts.p0_04.degrade())),
+                    ],
+                    #[cfg(all(feature = "ring-buttons", feature = "ws2812"))]
+                    ring_buttons: [Some(ring_button(p0_parts.p0_02.degrade())), None, None],
+                    gpiote: peripherals.GPIOTE,
+                    #[cfg(feature = "trigger")]
+                    trigger_pin: p0_parts.p0_12.into_pulldown_input().degrade(),
                 })
             }
-            None => None,
+            _ => None,
         }
     }
 }
+
+#[cfg(all(feature = "ring-buttons", not(feature = "ring-buttons-3v")))]
+fn ring_button(pin: Pin<Disconnected>) -> RingButton {
+    Button::new(pin.into_pullup_input(), ActiveLevel::Low)
+}
+
+#[cfg(feature = "ring-buttons-3v")]
+fn ring_button(pin: Pin<Disconnected>) -> RingButton {
+    Button::new(pin.into_pulldown_input(), ActiveLevel::High)
+}
@@ -0,0 +1,142 @@
+use crate::direct_display::{self, CROSS};
+use crate::event_log;
+use core::fmt::{self, Write};
+use core::mem::MaybeUninit;
+use core::panic::PanicInfo;
+use core::ptr::addr_of_mut;
+use cortex_m::peripheral::SCB;
+use cortex_m_rt::{exception, ExceptionFrame};
+use heapless::String;
+use rtt_target::{ChannelMode, UpChannel};
+
+// Number of times the image is shown before it's switched off or back on when it
+// blinks (250 ms).
+const BLINK_SCANS: u32 = direct_display::SCANS_PER_SECOND / 4;
+
+// Maximum length of the panic or fault message kept for the next boot, in bytes.
+// Longer messages are cut.
+pub const MESSAGE_SIZE: usize = 256;
+// Value of the first word of the kept message ("PNIC" in ASCII). The RAM holds random
+// values after the board is switched on, so anything else means there is no message.
+const MAGIC: u32 = 0x4349_4e50;
+
+// Message of a panic or a hard fault kept in a part of the RAM that isn't initialized
+// when the firmware starts, so it survives the reset by the watchdog and can be reported
+// after it.
+#[repr(C)]
+struct KeptMessage {
+    magic: u32,
+    length: usize,
+    bytes: [u8; MESSAGE_SIZE],
+}
+
+#[link_section = ".uninit.KEPT_MESSAGE"]
+static mut KEPT_MESSAGE: MaybeUninit<KeptMessage> = MaybeUninit::uninit();
+
+// Returns the message of the panic or the hard fault that caused the last reset, if
+// there was one, and forgets it so it's only reported once.
+pub fn take_kept_message() -> Option<String<MESSAGE_SIZE>> {
+    let kept = unsafe { &mut *(*addr_of_mut!(KEPT_MESSAGE)).as_mut_ptr() };
+    if kept.magic != MAGIC || kept.length > MESSAGE_SIZE {
+        return None;
+    }
+    kept.magic = 0;
+    let text = core::str::from_utf8(&kept.bytes[..kept.length]).ok()?;
+    Some(String::from(text))
+}
+
+// Writes the message to the kept message, cutting it when it's full. The message
+// is only cut between characters, so it stays valid UTF-8.
+impl Write for KeptMessage {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let end = self.length + c.len_utf8();
+            if end > MESSAGE_SIZE {
+                return Err(fmt::Error);
+            }
+            c.encode_utf8(&mut self.bytes[self.length..end]);
+            self.length = end;
+        }
+        Ok(())
+    }
+}
+
+const SAD_FACE: [[bool; 5]; 5] = [
+    [false, false, false, false, false],
+    [false, true, false, true, false],
+    [false, false, false, false, false],
+    [false, true, true, true, false],
+    [true, false, false, false, true],
+];
+// Panic handler that prints the message to the terminal channel of RTT, like the one of
+// the panic-rtt-target crate, and then shows a sad face on the LED display, so a crash
+// can be seen without a debugger. The interrupts are disabled, so the display is
+// driven directly through the registers of the ports. If the watchdog is running, it
+// resets the board a few seconds later, and the message is kept in RAM to be reported
+// when the board starts again.
+#[inline(never)]
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    report(format_args!("{}", info));
+    show_image(SAD_FACE, false)
+}
+
+// Handler of the hard faults, the errors detected by the CPU itself, like an access to
+// an invalid address or an invalid instruction, which Rust can't catch. It prints the
+// registers that the CPU stacked when the fault happened and the fault status registers
+// to RTT, keeps them for the next boot like the panic message, and blinks a cross on the
+// display until the watchdog resets the board. The program counter tells where the fault
+// happened, and the status registers why (see the Cortex-M4 reference manual).
+#[exception]
+unsafe fn HardFault(frame: &ExceptionFrame) -> ! {
+    let scb = &*SCB::PTR;
+    report(format_args!(
+        "HardFault at PC {:#010x}, LR {:#010x}, xPSR {:#010x}\n\
+         R0 {:#010x}, R1 {:#010x}, R2 {:#010x}, R3 {:#010x}, R12 {:#010x}\n\
+         CFSR {:#010x}, HFSR {:#010x}, MMFAR {:#010x}, BFAR {:#010x}",
+        frame.pc(),
+        frame.lr(),
+        frame.xpsr(),
+        frame.r0(),
+        frame.r1(),
+        frame.r2(),
+        frame.r3(),
+        frame.r12(),
+        scb.cfsr.read(),
+        scb.hfsr.read(),
+        scb.mmfar.read(),
+        scb.bfar.read(),
+    ));
+    show_image(CROSS, true)
+}
+
+// Print a message to the terminal channel of RTT, followed by the events recorded
+// before it, and keep the message for the next boot. The events are lost with the reset.
+fn report(message: fmt::Arguments) {
+    if let Some(mut channel) = unsafe { UpChannel::conjure(0) } {
+        // Without a debugger nothing reads the channel, so waiting for it to have room
+        // would never end.
+        channel.set_mode(ChannelMode::NoBlockTrim);
+        writeln!(channel, "{}", message).ok();
+        writeln!(channel, "Recent events:").ok();
+        event_log::write_log(&mut channel).ok();
+    }
+
+    let kept = unsafe { &mut *(*addr_of_mut!(KEPT_MESSAGE)).as_mut_ptr() };
+    kept.length = 0;
+    kept.write_fmt(message).ok();
+    kept.magic = MAGIC;
+}
+
+// Take over the pins of the display and multiplex an image forever, blinking it if
+// requested.
+fn show_image(image: [[bool; 5]; 5], blink: bool) -> ! {
+    direct_display::take_over();
+    loop {
+        direct_display::show(image, BLINK_SCANS);
+        if blink {
+            direct_display::show([[false; 5]; 5], BLINK_SCANS);
+        }
+    }
+}
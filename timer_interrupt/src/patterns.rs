@@ -0,0 +1,78 @@
+// A well-known Game of Life pattern. The pattern is described by the positions (row,
+// column) of its live cells, relative to its top left corner.
+pub struct Pattern {
+    pub name: &'static str,
+    pub cells: &'static [(usize, usize)],
+}
+
+// The diagonal directions, in which gliders travel.
+#[derive(Clone, Copy)]
+pub enum Diagonal {
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Diagonal {
+    // Returns the glider that travels in this direction. They are all mirror images of
+    // the glider of the library, which travels down and to the right.
+    pub fn glider(self) -> &'static Pattern {
+        &GLIDERS[self as usize]
+    }
+
+    // Returns the row and column of the top left corner of a glider stamped at the
+    // corner of the grid opposite to this direction, so that it crosses the whole grid.
+    pub fn launch_corner(self) -> (usize, usize) {
+        match self {
+            Diagonal::UpLeft => (2, 2),
+            Diagonal::UpRight => (2, 0),
+            Diagonal::DownLeft => (0, 2),
+            Diagonal::DownRight => (0, 0),
+        }
+    }
+}
+
+// Gliders traveling in each of the diagonal directions, in the order of Diagonal.
+const GLIDERS: [Pattern; 4] = [
+    Pattern {
+        name: "GLIDER",
+        cells: &[(2, 1), (1, 0), (0, 2), (0, 1), (0, 0)],
+    },
+    Pattern {
+        name: "GLIDER",
+        cells: &[(2, 1), (1, 2), (0, 0), (0, 1), (0, 2)],
+    },
+    Pattern {
+        name: "GLIDER",
+        cells: &[(0, 1), (1, 0), (2, 2), (2, 1), (2, 0)],
+    },
+    Pattern {
+        name: "GLIDER",
+        cells: &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+    },
+];
+
+// Library of patterns that fit in the 5x5 grid.
+pub const PATTERNS: [Pattern; 5] = [
+    Pattern {
+        name: "GLIDER",
+        cells: &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+    },
+    Pattern {
+        name: "BLINKER",
+        cells: &[(0, 0), (0, 1), (0, 2)],
+    },
+    Pattern {
+        name: "BLOCK",
+        cells: &[(0, 0), (0, 1), (1, 0), (1, 1)],
+    },
+    Pattern {
+        name: "TOAD",
+        cells: &[(0, 1), (0, 2), (0, 3), (1, 0), (1, 1), (1, 2)],
+    },
+    Pattern {
+        name: "BEACON",
+        cells: &[(0, 0), (0, 1), (1, 0), (2, 3), (3, 2), (3, 3)],
+    },
+];
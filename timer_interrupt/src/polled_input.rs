@@ -0,0 +1,110 @@
+use crate::button::ButtonState;
+use crate::button_pair::ButtonPair;
+use crate::input::{EventProducer, InputEvent, InputSource};
+use crate::my_board::BoardButton;
+#[cfg(feature = "ring-buttons")]
+use crate::my_board::RingButton;
+use crate::time;
+use crate::touch::TouchLogo;
+
+// Inputs that are read periodically, from the interrupt of a real time counter. Every
+// input keeps track of its presses with a ButtonState, which debounces the readings and
+// tells short presses, long presses, repeats and double clicks apart, timing them with
+// the uptime.
+pub struct PolledInputs {
+    // The buttons of the board and their states, or None if they are read through the
+    // GPIOTE or by the main loop:
+    buttons: Option<PolledButtons>,
+    // The touch logo goes through the same debouncing and press detection as the
+    // buttons, since a finger on the edge of the logo can make the readings flicker:
+    touch_logo: TouchLogo,
+    touch_state: ButtonState,
+    // External buttons connected to the rings of the edge connector, and their states:
+    #[cfg(feature = "ring-buttons")]
+    rings: [Option<RingButton>; 3],
+    #[cfg(feature = "ring-buttons")]
+    ring_states: [ButtonState; 3],
+}
+
+// The buttons of the board, when they are polled: from the interrupt of the real time
+// counter with the rest of the polled inputs, or by the main loop itself with the
+// blocking-input feature.
+pub struct PolledButtons {
+    button_a: BoardButton,
+    button_b: BoardButton,
+    states: ButtonPair,
+}
+
+impl PolledButtons {
+    pub fn new(button_a: BoardButton, button_b: BoardButton) -> Self {
+        Self {
+            button_a,
+            button_b,
+            states: ButtonPair::new(),
+        }
+    }
+}
+
+impl InputSource for PolledButtons {
+    fn poll(&mut self, events: &mut EventProducer) {
+        let now_ms = cortex_m::interrupt::free(time::now_ms);
+        let a_pressed = self.button_a.is_pressed();
+        let b_pressed = self.button_b.is_pressed();
+        self.states.update(a_pressed, b_pressed, now_ms, events);
+    }
+}
+
+impl PolledInputs {
+    pub fn new(touch_logo: TouchLogo) -> Self {
+        Self {
+            buttons: None,
+            touch_logo,
+            touch_state: ButtonState::new(),
+            #[cfg(feature = "ring-buttons")]
+            rings: [None, None, None],
+            #[cfg(feature = "ring-buttons")]
+            ring_states: [ButtonState::new(), ButtonState::new(), ButtonState::new()],
+        }
+    }
+
+    // Poll the buttons of the board too.
+    pub fn with_buttons(mut self, buttons: PolledButtons) -> Self {
+        self.buttons = Some(buttons);
+        self
+    }
+
+    // Poll the external buttons of the rings too. The rings that are None are ignored.
+    #[cfg(feature = "ring-buttons")]
+    pub fn with_rings(mut self, rings: [Option<RingButton>; 3]) -> Self {
+        self.rings = rings;
+        self
+    }
+}
+
+impl InputSource for PolledInputs {
+    fn poll(&mut self, events: &mut EventProducer) {
+        if let Some(buttons) = self.buttons.as_mut() {
+            buttons.poll(events);
+        }
+        let now_ms = cortex_m::interrupt::free(time::now_ms);
+
+        let touched = self.touch_logo.is_touched();
+        if let Some(event) = self.touch_state.update(touched, now_ms) {
+            events.enqueue(InputEvent::Touch(event)).ok();
+        }
+
+        #[cfg(feature = "ring-buttons")]
+        for (ring_n, (ring, state)) in self
+            .rings
+            .iter()
+            .zip(self.ring_states.iter_mut())
+            .enumerate()
+        {
+            if let Some(ring) = ring {
+                if let Some(event) = state.update(ring.is_pressed(), now_ms) {
+                    events.enqueue(InputEvent::Ring(ring_n, event)).ok();
+                }
+            }
+        }
+    }
+}
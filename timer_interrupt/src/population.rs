@@ -0,0 +1,66 @@
+// Number of generations whose population is remembered, one per column of the display.
+const HISTORY_LENGTH: usize = 5;
+// Maximum population of the 5x5 grid.
+const MAX_POPULATION: u8 = 25;
+
+// Ring buffer with the population (number of live cells) of the last generations, used
+// to show how the population evolves over time.
+pub struct PopulationHistory {
+    counts: [u8; HISTORY_LENGTH],
+    // Position in which the next population will be stored, which is also the position
+    // of the oldest one once the buffer is full:
+    next: usize,
+}
+
+impl PopulationHistory {
+    pub const fn new() -> Self {
+        Self {
+            counts: [0; HISTORY_LENGTH],
+            next: 0,
+        }
+    }
+
+    // Store the population of a new generation, replacing the oldest one.
+    pub fn record(&mut self, population: u8) {
+        self.counts[self.next] = population;
+        self.next = (self.next + 1) % HISTORY_LENGTH;
+    }
+
+    // Compute the brightness of a bar graph of the population of the last generations,
+    // with the oldest generation on the left and the newest on the right. The height of
+    // each bar is the population scaled from 0-25 to 0-5 LEDs, rounding up so that any
+    // generation with live cells has at least one LED on.
+    pub fn bar_graph(&self) -> [[u8; 5]; 5] {
+        let mut matrix = [[0; 5]; 5];
+        for col_n in 0..HISTORY_LENGTH {
+            let population = self.counts[(self.next + col_n) % HISTORY_LENGTH];
+            let height = (population as usize * 5).div_ceil(MAX_POPULATION as usize);
+            // The bars grow from the bottom row upwards.
+            for row in matrix.iter_mut().rev().take(height) {
+                row[col_n] = 9;
+            }
+        }
+        matrix
+    }
+
+    // Compute the brightness of a scrolling graph of the population of the last
+    // generations. Each column shows one generation as a single LED, whose height is
+    // the population scaled from 0-25 to the 5 rows of the display. Since the newest
+    // generation is always on the right, the graph scrolls one column to the left every
+    // generation, which makes oscillations easy to spot.
+    pub fn history_graph(&self) -> [[u8; 5]; 5] {
+        let mut matrix = [[0; 5]; 5];
+        let oldest_first = self
+            .counts
+            .iter()
+            .cycle()
+            .skip(self.next)
+            .take(HISTORY_LENGTH);
+        for (col_n, &population) in oldest_first.enumerate() {
+            let population = population as usize;
+            let level = (population * 4 + MAX_POPULATION as usize / 2) / MAX_POPULATION as usize;
+            matrix[4 - level][col_n] = 9;
+        }
+        matrix
+    }
+}
@@ -0,0 +1,29 @@
+use microbit::pac::POWER;
+
+// Power-fail warning of the nRF52833. The power-fail comparator (POFCON) watches the
+// supply voltage and generates the POFWARN event when it falls below the threshold, so
+// the state can be saved before the supply is too low for the CPU and the flash to
+// work. This happens when the batteries run out or the USB cable is pulled out without
+// batteries, and it leaves a few milliseconds while the capacitors of the board
+// discharge.
+pub struct PowerFail {
+    power: POWER,
+}
+
+impl PowerFail {
+    // Enable the comparator at 2.8 V, below the supply of the board from the USB port
+    // (3.3 V) and from a pair of new batteries (3 V), and its interrupt.
+    pub fn new(power: POWER) -> Self {
+        power.pofcon.write(|w| w.pof().enabled().threshold().v28());
+        power.intenset.write(|w| w.pofwarn().set());
+        Self { power }
+    }
+
+    // Called from the POWER_CLOCK interrupt. Returns true if the supply has fallen below
+    // the threshold.
+    pub fn on_interrupt(&mut self) -> bool {
+        let warned = self.power.events_pofwarn.read().bits() != 0;
+        self.power.events_pofwarn.reset();
+        warned
+    }
+}
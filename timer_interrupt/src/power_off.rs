@@ -0,0 +1,50 @@
+use microbit::pac::{P0, POWER};
+
+// Pins of port 0 that the buttons a and b are connected to. The buttons pull them low
+// when pressed.
+const BUTTON_A_PIN: usize = 14;
+const BUTTON_B_PIN: usize = 23;
+// Pins of port 0 that the rows of the LED display are connected to. A LED can only be
+// lit while its row is high.
+const ROW_PINS: [usize; 5] = [21, 22, 15, 24, 19];
+// Pin of port 0 that powers the microphone and lights the LED next to it.
+const MICROPHONE_POWER_PIN: usize = 20;
+
+// What the board does once it has finished showing that it's going down.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown {
+    // Switch off until button a is pressed.
+    PowerOff,
+    // Restart the firmware right away.
+    Reset,
+}
+
+// Returns true if any of the buttons is being pressed. The pins of the buttons belong
+// to the inputs, so they are read directly from the registers of the port, like the
+// sync pulse writes its pin.
+pub fn buttons_pressed() -> bool {
+    let p0 = unsafe { &*P0::ptr() };
+    p0.in_.read().bits() & (1 << BUTTON_A_PIN | 1 << BUTTON_B_PIN)
+        != (1 << BUTTON_A_PIN | 1 << BUTTON_B_PIN)
+}
+
+// Switch the board off, entering the System OFF mode of the nRF52833, in which only the
+// detection of the changes of the pins keeps running. The pins keep their levels, so
+// the LEDs and the microphone are switched off first. Pressing button a wakes the board
+// up, which resets it, so it starts like when it's switched on and loads the state
+// saved in flash. If button a is still pressed, the board wakes up right away.
+// While a debugger is connected, the System OFF mode is only emulated, so the CPU keeps
+// sleeping here until it's reset.
+pub fn system_off() -> ! {
+    let p0 = unsafe { &*P0::ptr() };
+    let mask = ROW_PINS
+        .iter()
+        .fold(1 << MICROPHONE_POWER_PIN, |mask, pin| mask | 1 << pin);
+    p0.outclr.write(|w| unsafe { w.bits(mask) });
+    p0.pin_cnf[BUTTON_A_PIN].modify(|_, w| w.sense().low());
+    let power = unsafe { &*POWER::ptr() };
+    power.systemoff.write(|w| w.systemoff().enter());
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::framebuffer::LedMatrix;
+use microbit::{
+    gpio::DisplayPins,
+    hal::{
+        gpio::{Output, Pin, PushPull},
+        prelude::*,
+        pwm::{Channel, Pwm},
+        time::Hertz,
+        timer::{Periodic, Timer},
+    },
+    pac::{PWM0, PWM1, TIMER0},
+};
+
+// Maximum brightness of an LED, the same as for the non-blocking display.
+const MAX_BRIGHTNESS: u16 = 9;
+// Frequency of the PWM signal that drives the columns. It must be much higher than the
+// rate at which the rows are switched, so each row sees many PWM periods.
+const PWM_FREQUENCY: Hertz = Hertz(32_000);
+// Time each row is kept on, in microseconds (the timer ticks at 1 MHz). With 5 rows
+// the whole display is refreshed every 10 ms, i.e., at 100 Hz.
+const ROW_PERIOD_US: u32 = 2_000;
+
+// Alternative display driver that dims the LEDs with the PWM peripherals instead of the
+// timer based multiplexing of the non-blocking display. The rows are still scanned one
+// at a time, but the brightness of each column is set by the duty cycle of a PWM
+// channel, which gives flicker-free dimming.
+// The LEDs of the micro:bit are on when their row pin is high and their column pin is
+// low. Each PWM peripheral has 4 channels, so PWM0 drives the first 4 columns and PWM1
+// drives the fifth one.
+pub struct PwmDisplay {
+    rows: [Pin<Output<PushPull>>; 5],
+    pwm0: Pwm<PWM0>,
+    pwm1: Pwm<PWM1>,
+    timer: Timer<TIMER0, Periodic>,
+    // Brightness of each LED:
+    brightness: [[u8; 5]; 5],
+    // Row that is currently on:
+    row: usize,
+}
+
+impl PwmDisplay {
+    pub fn new(timer: TIMER0, pins: DisplayPins, pwm0: PWM0, pwm1: PWM1) -> Self {
+        let pwm0 = Pwm::new(pwm0);
+        pwm0.set_period(PWM_FREQUENCY);
+        pwm0.set_output_pin(Channel::C0, pins.col1.degrade());
+        pwm0.set_output_pin(Channel::C1, pins.col2.degrade());
+        pwm0.set_output_pin(Channel::C2, pins.col3.degrade());
+        pwm0.set_output_pin(Channel::C3, pins.col4.degrade());
+        pwm0.enable();
+
+        let pwm1 = Pwm::new(pwm1);
+        pwm1.set_period(PWM_FREQUENCY);
+        pwm1.set_output_pin(Channel::C0, pins.col5.degrade());
+        pwm1.enable();
+
+        // The timer interrupt is used to switch from one row to the next.
+        let mut timer = Timer::periodic(timer);
+        timer.enable_interrupt();
+        timer.start(ROW_PERIOD_US);
+
+        let mut display = Self {
+            rows: [
+                pins.row1.degrade(),
+                pins.row2.degrade(),
+                pins.row3.degrade(),
+                pins.row4.degrade(),
+                pins.row5.degrade(),
+            ],
+            pwm0,
+            pwm1,
+            timer,
+            brightness: [[0; 5]; 5],
+            row: 0,
+        };
+        for row in display.rows.iter_mut() {
+            row.set_low().ok();
+        }
+        display
+    }
+
+    // Switch off the current row and switch on the next one. It must be called from
+    // the TIMER0 interrupt handler.
+    pub fn handle_display_event(&mut self) {
+        // Reading the timer resets its event, which is what triggers the interrupt.
+        self.timer.wait().ok();
+
+        self.rows[self.row].set_low().ok();
+        self.row = (self.row + 1) % 5;
+
+        // The duty cycles are set while all the rows are off, so no LED of the new row
+        // shows the brightness of the previous one. The duty cycle is inverted (the
+        // "off" duty), because the LEDs are on while their column pin is low.
+        let brightness = self.brightness[self.row];
+        let channels = [Channel::C0, Channel::C1, Channel::C2, Channel::C3];
+        for (channel, led_brightness) in channels.into_iter().zip(brightness) {
+            self.pwm0
+                .set_duty_off(channel, self.duty(led_brightness, self.pwm0.max_duty()));
+        }
+        self.pwm1
+            .set_duty_off(Channel::C0, self.duty(brightness[4], self.pwm1.max_duty()));
+
+        self.rows[self.row].set_high().ok();
+    }
+
+    // Duty cycle of the PWM channel for a given brightness of the LED.
+    fn duty(&self, brightness: u8, max_duty: u16) -> u16 {
+        (brightness as u16).min(MAX_BRIGHTNESS) * (max_duty / MAX_BRIGHTNESS)
+    }
+}
+
+impl LedMatrix for PwmDisplay {
+    fn show_brightness(&mut self, brightness: &[[u8; 5]; 5]) {
+        self.brightness = *brightness;
+    }
+}
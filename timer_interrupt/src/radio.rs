@@ -0,0 +1,136 @@
+use core::sync::atomic::{compiler_fence, Ordering};
+use heapless::Vec;
+use microbit::pac::RADIO;
+
+// Maximum size of the payload of a packet.
+pub const MAX_PAYLOAD: usize = 32;
+// Radio channel, the frequency is 2400 MHz + CHANNEL MHz. The rest of the settings of
+// the radio (the addresses, the CRC and the data whitening) are the ones used by the
+// radio of the micro:bit runtime, which is used by MakeCode and MicroPython.
+const CHANNEL: u8 = 7;
+const BASE_ADDRESS: u32 = 0x7562_6974;
+pub const GROUP: u8 = 0;
+const CRC_INIT: u32 = 0xffff;
+const CRC_POLY: u32 = 0x1_1021;
+const DATA_WHITENING_IV: u8 = 0x18;
+
+pub type Payload = Vec<u8, MAX_PAYLOAD>;
+
+// Driver of the 2.4 GHz radio of the nRF52833, in the proprietary 1 Mbit/s mode. The
+// radio is always listening, and every packet received with a valid CRC is returned by
+// on_interrupt(). Packets are sent by stopping the reception, sending them and going
+// back to listening. The packets are copied to and from RAM with EasyDMA, through a
+// single buffer: the length of the payload followed by the payload.
+pub struct Radio {
+    radio: RADIO,
+    buffer: &'static mut [u8; MAX_PAYLOAD + 1],
+}
+
+impl Radio {
+    // Configure the radio and start listening. It needs the external high frequency
+    // oscillator to be running.
+    pub fn new(radio: RADIO) -> Self {
+        let buffer = cortex_m::singleton!(: [u8; MAX_PAYLOAD + 1] = [0; MAX_PAYLOAD + 1]).unwrap();
+        radio.mode.write(|w| w.mode().nrf_1mbit());
+        radio
+            .frequency
+            .write(|w| unsafe { w.frequency().bits(CHANNEL) });
+        radio.txpower.write(|w| w.txpower()._0d_bm());
+        // The packets have a length field of 8 bits and no S0 and S1 fields.
+        radio
+            .pcnf0
+            .write(|w| unsafe { w.lflen().bits(8).s0len().clear_bit().s1len().bits(0) });
+        radio.pcnf1.write(|w| unsafe {
+            w.maxlen()
+                .bits(MAX_PAYLOAD as u8)
+                .statlen()
+                .bits(0)
+                .balen()
+                .bits(4)
+                .endian()
+                .little()
+                .whiteen()
+                .enabled()
+        });
+        radio.base0.write(|w| unsafe { w.bits(BASE_ADDRESS) });
+        radio.prefix0.write(|w| unsafe { w.ap0().bits(GROUP) });
+        radio.txaddress.write(|w| unsafe { w.txaddress().bits(0) });
+        radio.rxaddresses.write(|w| w.addr0().enabled());
+        radio.crccnf.write(|w| w.len().two().skipaddr().include());
+        radio
+            .crcinit
+            .write(|w| unsafe { w.crcinit().bits(CRC_INIT) });
+        radio
+            .crcpoly
+            .write(|w| unsafe { w.crcpoly().bits(CRC_POLY) });
+        radio
+            .datawhiteiv
+            .write(|w| unsafe { w.datawhiteiv().bits(DATA_WHITENING_IV) });
+        radio
+            .packetptr
+            .write(|w| unsafe { w.bits(buffer.as_ptr() as u32) });
+        radio.intenset.write(|w| w.end().set());
+
+        let mut radio = Self { radio, buffer };
+        radio.listen();
+        radio
+    }
+
+    // Send a packet. It blocks until the packet has been sent, which takes less than half
+    // a millisecond. A packet being received at the same time is lost.
+    pub fn send(&mut self, payload: &Payload) {
+        self.disable();
+        self.buffer[0] = payload.len() as u8;
+        self.buffer[1..=payload.len()].copy_from_slice(payload);
+        compiler_fence(Ordering::SeqCst);
+
+        // The end of the transmission doesn't trigger the interrupt, which is only for
+        // the packets received.
+        self.radio.intenclr.write(|w| w.end().clear());
+        self.radio
+            .shorts
+            .write(|w| w.ready_start().enabled().end_disable().enabled());
+        self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+        self.radio.events_end.reset();
+        self.radio.intenset.write(|w| w.end().set());
+
+        self.listen();
+    }
+
+    // Handle the events of the radio. Returns the payload of the packet received, if a
+    // packet has been received with a valid CRC. It must be called from the RADIO
+    // interrupt.
+    pub fn on_interrupt(&mut self) -> Option<Payload> {
+        if self.radio.events_end.read().bits() == 0 {
+            return None;
+        }
+        self.radio.events_end.reset();
+        compiler_fence(Ordering::SeqCst);
+        let payload = if self.radio.crcstatus.read().crcstatus().is_crcok() {
+            let length = (self.buffer[0] as usize).min(MAX_PAYLOAD);
+            Vec::from_slice(&self.buffer[1..=length]).ok()
+        } else {
+            None
+        };
+        // Wait for the next packet.
+        self.radio.tasks_start.write(|w| unsafe { w.bits(1) });
+        payload
+    }
+
+    // Start listening for packets. When the receiver is ready, it starts receiving
+    // right away.
+    fn listen(&mut self) {
+        self.radio.shorts.write(|w| w.ready_start().enabled());
+        self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+    }
+
+    fn disable(&mut self) {
+        self.radio.shorts.reset();
+        self.radio.events_disabled.reset();
+        self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+    }
+}
@@ -0,0 +1,177 @@
+use crate::radio::{Payload, MAX_PAYLOAD};
+use heapless::LinearMap;
+use microbit::hal::ccm::{Ccm, CcmData, DataRate};
+use microbit::pac::{AAR, CCM};
+
+// First byte of the encrypted packets.
+const ENCRYPTED_ID: u8 = b'E';
+// Length of the nonce sent with every packet: the identifier of the board (2 bytes) and
+// the number of the packet (4 bytes).
+const NONCE_LEN: usize = 6;
+// Length of the message integrity check added by the CCM.
+const MIC_LEN: usize = 4;
+// Length of the header of the packets of the CCM: S0, the length and S1.
+const HEADER_LEN: usize = 3;
+// Size of the scratch area used by the CCM, enough for the longest packet.
+const SCRATCH_LEN: usize = 43;
+// Maximum length of a packet before it's encrypted.
+const MAX_CLEAR_LEN: usize = MAX_PAYLOAD - 1 - NONCE_LEN - MIC_LEN;
+// Number of boards whose last packet is remembered, to drop the packets that are
+// replayed. When more boards are heard, the one heard the longest ago is forgotten.
+const MAX_SENDERS: usize = 16;
+// Time after which a board that hasn't sent any packet is forgotten, in milliseconds.
+const SENDER_TIMEOUT_MS: u64 = 10_000;
+
+// Optional encryption of the packets of the radio, with a key shared by the boards. The
+// packets are encrypted and signed with AES-CCM by the CCM peripheral, so a board
+// without the key can neither read them nor forge them. Every packet carries the nonce
+// it was encrypted with: the identifier of the board and a number that goes up with
+// every packet, starting from a random value, so that no nonce is used twice with the
+// same key. Without a key the packets go through unchanged, and with a key the packets
+// that are not encrypted with it are dropped, including the ones of MicroPython and
+// MakeCode.
+// The number of the last packet accepted from every board is remembered, and a packet
+// whose number is not past it is dropped, so a packet recorded and sent again later
+// can't be used to control the board. A board that restarts starts from a new random
+// number, which may be behind the one remembered, so a board that hasn't been heard
+// for SENDER_TIMEOUT_MS is forgotten, and its packets are accepted again from then on.
+pub struct RadioCipher {
+    ccm: Ccm,
+    key: Option<[u8; 16]>,
+    id: u16,
+    counter: u32,
+    // The last packet accepted from every board, by the identifier of the board:
+    senders: LinearMap<u16, Sender, MAX_SENDERS>,
+}
+
+// The number of the last packet accepted from a board and the uptime at which it was
+// received, in milliseconds.
+#[derive(Clone, Copy)]
+struct Sender {
+    counter: u32,
+    heard_at: u64,
+}
+
+impl RadioCipher {
+    pub fn new(ccm: CCM, aar: AAR, id: u16, counter: u32) -> Self {
+        Self {
+            ccm: Ccm::init(ccm, aar, DataRate::_1Mbit),
+            key: None,
+            id,
+            counter,
+            senders: LinearMap::new(),
+        }
+    }
+
+    // The numbers of the packets of the boards are only meaningful with the key they
+    // were accepted with, so they are forgotten when it changes.
+    pub fn set_key(&mut self, key: Option<[u8; 16]>) {
+        self.key = key;
+        self.senders.clear();
+    }
+
+    // Encrypt a packet, if there is a key. Packets longer than MAX_CLEAR_LEN can't be
+    // encrypted, and are not sent.
+    pub fn seal(&mut self, payload: &Payload) -> Option<Payload> {
+        let Some(key) = self.key else {
+            return Some(payload.clone());
+        };
+        if payload.len() > MAX_CLEAR_LEN {
+            return None;
+        }
+        self.counter = self.counter.wrapping_add(1);
+        let mut nonce = [0; NONCE_LEN];
+        nonce[..2].copy_from_slice(&self.id.to_le_bytes());
+        nonce[2..].copy_from_slice(&self.counter.to_le_bytes());
+
+        let mut clear = [0; HEADER_LEN + MAX_CLEAR_LEN];
+        clear[1] = payload.len() as u8;
+        clear[HEADER_LEN..HEADER_LEN + payload.len()].copy_from_slice(payload);
+        let mut cipher = [0; HEADER_LEN + MAX_CLEAR_LEN + MIC_LEN];
+        let mut scratch = [0; SCRATCH_LEN];
+        let mut data = CcmData::new(key, initialization_vector(nonce));
+        self.ccm
+            .encrypt_packet(&mut data, &clear, &mut cipher, &mut scratch)
+            .ok()?;
+
+        let mut sealed = Payload::new();
+        sealed.push(ENCRYPTED_ID).ok();
+        sealed.extend_from_slice(&nonce).ok();
+        sealed
+            .extend_from_slice(&cipher[HEADER_LEN..HEADER_LEN + payload.len() + MIC_LEN])
+            .ok();
+        Some(sealed)
+    }
+
+    // Decrypt a packet, if there is a key, given the uptime at which it was received, in
+    // milliseconds. Returns None if the packet is not encrypted with the key, or if it
+    // has been received before.
+    pub fn open(&mut self, payload: &[u8], now_ms: u64) -> Option<Payload> {
+        let Some(key) = self.key else {
+            return Payload::from_slice(payload).ok();
+        };
+        let [ENCRYPTED_ID, rest @ ..] = payload else {
+            return None;
+        };
+        if rest.len() < NONCE_LEN + MIC_LEN {
+            return None;
+        }
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        let id = u16::from_le_bytes([nonce[0], nonce[1]]);
+        let counter = u32::from_le_bytes([nonce[2], nonce[3], nonce[4], nonce[5]]);
+        if !self.is_fresh(id, counter, now_ms) {
+            return None;
+        }
+
+        let mut cipher = [0; HEADER_LEN + MAX_CLEAR_LEN + MIC_LEN];
+        cipher[1] = sealed.len() as u8;
+        cipher[HEADER_LEN..HEADER_LEN + sealed.len()].copy_from_slice(sealed);
+        let mut clear = [0; HEADER_LEN + MAX_CLEAR_LEN + MIC_LEN];
+        let mut scratch = [0; SCRATCH_LEN];
+        let mut data = CcmData::new(key, initialization_vector(nonce.try_into().ok()?));
+        self.ccm
+            .decrypt_packet(&mut data, &mut clear, &cipher, &mut scratch)
+            .ok()?;
+        // Only the packets signed with the key are remembered, so a forged packet can't
+        // make the board drop the real ones.
+        self.remember(id, counter, now_ms);
+        Payload::from_slice(&clear[HEADER_LEN..HEADER_LEN + sealed.len() - MIC_LEN]).ok()
+    }
+
+    // Returns true if a packet with the given number is past the last one accepted
+    // from its board. The numbers wrap around, so the ones up to half of their range
+    // ahead are past it.
+    fn is_fresh(&self, id: u16, counter: u32, now_ms: u64) -> bool {
+        match self.senders.get(&id) {
+            Some(sender) if now_ms - sender.heard_at < SENDER_TIMEOUT_MS => {
+                (counter.wrapping_sub(sender.counter) as i32) > 0
+            }
+            _ => true,
+        }
+    }
+
+    fn remember(&mut self, id: u16, counter: u32, now_ms: u64) {
+        let sender = Sender {
+            counter,
+            heard_at: now_ms,
+        };
+        if self.senders.len() == MAX_SENDERS && !self.senders.contains_key(&id) {
+            let oldest = self
+                .senders
+                .iter()
+                .min_by_key(|(_, sender)| sender.heard_at)
+                .map(|(&id, _)| id);
+            if let Some(oldest) = oldest {
+                self.senders.remove(&oldest);
+            }
+        }
+        self.senders.insert(id, sender).ok();
+    }
+}
+
+// The initialization vector of the CCM is the nonce of the packet, padded with zeros.
+fn initialization_vector(nonce: [u8; NONCE_LEN]) -> [u8; 8] {
+    let mut initialization_vector = [0; 8];
+    initialization_vector[..NONCE_LEN].copy_from_slice(&nonce);
+    initialization_vector
+}
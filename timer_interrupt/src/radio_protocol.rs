@@ -0,0 +1,272 @@
+use crate::crc::crc16;
+use crate::radio::Payload;
+use crate::stitch::Edges;
+
+// First byte of the packets of this firmware, so that the packets of other programs
+// using the same radio settings are ignored.
+const PROTOCOL_ID: u8 = b'L';
+// Types of the messages, the second byte of the packets.
+const GRID_TYPE: u8 = 0x01;
+const EDGES_TYPE: u8 = 0x02;
+const REMOTE_TYPE: u8 = 0x03;
+const RESEND_TYPE: u8 = 0x04;
+// Length of the trailer of the packets: the identifier of the sender (2 bytes), the
+// sequence number (a byte) and the CRC (2 bytes).
+const TRAILER_LEN: usize = 5;
+// Number of senders whose last sequence numbers are remembered.
+const MAX_SENDERS: usize = 8;
+
+// What the board does with the radio. It's selected in the settings menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RadioRole {
+    // Nothing is sent, and only the commands of remote controls are received.
+    Off,
+    // Broadcast the grid of every generation.
+    Send,
+    // Show the grids broadcast by another board, instead of running the game.
+    View,
+    // Simulate half of a world shared with another board, exchanging the edges of the
+    // grids.
+    Pair,
+    // Simulate a tile of a world shared with several boards, exchanging the edges of the
+    // grids with the boards of the tiles around it.
+    Tile,
+    // Act as a remote control of other boards: the buttons send commands instead of
+    // controlling the game of this board, which stops.
+    Remote,
+}
+
+// The commands sent by a remote control.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RemoteCommand {
+    // Pause the game if it's running, resume it if it's paused.
+    TogglePause,
+    // Pause the game and advance it one generation.
+    Step,
+    // Fill the grid with a random soup.
+    Seed,
+    Slower,
+    Faster,
+}
+
+impl RemoteCommand {
+    // The commands, in the order of their numbers in the packets.
+    const ALL: [Self; 5] = [
+        RemoteCommand::TogglePause,
+        RemoteCommand::Step,
+        RemoteCommand::Seed,
+        RemoteCommand::Slower,
+        RemoteCommand::Faster,
+    ];
+}
+
+impl RadioRole {
+    // Returns true if the board shares a world with other boards.
+    pub fn is_stitched(self) -> bool {
+        matches!(self, RadioRole::Pair | RadioRole::Tile)
+    }
+}
+
+// The messages sent between boards. Every packet starts with PROTOCOL_ID and the type of
+// the message, followed by its fields and the trailer added by the Link. The numbers are
+// little endian.
+#[derive(Clone, Copy)]
+pub enum RadioMessage {
+    // The grid of a generation: the number of the generation (4 bytes) and a byte per
+    // row, from top to bottom, in which the bit n is the cell of the column n.
+    Grid {
+        generation: u32,
+        matrix: [[bool; 5]; 5],
+    },
+    // The edges of the grid of a board of a stitched world: the number of the generation
+    // (4 bytes), the column and the row of the tile of the board (a byte each), and the
+    // edges of the generation and of the previous one (4 bytes each: the top row, the
+    // bottom row, the left column and the right column, in which the bit n is the cell
+    // n of the row or column).
+    Edges {
+        generation: u32,
+        position: (u8, u8),
+        edges: Edges,
+        previous_edges: Edges,
+    },
+    // A command of a remote control: its number (a byte).
+    Remote(RemoteCommand),
+    // A request to send the state again, sent by a board of a stitched world that is
+    // waiting for the edges of the given generation (4 bytes).
+    Resend {
+        generation: u32,
+    },
+}
+
+impl RadioMessage {
+    pub fn encode(&self) -> Payload {
+        let mut payload = Payload::new();
+        // None of the messages is longer than a packet, so the pushes can't fail.
+        match *self {
+            RadioMessage::Grid { generation, matrix } => {
+                payload.extend_from_slice(&[PROTOCOL_ID, GRID_TYPE]).ok();
+                payload.extend_from_slice(&generation.to_le_bytes()).ok();
+                for row in matrix {
+                    payload.push(encode_row(row)).ok();
+                }
+            }
+            RadioMessage::Edges {
+                generation,
+                position: (x, y),
+                edges,
+                previous_edges,
+            } => {
+                payload.extend_from_slice(&[PROTOCOL_ID, EDGES_TYPE]).ok();
+                payload.extend_from_slice(&generation.to_le_bytes()).ok();
+                payload.extend_from_slice(&[x, y]).ok();
+                payload.extend_from_slice(&encode_edges(edges)).ok();
+                payload
+                    .extend_from_slice(&encode_edges(previous_edges))
+                    .ok();
+            }
+            RadioMessage::Remote(command) => {
+                payload
+                    .extend_from_slice(&[PROTOCOL_ID, REMOTE_TYPE, command as u8])
+                    .ok();
+            }
+            RadioMessage::Resend { generation } => {
+                payload.extend_from_slice(&[PROTOCOL_ID, RESEND_TYPE]).ok();
+                payload.extend_from_slice(&generation.to_le_bytes()).ok();
+            }
+        }
+        payload
+    }
+
+    // Decode the fields of a message. Returns None if they are not a valid message of
+    // this protocol.
+    fn decode(payload: &[u8]) -> Option<Self> {
+        match payload {
+            [PROTOCOL_ID, GRID_TYPE, generation @ .., r0, r1, r2, r3, r4] => {
+                let generation = u32::from_le_bytes(generation.try_into().ok()?);
+                Some(RadioMessage::Grid {
+                    generation,
+                    matrix: [r0, r1, r2, r3, r4].map(|&bits| decode_row(bits)),
+                })
+            }
+            [PROTOCOL_ID, EDGES_TYPE, g0, g1, g2, g3, x, y, edges @ ..] if edges.len() == 8 => {
+                Some(RadioMessage::Edges {
+                    generation: u32::from_le_bytes([*g0, *g1, *g2, *g3]),
+                    position: (*x, *y),
+                    edges: decode_edges(&edges[..4]),
+                    previous_edges: decode_edges(&edges[4..]),
+                })
+            }
+            [PROTOCOL_ID, REMOTE_TYPE, command_n] => RemoteCommand::ALL
+                .get(*command_n as usize)
+                .map(|&command| RadioMessage::Remote(command)),
+            [PROTOCOL_ID, RESEND_TYPE, g0, g1, g2, g3] => Some(RadioMessage::Resend {
+                generation: u32::from_le_bytes([*g0, *g1, *g2, *g3]),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// The end of the radio link of a board. Every packet gets a trailer with the identifier
+// of the board, a sequence number and a CRC of the whole packet. The radio already checks
+// its own CRC, but this one also rejects the packets of other programs that happen to
+// start like the ones of this protocol, so a corrupted packet can't install a garbage
+// grid. The last sequence number of every sender is remembered, and a packet that has
+// already been received (a command sent twice, or an answer to a resend request that
+// was already handled) is dropped.
+pub struct Link {
+    id: u16,
+    sequence: u8,
+    // The last sequence number received from each sender, and the slot of the next
+    // sender to be forgotten when a new one appears:
+    senders: [Option<(u16, u8)>; MAX_SENDERS],
+    next_sender: usize,
+}
+
+impl Link {
+    // The identifier of the board must be unique among the boards in range, e.g. a part
+    // of the identifier of the chip.
+    pub fn new(id: u16) -> Self {
+        Self {
+            id,
+            sequence: 0,
+            senders: [None; MAX_SENDERS],
+            next_sender: 0,
+        }
+    }
+
+    // Encode a message as a packet, with its trailer.
+    pub fn encode(&mut self, message: &RadioMessage) -> Payload {
+        let mut payload = message.encode();
+        self.sequence = self.sequence.wrapping_add(1);
+        // The longest message leaves room for the trailer.
+        payload.extend_from_slice(&self.id.to_le_bytes()).ok();
+        payload.push(self.sequence).ok();
+        let crc = crc16(&payload);
+        payload.extend_from_slice(&crc.to_le_bytes()).ok();
+        payload
+    }
+
+    // Decode a packet. Returns None if it's not a valid message of this protocol, if its
+    // CRC is wrong, or if it has already been received.
+    pub fn decode(&mut self, payload: &[u8]) -> Option<RadioMessage> {
+        let (packet, crc) = payload.split_at(payload.len().checked_sub(2)?);
+        if crc16(packet).to_le_bytes() != crc {
+            return None;
+        }
+        let (fields, trailer) = payload.split_at(payload.len().checked_sub(TRAILER_LEN)?);
+        let sender = u16::from_le_bytes([trailer[0], trailer[1]]);
+        let sequence = trailer[2];
+        let message = RadioMessage::decode(fields)?;
+        self.record(sender, sequence).then_some(message)
+    }
+
+    // Record the sequence number of a packet. Returns false if it's the last one received
+    // from the same sender.
+    fn record(&mut self, sender: u16, sequence: u8) -> bool {
+        let known = self
+            .senders
+            .iter_mut()
+            .flatten()
+            .find(|(known_sender, _)| *known_sender == sender);
+        match known {
+            Some((_, last_sequence)) if *last_sequence == sequence => false,
+            Some((_, last_sequence)) => {
+                *last_sequence = sequence;
+                true
+            }
+            None => {
+                self.senders[self.next_sender] = Some((sender, sequence));
+                self.next_sender = (self.next_sender + 1) % MAX_SENDERS;
+                true
+            }
+        }
+    }
+}
+
+fn encode_row(row: [bool; 5]) -> u8 {
+    row.into_iter()
+        .enumerate()
+        .fold(0, |bits, (col_n, alive)| bits | (alive as u8) << col_n)
+}
+
+fn decode_row(bits: u8) -> [bool; 5] {
+    let mut row = [false; 5];
+    for (col_n, alive) in row.iter_mut().enumerate() {
+        *alive = bits & (1 << col_n) != 0;
+    }
+    row
+}
+
+fn encode_edges(edges: Edges) -> [u8; 4] {
+    [edges.top, edges.bottom, edges.left, edges.right].map(encode_row)
+}
+
+fn decode_edges(bytes: &[u8]) -> Edges {
+    Edges {
+        top: decode_row(bytes[0]),
+        bottom: decode_row(bytes[1]),
+        left: decode_row(bytes[2]),
+        right: decode_row(bytes[3]),
+    }
+}
@@ -0,0 +1,58 @@
+use crate::radio::{Payload, GROUP};
+
+// Header of the packets of the radio of the micro:bit runtime: the version of the
+// protocol, the group and the type of the packets, which is always 1 (datagram).
+const VERSION: u8 = 1;
+const DATAGRAM: u8 = 1;
+// MicroPython sends the strings with this prefix, to tell them from raw bytes.
+const MICROPYTHON_STRING: [u8; 3] = [0x01, 0x00, 0x01];
+// Types of the packets of MakeCode, the first byte after the header. They are followed
+// by the time at which the packet was sent (in milliseconds, 4 bytes) and the serial
+// number of the sender (4 bytes), which this firmware leaves at 0.
+const MAKECODE_VALUE: u8 = 1;
+const MAKECODE_STRING: u8 = 2;
+// Maximum length of the names of the values of MakeCode.
+const MAKECODE_MAX_NAME: usize = 8;
+
+// Returns the text of a string sent with the radio module of MicroPython
+// (`radio.send("pause")`) or of MakeCode (`radio.sendString("pause")`), if the packet
+// is one of them.
+pub fn decode(payload: &[u8]) -> Option<&str> {
+    let text = match payload {
+        [VERSION, GROUP, DATAGRAM, packet @ ..] if packet.starts_with(&MICROPYTHON_STRING) => {
+            &packet[MICROPYTHON_STRING.len()..]
+        }
+        // The time and the serial number are followed by the length of the string.
+        [VERSION, GROUP, DATAGRAM, MAKECODE_STRING, _, _, _, _, _, _, _, _, length, text @ ..] => {
+            text.get(..*length as usize)?
+        }
+        _ => return None,
+    };
+    core::str::from_utf8(text).ok()
+}
+
+// Encode a string the way MicroPython does, so that `radio.receive()` returns it. It's
+// cut if it's too long for a packet.
+pub fn encode_micropython(text: &str) -> Payload {
+    let mut payload = Payload::new();
+    payload.extend_from_slice(&[VERSION, GROUP, DATAGRAM]).ok();
+    payload.extend_from_slice(&MICROPYTHON_STRING).ok();
+    let length = text.len().min(payload.capacity() - payload.len());
+    payload.extend_from_slice(&text.as_bytes()[..length]).ok();
+    payload
+}
+
+// Encode a number with a name the way MakeCode does, so that it's received by
+// `radio.onReceivedValue()`. The name is cut to the 8 characters that MakeCode allows.
+pub fn encode_makecode_value(name: &str, value: i32) -> Payload {
+    let mut payload = Payload::new();
+    payload
+        .extend_from_slice(&[VERSION, GROUP, DATAGRAM, MAKECODE_VALUE])
+        .ok();
+    payload.extend_from_slice(&[0; 8]).ok();
+    payload.extend_from_slice(&value.to_le_bytes()).ok();
+    let name = &name.as_bytes()[..name.len().min(MAKECODE_MAX_NAME)];
+    payload.push(name.len() as u8).ok();
+    payload.extend_from_slice(name).ok();
+    payload
+}
@@ -0,0 +1,153 @@
+use crate::command::CommandError;
+use crate::rules::RULES;
+use core::fmt::{self, Write};
+
+// A pattern pasted in RLE, the format that Golly puts on the clipboard when a selection
+// is copied, placed in the middle of the grid.
+#[derive(Clone, Copy)]
+pub struct RlePattern {
+    pub matrix: [[bool; 5]; 5],
+    // Index of the rule given in the header, if there is one:
+    pub rule: Option<usize>,
+}
+
+// Returns true if a line is the header of a pattern in RLE, e.g.,
+// "x = 3, y = 3, rule = B3/S23".
+pub fn is_header(line: &str) -> bool {
+    line.strip_prefix('x')
+        .is_some_and(|rest| rest.trim_start().starts_with('='))
+}
+
+// Reads a pattern in RLE, given as the header line followed by any number of lines
+// with the cells. The cells are runs of the same state, a number of cells (1 if it's
+// missing) followed by b for dead cells or o for live ones, and the rows are separated
+// by $, also preceded by an optional number of rows. The pattern ends with a !. The
+// patterns fit in the grid, so a run longer than the grid is invalid.
+pub struct RleReader {
+    pattern: RlePattern,
+    // Position of the top left corner of the pattern in the grid:
+    left: usize,
+    top: usize,
+    // Position of the next cell, relative to the corner:
+    x: usize,
+    y: usize,
+    // The digits of the number of the run being read:
+    run: usize,
+}
+
+impl RleReader {
+    // Start reading a pattern from its header, which gives the size of the pattern and,
+    // optionally, its rule. Patterns larger than the grid and unknown rules are invalid.
+    pub fn new(header: &str) -> Result<Self, CommandError> {
+        let mut width = None;
+        let mut height = None;
+        let mut rule = None;
+        for field in header.split(',') {
+            let (name, value) = field.split_once('=').ok_or(CommandError::InvalidArgument)?;
+            let value = value.trim();
+            match name.trim() {
+                "x" => width = value.parse::<usize>().ok(),
+                "y" => height = value.parse::<usize>().ok(),
+                "rule" => {
+                    // Golly adds the size of bounded grids after a colon, like
+                    // B3/S23:T5,5, which is dropped.
+                    let rulestring = value.split(':').next().unwrap_or(value);
+                    rule = Some(
+                        RULES
+                            .iter()
+                            .position(|rule| rule.rulestring.eq_ignore_ascii_case(rulestring))
+                            .ok_or(CommandError::InvalidArgument)?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        let (width, height) = width.zip(height).ok_or(CommandError::InvalidArgument)?;
+        if width > 5 || height > 5 {
+            return Err(CommandError::InvalidArgument);
+        }
+        Ok(Self {
+            pattern: RlePattern {
+                matrix: [[false; 5]; 5],
+                rule,
+            },
+            left: (5 - width) / 2,
+            top: (5 - height) / 2,
+            x: 0,
+            y: 0,
+            run: 0,
+        })
+    }
+
+    // Read a line with cells of the pattern. Returns the pattern once its end has been
+    // read, or None if more lines are needed.
+    pub fn read(&mut self, line: &str) -> Result<Option<RlePattern>, CommandError> {
+        for c in line.chars() {
+            let count = self.run.max(1);
+            match c {
+                '0'..='9' => {
+                    self.run = self.run * 10 + c as usize - '0' as usize;
+                    if self.run > 5 {
+                        return Err(CommandError::InvalidArgument);
+                    }
+                    continue;
+                }
+                'b' | '.' => self.x = self.x.saturating_add(count),
+                'o' | 'A'..='Z' => {
+                    for _ in 0..count {
+                        let x = self.left.saturating_add(self.x);
+                        let y = self.top.saturating_add(self.y);
+                        if x >= 5 || y >= 5 {
+                            return Err(CommandError::InvalidArgument);
+                        }
+                        self.pattern.matrix[y][x] = true;
+                        self.x += 1;
+                    }
+                }
+                '$' => {
+                    self.x = 0;
+                    self.y = self.y.saturating_add(count);
+                }
+                '!' => return Ok(Some(self.pattern)),
+                c if c.is_whitespace() => {}
+                _ => return Err(CommandError::InvalidArgument),
+            }
+            self.run = 0;
+        }
+        Ok(None)
+    }
+}
+
+// Write a grid in RLE, with the header that gives its size and its rule, so it can be
+// pasted in Golly. The dead cells at the end of the rows are left out, and so are the
+// empty rows at the end of the grid.
+pub fn write_rle(out: &mut impl Write, matrix: [[bool; 5]; 5], rulestring: &str) -> fmt::Result {
+    write!(out, "x = 5, y = 5, rule = {}\r\n", rulestring)?;
+    // Row that the cells written next go to:
+    let mut row_n = 0;
+    for (y, row) in matrix.iter().enumerate() {
+        let Some(last) = row.iter().rposition(|&alive| alive) else {
+            continue;
+        };
+        if y > row_n {
+            write_run(out, y - row_n, '$')?;
+            row_n = y;
+        }
+        let mut x = 0;
+        while x <= last {
+            let alive = row[x];
+            let length = row[x..=last].iter().take_while(|&&a| a == alive).count();
+            write_run(out, length, if alive { 'o' } else { 'b' })?;
+            x += length;
+        }
+    }
+    out.write_str("!\r\n")
+}
+
+// Write a run of the same tag, leaving out the number when it's 1.
+fn write_run(out: &mut impl Write, length: usize, tag: char) -> fmt::Result {
+    if length > 1 {
+        write!(out, "{}", length)?;
+    }
+    out.write_char(tag)
+}
@@ -0,0 +1,54 @@
+use crate::shell::{Line, Terminal};
+use core::fmt;
+use rtt_target::{rprint, DownChannel};
+
+// Terminal over RTT, the channels that the debug probe reads from and writes to the RAM
+// of the microcontroller while it runs. The host sends the text through a down channel,
+// e.g., typing it in the terminal of probe-rs or cargo-embed, so the game can be
+// controlled without any extra wiring. The answers are sent through the print channel,
+// which is also used by the panic handler. The terminals of the host send whole lines
+// and show them while they are typed, so nothing is echoed back.
+pub struct RttTerminal {
+    channel: DownChannel,
+    // The line being received:
+    line: Line,
+    // The last byte received. Most terminals end the lines with a carriage return and a
+    // line feed, which is a single line ending:
+    last_byte: u8,
+}
+
+impl RttTerminal {
+    pub fn new(channel: DownChannel) -> Self {
+        Self {
+            channel,
+            line: Line::new(),
+            last_byte: 0,
+        }
+    }
+}
+
+impl Terminal for RttTerminal {
+    fn read_line(&mut self) -> Option<Line> {
+        // The bytes are read one by one, so the ones after the end of a line are left in
+        // the channel until the next call.
+        let mut byte = [0];
+        while self.channel.read(&mut byte) > 0 {
+            let last_byte = core::mem::replace(&mut self.last_byte, byte[0]);
+            match byte[0] {
+                b'\n' if last_byte == b'\r' => {}
+                b'\r' | b'\n' => return Some(core::mem::take(&mut self.line)),
+                byte => {
+                    self.line.push(byte as char).ok();
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Write for RttTerminal {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        rprint!("{}", s);
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+// A rule of a life-like cellular automaton. It's given by the numbers of live neighbors
+// that make a dead cell be born and the ones that keep a live cell alive. Any other
+// live cell dies.
+pub struct Rule {
+    pub name: &'static str,
+    pub rulestring: &'static str,
+    // Each bit set in these masks is a number of neighbors (from 0 to 8) that causes a
+    // birth or a survival:
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    // Create a rule from its rulestring in B/S notation, e.g., "B3/S23" for Conway's
+    // game of Life: a cell is born with 3 neighbors and survives with 2 or 3. It's a
+    // const function so the rulestrings are checked at compile time.
+    pub const fn new(name: &'static str, rulestring: &'static str) -> Self {
+        let bytes = rulestring.as_bytes();
+        let mut birth = 0;
+        let mut survival = 0;
+        let mut in_survival = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'B' | b'b' => in_survival = false,
+                b'S' | b's' => in_survival = true,
+                b'/' => {}
+                digit @ b'0'..=b'8' => {
+                    let bit = 1 << (digit - b'0');
+                    if in_survival {
+                        survival |= bit;
+                    } else {
+                        birth |= bit;
+                    }
+                }
+                _ => panic!("invalid character in rulestring"),
+            }
+            i += 1;
+        }
+        Self {
+            name,
+            rulestring,
+            birth,
+            survival,
+        }
+    }
+
+    // Returns whether a cell is alive in the next generation, given whether it's alive
+    // now and its number of live neighbors.
+    pub fn next_cell(&self, alive: bool, n_neighbors: u8) -> bool {
+        let mask = if alive { self.survival } else { self.birth };
+        mask & (1 << n_neighbors) != 0
+    }
+}
+
+// Rules that can be selected in the settings menu. The first one is used by default.
+pub const RULES: [Rule; 4] = [
+    Rule::new("LIFE", "B3/S23"),
+    Rule::new("HIGHLIFE", "B36/S23"),
+    Rule::new("SEEDS", "B2/S"),
+    Rule::new("DAYNIGHT", "B3678/S34678"),
+];
@@ -0,0 +1,80 @@
+// Initial value of the state of the generator. Any value other than 0 works.
+const INITIAL_STATE: u32 = 0x2545_f491;
+
+// Pseudo-random generator used to seed the grid with random soups. It's a xorshift
+// generator, which is tiny and more than good enough for this. On its own it would
+// always produce the same sequence of soups after a reset, so noisy measurements (like
+// the readings of the light sensor) are mixed into its state as they are taken.
+pub struct Seeder {
+    state: u32,
+}
+
+impl Seeder {
+    pub const fn new() -> Self {
+        Self {
+            state: INITIAL_STATE,
+        }
+    }
+
+    // Returns a generator that produces the sequence of soups of the given seed, without
+    // any noise.
+    pub fn with_seed(seed: u32) -> Self {
+        let mut seeder = Self::new();
+        seeder.add_entropy(seed);
+        seeder
+    }
+
+    // Mix a noisy value into the state of the generator. Only the lowest bits of most
+    // measurements change from one to the next, so the value is folded in and the
+    // generator is stepped to spread them over the whole state.
+    pub fn add_entropy(&mut self, value: u32) {
+        self.state ^= value.rotate_left(self.state % 32);
+        if self.state == 0 {
+            // The xorshift generator gets stuck at 0.
+            self.state = INITIAL_STATE;
+        }
+        self.next_u32();
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    // Returns a random number from 0 to limit - 1.
+    pub fn next_below(&mut self, limit: u32) -> u32 {
+        self.next_u32() % limit
+    }
+
+    // Generate a random column of cells, in which each cell has a chance of density / 5
+    // of being alive.
+    pub fn random_column(&mut self, density: u8) -> [bool; 5] {
+        let mut column = [false; 5];
+        for alive in column.iter_mut() {
+            *alive = self.next_u32() % 5 < density as u32;
+        }
+        column
+    }
+
+    // Generate a random soup, in which each cell has a chance of density % of being
+    // alive.
+    pub fn random_soup_with_density(&mut self, density: u8) -> [[bool; 5]; 5] {
+        let mut matrix = [[false; 5]; 5];
+        for alive in matrix.iter_mut().flatten() {
+            *alive = self.next_u32() % 100 < density as u32;
+        }
+        matrix
+    }
+
+    // Generate a random soup, in which each cell has a 50% chance of being alive.
+    pub fn random_soup(&mut self) -> [[bool; 5]; 5] {
+        let bits = self.next_u32();
+        let mut matrix = [[false; 5]; 5];
+        for (cell_n, alive) in matrix.iter_mut().flatten().enumerate() {
+            *alive = bits & (1 << cell_n) != 0;
+        }
+        matrix
+    }
+}
@@ -0,0 +1,147 @@
+use crate::direct_display::{self, CROSS, SCANS_PER_SECOND};
+use crate::lsm303agr::Lsm303agr;
+use crate::my_board::BoardButton;
+use crate::thermometer::Thermometer;
+use microbit::pac::RNG;
+use rtt_target::rprintln;
+
+// Number of times that each image of the walk through the LEDs is shown (50 ms).
+const WALK_SCANS: u32 = SCANS_PER_SECOND / 20;
+// Number of times that the arrow pointing to a button is shown while waiting for it to
+// be pressed, once per check of the button (5 s in total).
+const BUTTON_SCANS: u32 = 5 * SCANS_PER_SECOND;
+// Number of times that the results are shown, once per check of the buttons (10 s in
+// total), and number of times they are shown before the failed tests blink (250 ms).
+const RESULT_SCANS: u32 = 10 * SCANS_PER_SECOND;
+const BLINK_SCANS: u32 = SCANS_PER_SECOND / 4;
+// Number of values read from the random number generator, and maximum number of CPU
+// cycles to wait for each one (about 1 ms, a value takes a few tens of microseconds).
+const RNG_VALUES: usize = 8;
+const RNG_TIMEOUT_CYCLES: u32 = 64_000;
+// Number of CPU cycles between two polls of the temperature sensor (1 ms, a measurement
+// takes about 50 µs), number of polls before giving up, and range of plausible readings
+// of the sensor inside the chip, in quarters of a degree.
+const TEMP_POLL_CYCLES: u32 = 64_000;
+const TEMP_POLLS: u32 = 10;
+const TEMP_RANGE: core::ops::RangeInclusive<i32> = -40 * 4..=105 * 4;
+
+// Names of the tests whose result can be measured, in the order of the rows of the
+// results on the display.
+const TEST_NAMES: [&str; 5] = ["button a", "button b", "RNG", "TEMP", "I2C sensors"];
+
+const LEFT_ARROW: [[bool; 5]; 5] = [
+    [false, false, true, false, false],
+    [false, true, false, false, false],
+    [true, true, true, true, true],
+    [false, true, false, false, false],
+    [false, false, true, false, false],
+];
+const RIGHT_ARROW: [[bool; 5]; 5] = [
+    [false, false, true, false, false],
+    [false, false, false, true, false],
+    [true, true, true, true, true],
+    [false, false, false, true, false],
+    [false, false, true, false, false],
+];
+
+// Check the hardware of the board, run when the board starts with button a held down,
+// which is useful to find the faulty boards of a classroom set. The display is driven
+// directly, since its driver hasn't been started yet:
+// - Every LED is lit in turn, which has to be checked by looking at them.
+// - An arrow points to button a, and then to button b, until each is pressed.
+// - The random number generator, the temperature sensor and the sensors of the internal
+//   I2C bus (the accelerometer and the magnetometer) are read.
+// The results are printed over RTT and shown on the display, one row per test in the
+// order of TEST_NAMES: the rows of the tests that passed are lit, and the ones that
+// failed blink, with a cross before the results if any failed. The results are shown
+// until button a is pressed and released, and then the board starts as usual. Button b
+// isn't used, since it selects the GPIOTE inputs.
+pub fn run(
+    button_a: &BoardButton,
+    button_b: &BoardButton,
+    rng: &RNG,
+    thermometer: &mut Thermometer,
+    motion_sensor: &mut Lsm303agr,
+) {
+    rprintln!("Self-test");
+    direct_display::take_over();
+
+    for cell in 0..25 {
+        let mut image = [[false; 5]; 5];
+        image[cell / 5][cell % 5] = true;
+        direct_display::show(image, WALK_SCANS);
+    }
+    rprintln!("LEDs: check that every LED lit up");
+
+    let results = [
+        wait_for_press(button_a, LEFT_ARROW),
+        wait_for_press(button_b, RIGHT_ARROW),
+        test_rng(rng),
+        test_temperature(thermometer),
+        motion_sensor.acceleration().is_some() && motion_sensor.magnetic_field().is_some(),
+    ];
+    for (name, passed) in TEST_NAMES.iter().zip(results) {
+        rprintln!("{}: {}", name, if passed { "PASS" } else { "FAIL" });
+    }
+
+    if results.contains(&false) {
+        direct_display::show(CROSS, SCANS_PER_SECOND);
+    }
+    for scan in 0..RESULT_SCANS {
+        if button_a.is_pressed() {
+            break;
+        }
+        let shown = (scan / BLINK_SCANS).is_multiple_of(2);
+        let image = results.map(|passed| [passed || shown; 5]);
+        direct_display::show(image, 1);
+    }
+    while button_a.is_pressed() {}
+}
+
+// Wait until a button is released, in case it's still held down from a previous step,
+// and then until it's pressed, showing an arrow that points to it. Returns false if it's
+// not pressed in time.
+fn wait_for_press(button: &BoardButton, arrow: [[bool; 5]; 5]) -> bool {
+    let mut released = false;
+    for _ in 0..BUTTON_SCANS {
+        let pressed = button.is_pressed();
+        if released && pressed {
+            return true;
+        }
+        released |= !pressed;
+        direct_display::show(arrow, 1);
+    }
+    false
+}
+
+// Read a few values from the random number generator. It passes if they are all
+// generated in time, and they are not all the same.
+fn test_rng(rng: &RNG) -> bool {
+    let mut values = [0; RNG_VALUES];
+    rng.tasks_start.write(|w| unsafe { w.bits(1) });
+    let mut generated = true;
+    for value in values.iter_mut() {
+        let mut cycles = 0;
+        while rng.events_valrdy.read().bits() == 0 && cycles < RNG_TIMEOUT_CYCLES {
+            cortex_m::asm::delay(1000);
+            cycles += 1000;
+        }
+        generated &= rng.events_valrdy.read().bits() != 0;
+        rng.events_valrdy.reset();
+        *value = rng.value.read().value().bits();
+    }
+    rng.tasks_stop.write(|w| unsafe { w.bits(1) });
+    generated && values.iter().any(|&value| value != values[0])
+}
+
+// Measure the temperature. It passes if a measurement is ready in time, and it's within
+// the range of the sensor.
+fn test_temperature(thermometer: &mut Thermometer) -> bool {
+    for _ in 0..TEMP_POLLS {
+        if let Some(reading) = thermometer.poll() {
+            return TEMP_RANGE.contains(&reading);
+        }
+        cortex_m::asm::delay(TEMP_POLL_CYCLES);
+    }
+    false
+}
@@ -0,0 +1,225 @@
+use crate::my_board::UartPins;
+use crate::shell::{Line, Terminal};
+use core::fmt;
+use core::sync::atomic::{compiler_fence, Ordering};
+use heapless::spsc::{Consumer, Producer, Queue};
+use microbit::{
+    hal::uarte::{self, Baudrate, Parity, Uarte},
+    pac::UARTE0,
+};
+
+// Control characters sent by the terminals when the backspace key is pressed.
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+// Size of the queue of bytes received. One of the slots is always empty, so it can hold
+// a couple of lines before the main loop reads them, like a pattern pasted from Golly.
+const RX_QUEUE_SIZE: usize = 128;
+
+// Size of each of the buffers that the UARTE receives the bytes into. A buffer takes
+// about 5.5 ms to fill at 115200 baud.
+const RX_BUFFER_SIZE: usize = 64;
+
+type ByteQueue = Queue<u8, RX_QUEUE_SIZE>;
+type ByteProducer = Producer<'static, u8, RX_QUEUE_SIZE>;
+type ByteConsumer = Consumer<'static, u8, RX_QUEUE_SIZE>;
+
+// Serial interface over the UART of the micro:bit, which the interface chip of the
+// board forwards to the USB port, so any terminal program can talk to it (115200 baud).
+// The bytes are received by the SerialReceiver, in the UARTE0 interrupt, and pushed to
+// a queue. This side empties the queue from the main loop and splits the text into
+// lines, ended by a carriage return or a line feed. It also sends the answers.
+// Terminals don't show what is typed, so the characters of the line are echoed back
+// as they are received, and the backspace key deletes the last one.
+pub struct Serial {
+    uarte: Uarte<UARTE0>,
+    bytes: ByteConsumer,
+    // The line being received:
+    line: Line,
+    // The last byte received. Some terminals end the lines with a carriage return and a
+    // line feed, which is a single line ending:
+    last_byte: u8,
+}
+
+// The receiving side of the serial interface. The UARTE receives the bytes with
+// EasyDMA into two buffers in turns: while the interrupt copies the bytes of a full
+// buffer to the queue, the next bytes are already being received into the other one.
+// The interrupt can then be delayed by the rest of the interrupts for as long as it
+// takes to fill a buffer without losing bytes, but not longer: the bytes that arrive
+// while the interrupts are blocked for longer, e.g., while a page of the flash is
+// erased, can be lost.
+// A buffer that isn't full is only handed over when the reception is stopped, so once
+// no byte has been received for a poll of the inputs, poll stops it, and the interrupt
+// starts it again after the bytes have been copied. Otherwise, a line typed by hand
+// wouldn't be read until the buffer filled up.
+pub struct SerialReceiver {
+    buffers: &'static mut [[u8; RX_BUFFER_SIZE]; 2],
+    // The buffer that the reception in progress writes to, and the buffer that the
+    // next reception will write to:
+    receiving: usize,
+    next: usize,
+    // Flag set when bytes have been received since the last time the reception was
+    // stopped:
+    received: bool,
+    // Flag set while the reception is being stopped:
+    stopping: bool,
+    bytes: ByteProducer,
+}
+
+impl Serial {
+    // Configure the UARTE and start receiving. The receiver must be placed where the
+    // UARTE0 interrupt can reach it before the interrupt is unmasked.
+    pub fn new(uarte0: UARTE0, pins: UartPins) -> (Self, SerialReceiver) {
+        let uarte = Uarte::new(
+            uarte0,
+            uarte::Pins {
+                txd: pins.tx.degrade(),
+                rxd: pins.rx.degrade(),
+                cts: None,
+                rts: None,
+            },
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        // The queue and the buffers used by EasyDMA must live in RAM for the rest of the
+        // program, so they are created with the singleton macro.
+        let queue = cortex_m::singleton!(: ByteQueue = ByteQueue::new()).unwrap();
+        let (producer, consumer) = queue.split();
+        let buffers =
+            cortex_m::singleton!(: [[u8; RX_BUFFER_SIZE]; 2] = [[0; RX_BUFFER_SIZE]; 2]).unwrap();
+        let receiver = SerialReceiver::start(buffers, producer);
+        (
+            Self {
+                uarte,
+                bytes: consumer,
+                line: Line::new(),
+                last_byte: 0,
+            },
+            receiver,
+        )
+    }
+}
+
+impl Serial {
+    // Send binary data, like the frames of the telemetry. It blocks until all the bytes
+    // have been sent.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.uarte.write(bytes).ok();
+    }
+}
+
+impl Terminal for Serial {
+    // Read the bytes received since the last call. Returns a line once it's complete,
+    // without the line ending. The rest of the control characters are ignored.
+    fn read_line(&mut self) -> Option<Line> {
+        while let Some(byte) = self.bytes.dequeue() {
+            let last_byte = core::mem::replace(&mut self.last_byte, byte);
+            match byte {
+                b'\n' if last_byte == b'\r' => {}
+                b'\r' | b'\n' => {
+                    fmt::Write::write_str(self, "\r\n").ok();
+                    return Some(core::mem::take(&mut self.line));
+                }
+                // Move the cursor back, blank the deleted character and move back again.
+                BACKSPACE | DELETE if self.line.pop().is_some() => {
+                    fmt::Write::write_str(self, "\x08 \x08").ok();
+                }
+                // The characters that don't fit in the line are not echoed.
+                b' '..=b'~' if self.line.push(byte as char).is_ok() => {
+                    fmt::Write::write_char(self, byte as char).ok();
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+// Text is sent through the serial interface with the write! and writeln! macros. It
+// blocks until all the text has been sent.
+impl fmt::Write for Serial {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fmt::Write::write_str(&mut self.uarte, s)
+    }
+}
+
+impl SerialReceiver {
+    fn start(buffers: &'static mut [[u8; RX_BUFFER_SIZE]; 2], bytes: ByteProducer) -> Self {
+        let uarte = unsafe { &*UARTE0::ptr() };
+        uarte
+            .rxd
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(buffers[0].as_ptr() as u32) });
+        uarte
+            .rxd
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(RX_BUFFER_SIZE as u16) });
+        // When a buffer is full, the next reception starts right away, without waiting
+        // for the interrupt.
+        uarte.shorts.write(|w| w.endrx_startrx().enabled());
+        uarte
+            .intenset
+            .write(|w| w.rxstarted().set().endrx().set().rxto().set());
+        compiler_fence(Ordering::SeqCst);
+        uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+        Self {
+            buffers,
+            receiving: 0,
+            next: 0,
+            received: false,
+            stopping: false,
+            bytes,
+        }
+    }
+
+    // Handle the events of the UARTE. It must be called from the UARTE0 interrupt.
+    pub fn on_interrupt(&mut self) {
+        let uarte = unsafe { &*UARTE0::ptr() };
+        // A buffer has been filled, or the reception has been stopped: push its bytes to
+        // the queue. If the queue is full, the bytes are lost, which is better than
+        // blocking the interrupt.
+        if uarte.events_endrx.read().bits() != 0 {
+            uarte.events_endrx.reset();
+            compiler_fence(Ordering::SeqCst);
+            let amount = uarte.rxd.amount.read().amount().bits() as usize;
+            for &byte in &self.buffers[self.receiving][..amount.min(RX_BUFFER_SIZE)] {
+                self.bytes.enqueue(byte).ok();
+            }
+            self.receiving = 1 - self.receiving;
+        }
+        // A reception has started, and its buffer has been latched by the UARTE, so the
+        // other buffer can be set up for the next one.
+        if uarte.events_rxstarted.read().bits() != 0 {
+            uarte.events_rxstarted.reset();
+            self.next = 1 - self.next;
+            uarte
+                .rxd
+                .ptr
+                .write(|w| unsafe { w.ptr().bits(self.buffers[self.next].as_ptr() as u32) });
+        }
+        // The reception has stopped, after the bytes of its buffer have been handed over,
+        // so it's started again, into the buffer that was set up for the next one.
+        if uarte.events_rxto.read().bits() != 0 {
+            uarte.events_rxto.reset();
+            self.stopping = false;
+            uarte.shorts.write(|w| w.endrx_startrx().enabled());
+            uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+        }
+    }
+
+    // Stop the reception if bytes have been received since the last time, but not since
+    // the last poll, so the bytes of a buffer that isn't full reach the queue. It must
+    // be called periodically, every poll of the inputs.
+    pub fn poll(&mut self) {
+        let uarte = unsafe { &*UARTE0::ptr() };
+        if uarte.events_rxdrdy.read().bits() != 0 {
+            uarte.events_rxdrdy.reset();
+            self.received = true;
+        } else if self.received && !self.stopping {
+            self.received = false;
+            self.stopping = true;
+            // The next reception is started by the interrupt, once this one has stopped.
+            uarte.shorts.write(|w| w.endrx_startrx().disabled());
+            uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+        }
+    }
+}
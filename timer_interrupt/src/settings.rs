@@ -0,0 +1,153 @@
+use crate::patterns::PATTERNS;
+use crate::rules::RULES;
+use crate::timing::DEFAULT_SPEED;
+
+// The settings that can be changed at runtime, in the order they appear in the settings
+// menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    // What the display shows: the cells or one of the other views.
+    View,
+    // Pattern the game starts from when it's reset.
+    Pattern,
+    // Rule of the game.
+    Rule,
+    // Time between generations.
+    Speed,
+    // What happens at the edges of the grid.
+    Edge,
+    // Rotation of the display.
+    Rotation,
+    // What the speaker plays: nothing, the births and deaths of the cells or the
+    // population.
+    Sound,
+    // Read out the generation number in Morse code. Its value is the output used.
+    Morse,
+    // What the microphone does: reseed the grid with claps or inject cells as loud as
+    // the sound.
+    Microphone,
+    // Whether the temperature nudges the speed of the game.
+    Temperature,
+    // What the board does with the radio: nothing, broadcast its grid, show the grid of
+    // another board or share a world with other boards.
+    Radio,
+    // Pin of the edge connector that changes level with every generation, or none.
+    Sync,
+    // Number of generations between two saves of the state to flash, or never.
+    Autosave,
+    // Time without inputs after which the display is dimmed, or never.
+    Idle,
+    // Whether the game keeps running with new soups while the board is idle.
+    Demo,
+}
+
+pub const SETTINGS: [Setting; 15] = [
+    Setting::View,
+    Setting::Pattern,
+    Setting::Rule,
+    Setting::Speed,
+    Setting::Edge,
+    Setting::Rotation,
+    Setting::Sound,
+    Setting::Morse,
+    Setting::Microphone,
+    Setting::Temperature,
+    Setting::Radio,
+    Setting::Sync,
+    Setting::Autosave,
+    Setting::Idle,
+    Setting::Demo,
+];
+pub const SETTING_NAMES: [&str; 15] = [
+    "VIEW", "PATTERN", "RULE", "SPEED", "EDGE", "ROTATE", "SOUND", "MORSE", "MIC", "TEMP", "RADIO",
+    "SYNC", "SAVE", "IDLE", "DEMO",
+];
+
+// Names of the options of each setting. The value of a setting is the index of the
+// selected option.
+const VIEW_NAMES: [&str; 4] = ["CELLS", "BARS", "HISTORY", "STATS"];
+// The first pattern is the initial state of the game, followed by the library.
+const PATTERN_NAMES: [&str; PATTERNS.len() + 1] = pattern_names();
+const RULE_NAMES: [&str; RULES.len()] = rule_names();
+// Generation periods, matching timing::GENERATION_PERIODS_MS.
+const SPEED_NAMES: [&str; 5] = ["2S", "1S", "0.5S", "0.25S", "0.12S"];
+const EDGE_NAMES: [&str; 2] = ["DEAD", "WRAP"];
+const ROTATION_NAMES: [&str; 4] = ["0", "90", "180", "270"];
+const SOUND_NAMES: [&str; 3] = ["OFF", "BLIPS", "PITCH"];
+const MORSE_NAMES: [&str; 3] = ["LED", "SOUND", "BOTH"];
+const MICROPHONE_NAMES: [&str; 2] = ["CLAP", "LEVELS"];
+const TEMPERATURE_NAMES: [&str; 2] = ["OFF", "SPEED"];
+const RADIO_NAMES: [&str; 6] = ["OFF", "SEND", "VIEW", "PAIR", "TILE", "REMOTE"];
+// Pins of the edge connector, matching sync_pulse::SyncPin::ALL.
+const SYNC_NAMES: [&str; 6] = ["OFF", "0", "1", "2", "12", "14"];
+const AUTOSAVE_NAMES: [&str; 4] = ["OFF", "10", "100", "1000"];
+// Minutes without inputs, matching IDLE_MS in main.
+const IDLE_NAMES: [&str; 4] = ["OFF", "1M", "5M", "15M"];
+const DEMO_NAMES: [&str; 2] = ["OFF", "ON"];
+
+// Values of the settings when the board starts. The rotation can be changed to match
+// the orientation in which the board is mounted. The sound starts muted and the radio
+// off, and so are the sync signal, the autosave, the dimming and the demo.
+const DEFAULTS: [usize; 15] = [0, 0, 0, DEFAULT_SPEED, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+const fn pattern_names() -> [&'static str; PATTERNS.len() + 1] {
+    let mut names = ["START"; PATTERNS.len() + 1];
+    let mut i = 0;
+    while i < PATTERNS.len() {
+        names[i + 1] = PATTERNS[i].name;
+        i += 1;
+    }
+    names
+}
+
+const fn rule_names() -> [&'static str; RULES.len()] {
+    let mut names = [""; RULES.len()];
+    let mut i = 0;
+    while i < RULES.len() {
+        names[i] = RULES[i].name;
+        i += 1;
+    }
+    names
+}
+
+impl Setting {
+    // Names of the options of the setting.
+    pub fn option_names(self) -> &'static [&'static str] {
+        match self {
+            Setting::View => &VIEW_NAMES,
+            Setting::Pattern => &PATTERN_NAMES,
+            Setting::Rule => &RULE_NAMES,
+            Setting::Speed => &SPEED_NAMES,
+            Setting::Edge => &EDGE_NAMES,
+            Setting::Rotation => &ROTATION_NAMES,
+            Setting::Sound => &SOUND_NAMES,
+            Setting::Morse => &MORSE_NAMES,
+            Setting::Microphone => &MICROPHONE_NAMES,
+            Setting::Temperature => &TEMPERATURE_NAMES,
+            Setting::Radio => &RADIO_NAMES,
+            Setting::Sync => &SYNC_NAMES,
+            Setting::Autosave => &AUTOSAVE_NAMES,
+            Setting::Idle => &IDLE_NAMES,
+            Setting::Demo => &DEMO_NAMES,
+        }
+    }
+}
+
+// Current value of every setting.
+pub struct Settings {
+    values: [usize; SETTINGS.len()],
+}
+
+impl Settings {
+    pub const fn new() -> Self {
+        Self { values: DEFAULTS }
+    }
+
+    pub fn get(&self, setting: Setting) -> usize {
+        self.values[setting as usize]
+    }
+
+    pub fn set(&mut self, setting: Setting, value: usize) {
+        self.values[setting as usize] = value;
+    }
+}
@@ -0,0 +1,165 @@
+use crate::command::{self, Command, CommandError, HELP};
+use crate::event_log;
+use crate::rle::{self, RleReader};
+use crate::rules::RULES;
+use core::fmt::{self, Write};
+use heapless::String;
+
+// Maximum length of a line received. The characters beyond it are dropped. Golly
+// splits the patterns it copies in lines of up to 70 characters.
+const LINE_LENGTH: usize = 72;
+// Text shown when the shell is ready for the next command.
+const PROMPT: &str = "life> ";
+
+pub type Line = String<LINE_LENGTH>;
+
+// A text channel to the host that the shell can run on: it receives the text typed as
+// lines and sends the answers through the write! and writeln! macros.
+pub trait Terminal: Write {
+    // Read the text received since the last call. Returns a line once it's complete,
+    // without the line ending.
+    fn read_line(&mut self) -> Option<Line>;
+}
+
+// The state of the game shown by the shell after a command.
+pub struct Snapshot {
+    pub matrix: [[bool; 5]; 5],
+    pub generation: u32,
+    pub paused: bool,
+    // Index of the rule:
+    pub rule: usize,
+}
+
+// Interactive shell on top of a terminal, like the serial interface, to drive the game
+// from a terminal program. It shows a prompt, reads the commands and answers them in plain words,
+// showing the grid as text after the commands that change it. The invalid commands are
+// answered by the shell itself, and the valid ones are run by the caller, which then
+// hands the state of the game to the shell to answer them. A pattern copied from Golly
+// can be pasted as it is: a line with the header of a pattern in RLE starts reading it,
+// and the following lines are read as its cells until it ends.
+pub struct Shell<T: Terminal> {
+    terminal: T,
+    // The pattern being pasted, if any:
+    paste: Option<RleReader>,
+}
+
+impl<T: Terminal> Shell<T> {
+    pub fn new(terminal: T) -> Self {
+        let mut shell = Self {
+            terminal,
+            paste: None,
+        };
+        write!(
+            shell.terminal,
+            "\r\nGame of Life, type help for the list of commands\r\n{}",
+            PROMPT
+        )
+        .ok();
+        shell
+    }
+
+    // Returns the terminal, to send other data through it.
+    pub fn terminal(&mut self) -> &mut T {
+        &mut self.terminal
+    }
+
+    // Read the text received since the last call. Returns a command once a line with a
+    // valid command is complete. Empty lines only show the prompt again.
+    pub fn read_command(&mut self) -> Option<Command> {
+        let line = self.terminal.read_line()?;
+        let line = line.trim();
+        if let Some(reader) = self.paste.as_mut() {
+            return match reader.read(line) {
+                Ok(pattern) => {
+                    if pattern.is_some() {
+                        self.paste = None;
+                    }
+                    pattern.map(Command::Paste)
+                }
+                Err(error) => {
+                    self.paste = None;
+                    self.show_error(error);
+                    None
+                }
+            };
+        }
+        if line.is_empty() {
+            self.terminal.write_str(PROMPT).ok();
+            return None;
+        }
+        // Golly copies the comments of a pattern too, as lines that start with a #.
+        if line.starts_with('#') {
+            return None;
+        }
+        if rle::is_header(line) {
+            match RleReader::new(line) {
+                Ok(reader) => self.paste = Some(reader),
+                Err(error) => self.show_error(error),
+            }
+            return None;
+        }
+        match command::parse(line) {
+            Ok(command) => Some(command),
+            Err(error) => {
+                self.show_error(error);
+                None
+            }
+        }
+    }
+
+    fn show_error(&mut self, error: CommandError) {
+        write!(
+            self.terminal,
+            "Error: {}, type help for the list of commands\r\n{}",
+            error.message(),
+            PROMPT
+        )
+        .ok();
+    }
+
+    // Answer a command that has been run, given the state of the game after it.
+    pub fn answer(&mut self, command: Command, snapshot: &Snapshot) {
+        write!(self.terminal, "{}\r\n", command).ok();
+        if let Command::Help = command {
+            self.terminal.write_str(HELP).ok();
+        }
+        if command.shows_grid() {
+            self.show_grid(snapshot);
+        }
+        if let Command::Rle = command {
+            rle::write_rle(
+                &mut self.terminal,
+                snapshot.matrix,
+                RULES[snapshot.rule].rulestring,
+            )
+            .ok();
+        }
+        if let Command::Log = command {
+            event_log::write_log(&mut self.terminal).ok();
+        }
+        self.terminal.write_str(PROMPT).ok();
+    }
+
+    fn show_grid(&mut self, snapshot: &Snapshot) {
+        let state = if snapshot.paused { "paused" } else { "running" };
+        write!(
+            self.terminal,
+            "Generation {} ({})\r\n",
+            snapshot.generation, state
+        )
+        .ok();
+        write_grid(&mut self.terminal, snapshot.matrix).ok();
+    }
+}
+
+// Write a grid as text, a line per row, with a # for every live cell and a . for every
+// dead one.
+pub fn write_grid(out: &mut impl Write, matrix: [[bool; 5]; 5]) -> fmt::Result {
+    for row in matrix {
+        for alive in row {
+            out.write_char(if alive { '#' } else { '.' })?;
+        }
+        out.write_str("\r\n")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use crate::game_of_life::Changes;
+use crate::timing::ms_to_ticks;
+use microbit::{
+    hal::{
+        gpio::{Output, Pin, PushPull},
+        pwm::{Channel, Pwm},
+        time::Hertz,
+    },
+    pac::PWM2,
+};
+
+// Tone of the blip played when more cells are born than die in a generation.
+const BIRTH_TONE: Hertz = Hertz(1760);
+// Lower tone played when more cells die than are born.
+const DEATH_TONE: Hertz = Hertz(440);
+// Number of display ticks that a blip lasts (about 60 ms).
+const BLIP_TICKS: u32 = ms_to_ticks(60);
+// Minimum number of display ticks between the start of two blips (250 ms). At the
+// fastest speeds the generations are skipped, so the board doesn't buzz non-stop.
+const MIN_BLIP_INTERVAL_TICKS: u32 = ms_to_ticks(250);
+// Pitch of the continuous tone for a population of one cell, and the increase of the
+// pitch for each additional cell. A full grid plays 1180 Hz.
+const BASE_PITCH_HZ: u32 = 220;
+const PITCH_STEP_HZ: u32 = 40;
+// Every display tick, the pitch of the continuous tone moves this fraction of the way
+// to its target (1/4), so it glides from the pitch of a generation to the next.
+const GLIDE_DIVISOR: u32 = 4;
+// Tone of the Morse code.
+const MORSE_TONE: Hertz = Hertz(880);
+
+// What the speaker plays.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    // Nothing.
+    Off,
+    // A blip after every generation, depending on the cells born and dead.
+    Blips,
+    // A continuous tone whose pitch follows the population.
+    Pitch,
+}
+
+// The speaker of the micro:bit v2, driven with a square wave generated by a PWM
+// peripheral. The frequency of the PWM signal is the frequency of the tone.
+pub struct Speaker {
+    pwm: Pwm<PWM2>,
+    // Number of display ticks since the last blip started:
+    ticks_since_blip: u32,
+    // Pitch of the continuous tone and the pitch it's gliding to, in Hz, or None if the
+    // tone isn't playing:
+    tone: Option<(u32, u32)>,
+}
+
+impl Speaker {
+    pub fn new(pwm2: PWM2, pin: Pin<Output<PushPull>>) -> Self {
+        let pwm = Pwm::new(pwm2);
+        pwm.set_output_pin(Channel::C0, pin);
+        Self {
+            pwm,
+            ticks_since_blip: MIN_BLIP_INTERVAL_TICKS,
+            tone: None,
+        }
+    }
+
+    // Play a blip for the changes of a generation: a high one if more cells have been
+    // born than have died, and a low one otherwise. Nothing is played if no cell has
+    // changed or if the last blip started too recently.
+    pub fn play_changes(&mut self, changes: Changes) {
+        if changes.births > 0 && changes.births >= changes.deaths {
+            self.blip(BIRTH_TONE);
+        } else if changes.deaths > 0 {
+            self.blip(DEATH_TONE);
+        }
+    }
+
+    // Make the continuous tone glide to the pitch of a population. It starts the tone
+    // if it wasn't playing, and an empty grid silences it.
+    pub fn play_population(&mut self, population: u8) {
+        if population == 0 {
+            self.stop();
+            return;
+        }
+        let target = BASE_PITCH_HZ + (population as u32 - 1) * PITCH_STEP_HZ;
+        match self.tone.as_mut() {
+            Some((_, tone_target)) => *tone_target = target,
+            None => {
+                self.tone = Some((target, target));
+                self.play(Hertz(target));
+            }
+        }
+    }
+
+    // Play the tone of the Morse code while the key is down. The rest of the sounds
+    // must be stopped first.
+    pub fn set_morse_key(&mut self, down: bool) {
+        if down {
+            self.play(MORSE_TONE);
+        } else {
+            self.pwm.disable();
+        }
+    }
+
+    // Stop the continuous tone and any blip that is playing.
+    pub fn stop(&mut self) {
+        self.tone = None;
+        self.ticks_since_blip = MIN_BLIP_INTERVAL_TICKS;
+        self.pwm.disable();
+    }
+
+    // Advance the time of the current blip and stop it once it's over, and move the
+    // pitch of the continuous tone towards its target. It must be called every display
+    // tick.
+    pub fn tick(&mut self) {
+        if self.ticks_since_blip < MIN_BLIP_INTERVAL_TICKS {
+            self.ticks_since_blip += 1;
+            if self.ticks_since_blip == BLIP_TICKS {
+                self.pwm.disable();
+            }
+        }
+        if let Some((pitch, target)) = self.tone {
+            if pitch != target {
+                // Move at least 1 Hz, so that the pitch always reaches the target.
+                let step = (pitch.abs_diff(target) / GLIDE_DIVISOR).max(1);
+                let pitch = if pitch < target {
+                    pitch + step
+                } else {
+                    pitch - step
+                };
+                self.tone = Some((pitch, target));
+                self.play(Hertz(pitch));
+            }
+        }
+    }
+
+    fn blip(&mut self, tone: Hertz) {
+        if self.ticks_since_blip < MIN_BLIP_INTERVAL_TICKS {
+            return;
+        }
+        self.play(tone);
+        self.ticks_since_blip = 0;
+    }
+
+    fn play(&mut self, tone: Hertz) {
+        self.pwm.set_period(tone);
+        // A duty cycle of 50% gives the loudest square wave. The maximum duty depends
+        // on the period, so it must be set again after every change.
+        self.pwm.set_duty_on_common(self.pwm.max_duty() / 2);
+        self.pwm.enable();
+    }
+}
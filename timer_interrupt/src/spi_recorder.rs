@@ -0,0 +1,55 @@
+use crate::my_board::SpiPins;
+use embedded_hal::digital::v2::OutputPin;
+use microbit::{
+    hal::{
+        gpio::{Output, Pin, PushPull},
+        spim::{self, Frequency, Spim},
+    },
+    pac::SPIM3,
+};
+
+// Streams the grid of every generation over the SPI bus of the edge connector (SCK on
+// pin 13 and MOSI on pin 15), so that a logic analyzer or an FPGA can record the whole
+// evolution of the game. Each frame is the number of the generation (4 bytes, little
+// endian) followed by a byte per row, from top to bottom, in which the bit n is the cell
+// of the column n. Pin 16 goes high at the start of every frame and low at its end, so
+// that the frames can be told apart.
+pub struct SpiRecorder {
+    spim: Spim<SPIM3>,
+    sync: Pin<Output<PushPull>>,
+}
+
+impl SpiRecorder {
+    pub fn new(spim3: SPIM3, pins: SpiPins) -> Self {
+        let spim = Spim::new(
+            spim3,
+            spim::Pins {
+                sck: pins.sck.degrade(),
+                mosi: Some(pins.mosi.degrade()),
+                miso: None,
+            },
+            Frequency::M8,
+            spim::MODE_0,
+            0,
+        );
+        let mut sync = pins.cs.degrade();
+        sync.set_low().ok();
+        Self { spim, sync }
+    }
+
+    pub fn record(&mut self, generation: u32, matrix: [[bool; 5]; 5]) {
+        let mut frame = [0; 9];
+        frame[..4].copy_from_slice(&generation.to_le_bytes());
+        for (byte, row) in frame[4..].iter_mut().zip(matrix) {
+            *byte = row
+                .into_iter()
+                .enumerate()
+                .fold(0, |bits, (col_n, alive)| bits | (alive as u8) << col_n);
+        }
+        // If the transfer fails the frame is lost, and the recorder sees a gap in the
+        // numbers of the generations.
+        self.sync.set_high().ok();
+        embedded_hal::blocking::spi::Write::write(&mut self.spim, &frame).ok();
+        self.sync.set_low().ok();
+    }
+}
@@ -0,0 +1,125 @@
+use crate::framebuffer::LedMatrix;
+use crate::my_board::I2cPins;
+use microbit::{
+    hal::twim::{self, Frequency, Twim},
+    pac::TWIM1,
+};
+
+// I2C address of the display.
+const ADDRESS: u8 = 0x3c;
+// First byte of every transfer, which tells the display if the rest of bytes are
+// commands or pixel data.
+const COMMANDS: u8 = 0x00;
+const DATA: u8 = 0x40;
+
+// Size of the display, in pixels. The pixels are grouped in 8 pages of 8 rows each, and
+// every byte of pixel data is a column of 8 pixels of a page.
+const WIDTH: usize = 128;
+const PAGES: usize = 8;
+// Size of each cell of the game, in pixels. The last row and column of each cell are
+// left dark, so that neighbouring live cells can be told apart.
+const CELL_SIZE: usize = 12;
+// Position of the top left corner of the grid, so that it's centered on the display.
+const GRID_X: usize = (WIDTH - 5 * CELL_SIZE) / 2;
+const GRID_Y: usize = (PAGES * 8 - 5 * CELL_SIZE) / 2;
+// The pixels of the display can only be on or off, so the brightness of the cells is
+// shown by turning on a fraction of their pixels. Each pixel is on if the brightness of
+// its cell is greater than the value of this 2x2 matrix at its position, e.g., a
+// brightness of 9 turns all the pixels of the cell on and a brightness of 3 half of
+// them.
+const DITHER: [[u8; 2]; 2] = [[0, 4], [6, 2]];
+
+// 128x64 SSD1306 OLED display connected to the I2C pins of the edge connector (SCL on
+// pin 19 and SDA on pin 20). It mirrors the images shown on the on-board display, with
+// each LED drawn as a block of pixels.
+pub struct Ssd1306 {
+    twim: Twim<TWIM1>,
+}
+
+impl Ssd1306 {
+    pub fn new(twim1: TWIM1, pins: I2cPins) -> Self {
+        let twim = Twim::new(
+            twim1,
+            twim::Pins {
+                scl: pins.scl.degrade(),
+                sda: pins.sda.degrade(),
+            },
+            Frequency::K400,
+        );
+        let mut display = Self { twim };
+        // The configuration of a 128x64 module with the internal charge pump, written
+        // in horizontal addressing mode so that the whole display can be written in
+        // one go. The buffer is a local variable because the TWIM can only send data
+        // from RAM.
+        let init: [u8; 26] = [
+            COMMANDS, 0xae, // Display off
+            0xd5, 0x80, // Clock divider
+            0xa8, 0x3f, // Multiplex ratio: 64 rows
+            0xd3, 0x00, // No display offset
+            0x40, // Start line 0
+            0x8d, 0x14, // Enable the charge pump
+            0x20, 0x00, // Horizontal addressing mode
+            0xa1, // Column 127 mapped to segment 0
+            0xc8, // Scan the rows from the bottom up
+            0xda, 0x12, // Alternative row pin configuration
+            0x81, 0xcf, // Contrast
+            0xd9, 0xf1, // Pre-charge period
+            0xdb, 0x40, // Vcomh deselect level
+            0xa4, // Show the contents of the RAM
+            0xa6, // Not inverted
+            0xaf, // Display on
+        ];
+        // If the display is not connected the writes fail, and the board keeps working
+        // without it.
+        display.twim.write(ADDRESS, &init).ok();
+        display
+    }
+}
+
+impl LedMatrix for Ssd1306 {
+    fn show_brightness(&mut self, brightness: &[[u8; 5]; 5]) {
+        // Write from the first column of the first page to the last column of the last
+        // page.
+        let window = [
+            COMMANDS,
+            0x21,
+            0,
+            (WIDTH - 1) as u8,
+            0x22,
+            0,
+            (PAGES - 1) as u8,
+        ];
+        if self.twim.write(ADDRESS, &window).is_err() {
+            return;
+        }
+
+        let mut page_data = [0; WIDTH + 1];
+        page_data[0] = DATA;
+        for page in 0..PAGES {
+            for (x, column) in page_data[1..].iter_mut().enumerate() {
+                *column = (0..8).fold(0, |bits, bit| {
+                    if pixel_on(brightness, x, 8 * page + bit) {
+                        bits | (1 << bit)
+                    } else {
+                        bits
+                    }
+                });
+            }
+            self.twim.write(ADDRESS, &page_data).ok();
+        }
+    }
+}
+
+// Returns whether the pixel at the given position must be on to show the image.
+fn pixel_on(brightness: &[[u8; 5]; 5], x: usize, y: usize) -> bool {
+    if !(GRID_X..GRID_X + 5 * CELL_SIZE).contains(&x)
+        || !(GRID_Y..GRID_Y + 5 * CELL_SIZE).contains(&y)
+    {
+        return false;
+    }
+    let (grid_x, grid_y) = (x - GRID_X, y - GRID_Y);
+    if grid_x % CELL_SIZE == CELL_SIZE - 1 || grid_y % CELL_SIZE == CELL_SIZE - 1 {
+        return false;
+    }
+    brightness[grid_y / CELL_SIZE][grid_x / CELL_SIZE] > DITHER[y % 2][x % 2]
+}
@@ -0,0 +1,49 @@
+use core::ptr::addr_of;
+
+// Value written to the free part of the stack at boot ("STAK" in ASCII). The words that
+// still hold it have never been used.
+const PAINT: u32 = 0x4b41_5453;
+// Number of bytes below the stack pointer that are not painted, for the frame of the
+// function that paints.
+const PAINT_MARGIN: usize = 64;
+
+// Symbols of the linker script of cortex-m-rt: the start of the heap, which is right
+// after the static variables and is not used, so the stack can grow down to it, and
+// the top of the stack, at the end of the RAM.
+extern "C" {
+    static __sheap: u32;
+    static _stack_start: u32;
+}
+
+fn bottom() -> usize {
+    addr_of!(__sheap) as usize
+}
+
+fn top() -> usize {
+    addr_of!(_stack_start) as usize
+}
+
+// Size of the stack, in bytes.
+pub fn size() -> usize {
+    top() - bottom()
+}
+
+// Fill the part of the stack that hasn't been used yet with PAINT, so the high water
+// mark can be measured later. It must be called at the start of main, when the stack
+// is almost empty.
+pub fn paint() {
+    let stack_pointer = cortex_m::register::msp::read() as usize;
+    for address in (bottom()..stack_pointer - PAINT_MARGIN).step_by(4) {
+        unsafe { core::ptr::write_volatile(address as *mut u32, PAINT) };
+    }
+}
+
+// Returns the maximum number of bytes of the stack that have been used since it was
+// painted: the distance from the top to the lowest word that doesn't hold PAINT.
+pub fn high_water_mark() -> usize {
+    let lowest_used = (bottom()..top())
+        .step_by(4)
+        .find(|&address| unsafe { core::ptr::read_volatile(address as *const u32) } != PAINT)
+        .unwrap_or(top());
+    top() - lowest_used
+}
@@ -0,0 +1,218 @@
+use crate::game_of_life::Border;
+
+// Offsets of the positions of the tiles around a tile, in columns and rows: top left,
+// top, top right, left, right, bottom left, bottom and bottom right.
+const DIRECTIONS: [(i8, i8); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// The rows and columns at the edges of a grid, the cells that the boards of the tiles
+// around it need to compute their next generation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Edges {
+    pub top: [bool; 5],
+    pub bottom: [bool; 5],
+    pub left: [bool; 5],
+    pub right: [bool; 5],
+}
+
+impl Edges {
+    pub const EMPTY: Self = Self {
+        top: [false; 5],
+        bottom: [false; 5],
+        left: [false; 5],
+        right: [false; 5],
+    };
+
+    pub fn of(matrix: [[bool; 5]; 5]) -> Self {
+        Self {
+            top: matrix[0],
+            bottom: matrix[4],
+            left: matrix.map(|row| row[0]),
+            right: matrix.map(|row| row[4]),
+        }
+    }
+}
+
+// The position of a board in a world made of the grids of several boards, laid out in
+// columns and rows of tiles. The world is a torus, so the tiles of the last column are
+// next to the ones of the first column, and the same goes for the rows.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u8,
+    pub y: u8,
+    pub columns: u8,
+    pub rows: u8,
+}
+
+impl Tile {
+    // The tile of a board paired with another one, in a world two tiles wide. Both boards
+    // use it, since the other board is always at the other position.
+    pub const PAIR: Self = Self {
+        x: 0,
+        y: 0,
+        columns: 2,
+        rows: 1,
+    };
+
+    // Returns the position of the tile at an offset from this one.
+    fn neighbor(&self, (dx, dy): (i8, i8)) -> (u8, u8) {
+        (
+            (self.x as i8 + dx).rem_euclid(self.columns as i8) as u8,
+            (self.y as i8 + dy).rem_euclid(self.rows as i8) as u8,
+        )
+    }
+}
+
+// State of a world stitched from the grids of several boards, each of which simulates
+// one tile of it. The boards advance in lockstep: after every generation, a board
+// broadcasts its edges, and it can't compute the next generation until it has received
+// the edges of all the tiles around it for the same generation. Every packet also
+// carries the edges of the previous generation, so if a packet is lost, a board that is
+// ahead can still provide the edges that another one is waiting for, and a board that
+// is waiting sends its edges again every time it tries to advance. If a board gets more
+// than a generation behind (e.g., its grid has been replaced, which resets its
+// generation count), it jumps to the generation of the others. In small worlds a tile
+// can be next to itself or have the same tile on several sides.
+//
+// The boards also work as a barrier: a board that tried to advance and had to wait is
+// released as soon as the last of the edges it was missing arrives, without waiting for
+// its next period, so the boards advance at the same time instead of drifting apart.
+pub struct Stitch {
+    tile: Tile,
+    // The generation of this board and its edges, and the edges of the previous one:
+    generation: u32,
+    edges: Edges,
+    previous_edges: Edges,
+    // The last generation received from the tile in each direction, with its edges and
+    // the edges of the previous one:
+    neighbors: [Option<(u32, Edges, Edges)>; DIRECTIONS.len()],
+    // Whether the board is waiting for the edges of the tiles around it:
+    waiting: bool,
+}
+
+impl Stitch {
+    pub const fn new() -> Self {
+        Self {
+            tile: Tile::PAIR,
+            generation: 0,
+            edges: Edges::EMPTY,
+            previous_edges: Edges::EMPTY,
+            neighbors: [None; DIRECTIONS.len()],
+            waiting: false,
+        }
+    }
+
+    pub fn tile(&self) -> Tile {
+        self.tile
+    }
+
+    // Move the board to another tile, forgetting the edges received so far.
+    pub fn set_tile(&mut self, tile: Tile) {
+        self.tile = tile;
+        self.neighbors = [None; DIRECTIONS.len()];
+        self.waiting = false;
+    }
+
+    // Record the grid of a new generation of this board. A grid that has been replaced
+    // starts from generation 0, and its edges are also used as the previous ones.
+    pub fn record(&mut self, generation: u32, matrix: [[bool; 5]; 5]) {
+        let edges = Edges::of(matrix);
+        self.previous_edges = if generation == self.generation.wrapping_add(1) {
+            self.edges
+        } else {
+            edges
+        };
+        self.generation = generation;
+        self.edges = edges;
+        self.waiting = false;
+    }
+
+    // Record that the board couldn't advance because some edges are missing.
+    pub fn wait(&mut self) {
+        self.waiting = true;
+    }
+
+    // Returns true if the board is waiting and all the edges it needs have arrived.
+    pub fn is_released(&self) -> bool {
+        self.waiting && self.border().is_some()
+    }
+
+    // Returns the generation of this board, with its edges and the ones of the previous
+    // generation, to be sent to the boards around it.
+    pub fn own_edges(&self) -> (u32, Edges, Edges) {
+        (self.generation, self.edges, self.previous_edges)
+    }
+
+    // Record the edges received from the board at the given tile. Returns the generation
+    // that this board must jump to, if it's more than a generation behind.
+    pub fn receive(
+        &mut self,
+        position: (u8, u8),
+        generation: u32,
+        edges: Edges,
+        previous_edges: Edges,
+    ) -> Option<u32> {
+        if position == (self.tile.x, self.tile.y) {
+            return None;
+        }
+        let tile = self.tile;
+        for (direction, neighbor) in DIRECTIONS.into_iter().zip(self.neighbors.iter_mut()) {
+            if tile.neighbor(direction) == position {
+                *neighbor = Some((generation, edges, previous_edges));
+            }
+        }
+        if generation > self.generation.wrapping_add(1) {
+            self.generation = generation;
+            self.previous_edges = self.edges;
+            Some(generation)
+        } else {
+            None
+        }
+    }
+
+    // Returns the cells around the grid of this board for its current generation, if the
+    // edges of all the tiles around it have been received.
+    pub fn border(&self) -> Option<Border> {
+        let mut edges = [Edges::EMPTY; DIRECTIONS.len()];
+        for ((direction, neighbor), edges) in DIRECTIONS
+            .into_iter()
+            .zip(self.neighbors)
+            .zip(edges.iter_mut())
+        {
+            *edges = if self.tile.neighbor(direction) == (self.tile.x, self.tile.y) {
+                self.edges
+            } else {
+                match neighbor {
+                    Some((generation, current, _)) if generation == self.generation => current,
+                    Some((generation, _, previous))
+                        if generation == self.generation.wrapping_add(1) =>
+                    {
+                        previous
+                    }
+                    _ => return None,
+                }
+            };
+        }
+        let [top_left, top, top_right, left, right, bottom_left, bottom, bottom_right] = edges;
+        Some(Border {
+            north: top.bottom,
+            south: bottom.top,
+            west: left.right,
+            east: right.left,
+            corners: [
+                top_left.bottom[4],
+                top_right.bottom[0],
+                bottom_left.top[4],
+                bottom_right.top[0],
+            ],
+        })
+    }
+}
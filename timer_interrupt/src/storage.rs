@@ -0,0 +1,247 @@
+use crate::crc::crc16;
+use microbit::pac::NVMC;
+
+// Addresses of the two pages of flash reserved for the journal of saved states: the
+// last two pages of the 512 KiB of the nRF52833. The linker script of the project
+// (memory.x) leaves them out of the flash given to the program, so a program that grew
+// into them would fail to link.
+const PAGE_ADDRESSES: [usize; 2] = [0x7_e000, 0x7_f000];
+const PAGE_SIZE: usize = 4096;
+// Value of the first word of a page of the journal ("JRNL" in ASCII).
+const PAGE_MAGIC: u32 = 0x4c4e_524a;
+// Value of the first word of a record ("LIFE" in ASCII). An erased page reads as all
+// ones.
+const MAGIC: u32 = 0x4546_494c;
+// Version of the layout of the records, kept in the record next to its CRC. This is the
+// first layout. When it changes, the version must be increased and the records of the
+// previous versions converted in SavedState::from_record(), so the boards keep their
+// saved state when the firmware is updated.
+const VERSION: u16 = 1;
+// Number of words of a record: the magic word, the version and the CRC, and the state.
+const RECORD_WORDS: usize = 4;
+
+// The state of the game that is kept in flash, so the board resumes where it left off
+// after it's switched off: the grid and the settings that change how it evolves.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SavedState {
+    pub matrix: [[bool; 5]; 5],
+    // The values of the rule, speed and edge settings:
+    pub rule: usize,
+    pub speed: usize,
+    pub edge: usize,
+}
+
+impl SavedState {
+    // Encode the state as a record: the magic word, a word with the version (low half)
+    // and the CRC-16 of the version and the state (high half), a word with the cells
+    // (bit n is the cell n, counting by rows) and a word with the settings (a byte
+    // each).
+    fn to_record(self) -> [u32; RECORD_WORDS] {
+        let mut cells = 0;
+        for (i, &alive) in self.matrix.iter().flatten().enumerate() {
+            cells |= (alive as u32) << i;
+        }
+        let settings = self.rule as u32 | (self.speed as u32) << 8 | (self.edge as u32) << 16;
+        let crc = record_crc(VERSION, &[cells, settings]);
+        [MAGIC, VERSION as u32 | (crc as u32) << 16, cells, settings]
+    }
+
+    // Decode a record. Returns None if there is no record, if it's corrupted or if its
+    // version is unknown.
+    fn from_record(words: [u32; RECORD_WORDS]) -> Option<Self> {
+        if words[0] != MAGIC {
+            return None;
+        }
+        let version = words[1] as u16;
+        let crc = (words[1] >> 16) as u16;
+        if crc != record_crc(version, &words[2..]) {
+            return None;
+        }
+        match version {
+            VERSION => Some(Self::decode(words[2], words[3])),
+            _ => None,
+        }
+    }
+
+    // Decode the words with the cells and the settings.
+    fn decode(cells: u32, settings: u32) -> Self {
+        let mut matrix = [[false; 5]; 5];
+        for (i, cell) in matrix.iter_mut().flatten().enumerate() {
+            *cell = cells & (1 << i) != 0;
+        }
+        Self {
+            matrix,
+            rule: (settings & 0xff) as usize,
+            speed: (settings >> 8 & 0xff) as usize,
+            edge: (settings >> 16 & 0xff) as usize,
+        }
+    }
+}
+
+// CRC-16 of the version and the words of the state of a record, little endian.
+fn record_crc(version: u16, words: &[u32]) -> u16 {
+    let mut bytes = [0; 2 + 4 * (RECORD_WORDS - 2)];
+    bytes[..2].copy_from_slice(&version.to_le_bytes());
+    for (chunk, word) in bytes[2..].chunks_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    crc16(&bytes[..2 + 4 * words.len()])
+}
+
+// Size of a record in bytes. The pages are divided in slots of this size, the first one
+// holding the header of the page: the magic word and its sequence number.
+const SLOT_SIZE: usize = RECORD_WORDS * 4;
+const SLOTS_PER_PAGE: usize = PAGE_SIZE / SLOT_SIZE;
+
+// Storage of the state of the game in flash, written through the non-volatile memory
+// controller (NVMC). Flash can only be written from ones to zeros, and it must be erased
+// a whole page at a time, which takes up to 85 ms during which the CPU is halted and
+// wears the page out after about 10000 erases. So the states are appended to a journal
+// instead: every save writes a record to the next free slot of a page, and the newest
+// record is the one loaded. When the page is full, the other page is erased and the
+// journal continues there, so the pages are erased in turns, once every 255 saves. Each
+// page has a sequence number that grows with every erase, to tell which one is newer.
+pub struct Storage {
+    nvmc: NVMC,
+    // The page that the records are appended to, if any, its sequence number and the
+    // next free slot:
+    page: Option<usize>,
+    sequence: u32,
+    slot: usize,
+    // The newest saved state, read once when the journal is opened and kept up to date
+    // by every save, so saving doesn't need to read the journal:
+    newest: Option<SavedState>,
+}
+
+impl Storage {
+    // Find the newest page of the journal and its first free slot.
+    pub fn new(nvmc: NVMC) -> Self {
+        let mut storage = Self {
+            nvmc,
+            page: None,
+            sequence: 0,
+            slot: SLOTS_PER_PAGE,
+            newest: None,
+        };
+        for (page, &address) in PAGE_ADDRESSES.iter().enumerate() {
+            if read_word(address) != PAGE_MAGIC {
+                continue;
+            }
+            let sequence = read_word(address + 4);
+            // The sequence numbers are compared in a way that works when they wrap.
+            if storage.page.is_none() || sequence.wrapping_sub(storage.sequence) as i32 > 0 {
+                storage.page = Some(page);
+                storage.sequence = sequence;
+            }
+        }
+        if let Some(page) = storage.page {
+            // A slot that was being written when the board was switched off is not
+            // erased, so it's skipped.
+            storage.slot = (1..SLOTS_PER_PAGE)
+                .find(|&slot| read_word(slot_address(page, slot)) == u32::MAX)
+                .unwrap_or(SLOTS_PER_PAGE);
+        }
+        storage.newest = storage.load_older(0);
+        storage
+    }
+
+    // Returns the newest saved state, or None if nothing has been saved yet.
+    pub fn load(&self) -> Option<SavedState> {
+        self.newest
+    }
+
+    // Returns the state saved n saves before the newest one, which is 0. The records that
+    // are corrupted are skipped, so they don't count. The journal keeps the states saved
+    // since the newest page was started and the ones of the page before it, until it's
+    // erased, so at least the last 256 saves can be loaded.
+    pub fn load_older(&self, n: usize) -> Option<SavedState> {
+        let page = self.page?;
+        let other = 1 - page;
+        let other_address = PAGE_ADDRESSES[other];
+        let other_slots = if read_word(other_address) == PAGE_MAGIC
+            && read_word(other_address + 4) == self.sequence.wrapping_sub(1)
+        {
+            1..SLOTS_PER_PAGE
+        } else {
+            1..1
+        };
+        (1..self.slot)
+            .rev()
+            .map(|slot| slot_address(page, slot))
+            .chain(other_slots.rev().map(|slot| slot_address(other, slot)))
+            .filter_map(|address| SavedState::from_record(read_record(address)))
+            .nth(n)
+    }
+
+    // Append a record with the state to the journal, unless it's the same as the newest
+    // one.
+    pub fn save(&mut self, state: &SavedState) {
+        if self.newest == Some(*state) {
+            return;
+        }
+        if self.slot == SLOTS_PER_PAGE {
+            self.start_page();
+        }
+        let Some(page) = self.page else {
+            return;
+        };
+        self.write(slot_address(page, self.slot), &state.to_record());
+        self.slot += 1;
+        self.newest = Some(*state);
+    }
+
+    // Append a record with the state only if there is a free slot in the page, since
+    // starting a new page takes too long. Used when the supply is failing, so it only
+    // compares the state with the one in memory and writes the record, without reading
+    // the journal.
+    pub fn save_without_erase(&mut self, state: &SavedState) {
+        if self.slot < SLOTS_PER_PAGE {
+            self.save(state);
+        }
+    }
+
+    // Erase the page that isn't in use and continue the journal there.
+    fn start_page(&mut self) {
+        let page = self.page.map_or(0, |page| 1 - page);
+        let address = PAGE_ADDRESSES[page];
+        self.nvmc.config.write(|w| w.wen().een());
+        self.nvmc
+            .erasepage()
+            .write(|w| unsafe { w.bits(address as u32) });
+        self.wait_ready();
+        self.sequence = self.sequence.wrapping_add(1);
+        self.write(address, &[PAGE_MAGIC, self.sequence]);
+        self.page = Some(page);
+        self.slot = 1;
+    }
+
+    fn write(&mut self, address: usize, words: &[u32]) {
+        self.nvmc.config.write(|w| w.wen().wen());
+        for (i, &word) in words.iter().enumerate() {
+            unsafe { core::ptr::write_volatile((address + i * 4) as *mut u32, word) };
+            self.wait_ready();
+        }
+        self.nvmc.config.write(|w| w.wen().ren());
+    }
+
+    fn wait_ready(&self) {
+        while self.nvmc.ready.read().ready().is_busy() {}
+    }
+}
+
+fn slot_address(page: usize, slot: usize) -> usize {
+    PAGE_ADDRESSES[page] + slot * SLOT_SIZE
+}
+
+// The flash is mapped in memory, so it's read like the RAM.
+fn read_word(address: usize) -> u32 {
+    unsafe { core::ptr::read_volatile(address as *const u32) }
+}
+
+fn read_record(address: usize) -> [u32; RECORD_WORDS] {
+    let mut words = [0; RECORD_WORDS];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = read_word(address + i * 4);
+    }
+    words
+}
@@ -0,0 +1,99 @@
+use microbit::pac::P0;
+
+// Pins of the edge connector that can output the sync signal, named after their number
+// on the edge connector.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SyncPin {
+    Pin0,
+    Pin1,
+    Pin2,
+    Pin12,
+    Pin14,
+}
+
+impl SyncPin {
+    pub const ALL: [SyncPin; 5] = [
+        SyncPin::Pin0,
+        SyncPin::Pin1,
+        SyncPin::Pin2,
+        SyncPin::Pin12,
+        SyncPin::Pin14,
+    ];
+
+    // Number of the pin on the edge connector.
+    pub fn number(self) -> u8 {
+        match self {
+            SyncPin::Pin0 => 0,
+            SyncPin::Pin1 => 1,
+            SyncPin::Pin2 => 2,
+            SyncPin::Pin12 => 12,
+            SyncPin::Pin14 => 14,
+        }
+    }
+
+    // Returns true if the pin is used by one of the enabled features, so it can't output
+    // the sync signal.
+    pub fn is_claimed(self) -> bool {
+        match self {
+            SyncPin::Pin0 => cfg!(feature = "ring-buttons"),
+            SyncPin::Pin1 | SyncPin::Pin2 => {
+                cfg!(feature = "ring-buttons") || cfg!(feature = "ws2812")
+            }
+            SyncPin::Pin12 => cfg!(feature = "trigger"),
+            SyncPin::Pin14 => false,
+        }
+    }
+
+    // Pin of port 0 that the pin of the edge connector is connected to.
+    fn port_pin(self) -> usize {
+        match self {
+            SyncPin::Pin0 => 2,
+            SyncPin::Pin1 => 3,
+            SyncPin::Pin2 => 4,
+            SyncPin::Pin12 => 12,
+            SyncPin::Pin14 => 1,
+        }
+    }
+}
+
+// Signal that changes level every time a generation is committed, so that external
+// instruments (a scope, the trigger of a camera or another board) can follow the game.
+// The pin can be changed at runtime, so instead of owning it, it's configured directly
+// through the registers of the port, like the touch logo.
+pub struct SyncPulse {
+    pin: Option<SyncPin>,
+}
+
+impl SyncPulse {
+    pub const fn new() -> Self {
+        Self { pin: None }
+    }
+
+    // Output the signal on a different pin, or on none. The previous pin goes back to
+    // being a disconnected input, like it is when the board starts, and the new one
+    // starts low.
+    pub fn select(&mut self, pin: Option<SyncPin>) {
+        let p0 = unsafe { &*P0::ptr() };
+        if let Some(old) = self.pin {
+            p0.pin_cnf[old.port_pin()].reset();
+        }
+        if let Some(new) = pin {
+            p0.outclr.write(|w| unsafe { w.bits(1 << new.port_pin()) });
+            p0.pin_cnf[new.port_pin()].write(|w| w.dir().output());
+        }
+        self.pin = pin;
+    }
+
+    // Change the level of the pin. Both edges mark a new generation.
+    pub fn toggle(&self) {
+        if let Some(pin) = self.pin {
+            let p0 = unsafe { &*P0::ptr() };
+            let mask = 1 << pin.port_pin();
+            if p0.out.read().bits() & mask == 0 {
+                p0.outset.write(|w| unsafe { w.bits(mask) });
+            } else {
+                p0.outclr.write(|w| unsafe { w.bits(mask) });
+            }
+        }
+    }
+}
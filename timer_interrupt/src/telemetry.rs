@@ -0,0 +1,174 @@
+use crate::crc::crc16;
+use crate::game_of_life::Changes;
+use heapless::{
+    spsc::{Producer, Queue},
+    Vec,
+};
+
+// Size of the queue of messages generated by the interrupts. A generation generates
+// two, so it can hold a few generations until the main loop sends them.
+const QUEUE_SIZE: usize = 8;
+// Maximum size of a message before it's encoded: the type, the longest payload (the
+// statistics) and the CRC.
+const MAX_MESSAGE_SIZE: usize = 1 + 11 + 2;
+// Maximum size of an encoded frame. COBS adds a byte at the start of the message, and
+// the frame ends with the delimiter.
+pub const MAX_FRAME_SIZE: usize = MAX_MESSAGE_SIZE + 2;
+
+// Types of the messages, the first byte of every message.
+const GRID_TYPE: u8 = 0x01;
+const STATS_TYPE: u8 = 0x02;
+const EVENT_TYPE: u8 = 0x03;
+
+// Where the telemetry frames are sent. It's selected with a command of the shell.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryOutput {
+    Off,
+    // A channel of RTT of its own, Telemetry.
+    Rtt,
+    // The serial interface. The answers of the shell are mixed with the frames, but the
+    // host can tell them apart, since they don't pass the CRC check.
+    Serial,
+}
+
+// Changes of the state of the game reported as events.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Paused,
+    Resumed,
+    // It carries the index of the new rule.
+    Rule(u8),
+    // It carries the new speed.
+    Speed(u8),
+}
+
+// The messages of the telemetry protocol. They are encoded in frames with the type of
+// the message, its payload and a CRC-16 (CCITT-FALSE, little endian) of both, and the
+// frame is then encoded with COBS (consistent overhead byte stuffing), which removes all
+// the zeros from it, so a zero marks the end of every frame. This way the host can find
+// the start of the next frame after it loses some bytes, and the CRC lets it discard
+// the corrupted ones. All the numbers are little endian.
+#[derive(Clone, Copy)]
+pub enum Message {
+    // The grid of a generation: the number of the generation (4 bytes) and a byte per
+    // row, from top to bottom, in which the bit n is the cell of the column n.
+    Grid {
+        generation: u32,
+        matrix: [[bool; 5]; 5],
+    },
+    // The statistics of a generation: the number of the generation (4 bytes), the number
+    // of live cells, cells born and cells dead (a byte each), and the uptime at which the
+    // generation was computed, in milliseconds (4 bytes, it wraps after 49 days).
+    Stats {
+        generation: u32,
+        population: u8,
+        changes: Changes,
+        uptime_ms: u32,
+    },
+    // An event: its code (1 for paused, 2 for resumed, 3 for a rule change and 4 for a
+    // speed change) and a byte with its value (the rule or the speed), or 0.
+    Event(Event),
+}
+
+// Single producer single consumer queue of messages. The interrupts push the messages
+// with the producer and the main loop pops them with the consumer and sends them.
+pub type MessageQueue = Queue<Message, QUEUE_SIZE>;
+pub type MessageProducer = Producer<'static, Message, QUEUE_SIZE>;
+
+pub type Frame = Vec<u8, MAX_FRAME_SIZE>;
+
+impl Message {
+    // Encode the message into a frame, ready to be sent.
+    pub fn encode(&self) -> Frame {
+        let mut message: Vec<u8, MAX_MESSAGE_SIZE> = Vec::new();
+        // None of the messages is longer than MAX_MESSAGE_SIZE, so the pushes can't fail.
+        match *self {
+            Message::Grid { generation, matrix } => {
+                message.push(GRID_TYPE).ok();
+                message.extend_from_slice(&generation.to_le_bytes()).ok();
+                for row in matrix {
+                    let bits = row
+                        .into_iter()
+                        .enumerate()
+                        .fold(0, |bits, (col_n, alive)| bits | (alive as u8) << col_n);
+                    message.push(bits).ok();
+                }
+            }
+            Message::Stats {
+                generation,
+                population,
+                changes,
+                uptime_ms,
+            } => {
+                message.push(STATS_TYPE).ok();
+                message.extend_from_slice(&generation.to_le_bytes()).ok();
+                message
+                    .extend_from_slice(&[population, changes.births, changes.deaths])
+                    .ok();
+                message.extend_from_slice(&uptime_ms.to_le_bytes()).ok();
+            }
+            Message::Event(event) => {
+                let (code, value) = match event {
+                    Event::Paused => (1, 0),
+                    Event::Resumed => (2, 0),
+                    Event::Rule(rule_n) => (3, rule_n),
+                    Event::Speed(speed) => (4, speed),
+                };
+                message.extend_from_slice(&[EVENT_TYPE, code, value]).ok();
+            }
+        }
+        let crc = crc16(&message);
+        message.extend_from_slice(&crc.to_le_bytes()).ok();
+        cobs_encode(&message)
+    }
+}
+
+// The parts of the state of the game that are reported as events when they change.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct GameStatus {
+    pub paused: bool,
+    pub rule: u8,
+    pub speed: u8,
+}
+
+impl GameStatus {
+    // Returns the events that take the game from this status to a new one.
+    pub fn events(&self, new: GameStatus) -> Vec<Event, 3> {
+        let mut events = Vec::new();
+        if new.paused != self.paused {
+            let event = if new.paused {
+                Event::Paused
+            } else {
+                Event::Resumed
+            };
+            events.push(event).ok();
+        }
+        if new.rule != self.rule {
+            events.push(Event::Rule(new.rule)).ok();
+        }
+        if new.speed != self.speed {
+            events.push(Event::Speed(new.speed)).ok();
+        }
+        events
+    }
+}
+
+// Encode a message with COBS and add the delimiter. Every zero is replaced with the
+// distance to the next one, and the first byte is the distance to the first zero. The
+// messages are shorter than 254 bytes, so there are no blocks of 254 non-zero bytes,
+// which would need an extra byte.
+fn cobs_encode(message: &[u8]) -> Frame {
+    let mut frame = Frame::new();
+    let mut code_index = 0;
+    frame.push(0).ok();
+    for &byte in message {
+        if byte == 0 {
+            frame[code_index] = (frame.len() - code_index) as u8;
+            code_index = frame.len();
+        }
+        frame.push(byte).ok();
+    }
+    frame[code_index] = (frame.len() - code_index) as u8;
+    frame.push(0).ok();
+    frame
+}
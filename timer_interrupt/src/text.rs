@@ -0,0 +1,171 @@
+use core::fmt;
+
+// Maximum number of characters that a ScrollingText can hold. Characters beyond this
+// limit are silently dropped.
+const MAX_TEXT_LENGTH: usize = 32;
+// Number of blank columns left between two consecutive characters.
+const CHARACTER_SPACING: usize = 1;
+// Width of the characters of the font, and of the display.
+const WIDTH: usize = 5;
+
+// Text that scrolls from right to left across the 5x5 LED matrix. The text is shifted
+// one column per frame, and the frames are produced as 5x5 matrices of 0s and 1s so
+// they can be shown both with the blocking display (directly) and the non-blocking
+// display (through FrameBuffer::draw_bits).
+// The frames are obtained by iterating over the ScrollingText. The text enters the
+// display from the right edge and leaves it through the left edge, so the first and
+// the last frames are blank.
+pub struct ScrollingText {
+    text: [u8; MAX_TEXT_LENGTH],
+    length: usize,
+    // Position of the left edge of the display within the scrolling text:
+    column: usize,
+}
+
+impl ScrollingText {
+    pub const fn empty() -> Self {
+        Self {
+            text: [b' '; MAX_TEXT_LENGTH],
+            length: 0,
+            column: 0,
+        }
+    }
+
+    pub fn new(text: &str) -> Self {
+        let mut scrolling_text = Self::empty();
+        scrolling_text.push_str(text);
+        scrolling_text
+    }
+
+    // Add more characters at the end of the text. Only ASCII characters are supported,
+    // the rest are replaced by '?'.
+    pub fn push_str(&mut self, text: &str) {
+        for character in text.chars() {
+            if self.length == MAX_TEXT_LENGTH {
+                break;
+            }
+            self.text[self.length] = if character.is_ascii() {
+                character as u8
+            } else {
+                b'?'
+            };
+            self.length += 1;
+        }
+    }
+
+    // Total number of columns of the text, including the blank columns on both sides
+    // used to make it enter and leave the display.
+    fn n_columns(&self) -> usize {
+        WIDTH + self.length * (WIDTH + CHARACTER_SPACING) + WIDTH
+    }
+
+    // Returns the column number `column` of the text, where each bit of the returned
+    // value corresponds to one row (the least significant bit is the top row).
+    fn column_bits(&self, column: usize) -> u8 {
+        // The first WIDTH columns are blank, so the text enters from the right.
+        let Some(column) = column.checked_sub(WIDTH) else {
+            return 0;
+        };
+        let character_n = column / (WIDTH + CHARACTER_SPACING);
+        let column_in_character = column % (WIDTH + CHARACTER_SPACING);
+        if character_n >= self.length || column_in_character >= WIDTH {
+            return 0;
+        }
+
+        let rows = glyph(self.text[character_n]);
+        let mut bits = 0;
+        for (row_n, row) in rows.into_iter().enumerate() {
+            // The leftmost column of the glyph is stored in the most significant bit.
+            if row & (1 << (WIDTH - 1 - column_in_character)) != 0 {
+                bits |= 1 << row_n;
+            }
+        }
+        bits
+    }
+}
+
+impl Iterator for ScrollingText {
+    type Item = [[u8; 5]; 5];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The last frame is the one in which the display shows the last WIDTH columns.
+        if self.column + WIDTH > self.n_columns() {
+            return None;
+        }
+
+        let mut frame = [[0; 5]; 5];
+        for col_n in 0..WIDTH {
+            let bits = self.column_bits(self.column + col_n);
+            for (row_n, row) in frame.iter_mut().enumerate() {
+                row[col_n] = (bits >> row_n) & 1;
+            }
+        }
+        self.column += 1;
+        Some(frame)
+    }
+}
+
+// Implementing fmt::Write allows to use the write! macro to build texts with numbers
+// in them, such as write!(text, "GEN {}", generation), without needing an allocator.
+impl fmt::Write for ScrollingText {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.push_str(text);
+        Ok(())
+    }
+}
+
+// 5x5 font. Each character is represented by 5 rows (top first), and in each row the
+// 5 least significant bits indicate which LEDs are on (the leftmost LED is the most
+// significant bit). Lowercase letters are shown as uppercase, and characters that are
+// not in the font are shown as '?'.
+fn glyph(character: u8) -> [u8; 5] {
+    match character.to_ascii_uppercase() {
+        b' ' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        b'0' => [0b01110, 0b10011, 0b10101, 0b11001, 0b01110],
+        b'1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b01110],
+        b'2' => [0b11110, 0b00001, 0b01110, 0b10000, 0b11111],
+        b'3' => [0b11110, 0b00001, 0b00110, 0b00001, 0b11110],
+        b'4' => [0b00110, 0b01010, 0b10010, 0b11111, 0b00010],
+        b'5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b11110],
+        b'6' => [0b00010, 0b00100, 0b01110, 0b10001, 0b01110],
+        b'7' => [0b11111, 0b00010, 0b00100, 0b01000, 0b10000],
+        b'8' => [0b01110, 0b10001, 0b01110, 0b10001, 0b01110],
+        b'9' => [0b01110, 0b10001, 0b01110, 0b00100, 0b01000],
+        b'A' => [0b01110, 0b10001, 0b11111, 0b10001, 0b10001],
+        b'B' => [0b11110, 0b10001, 0b11110, 0b10001, 0b11110],
+        b'C' => [0b01111, 0b10000, 0b10000, 0b10000, 0b01111],
+        b'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b11110],
+        b'E' => [0b11111, 0b10000, 0b11110, 0b10000, 0b11111],
+        b'F' => [0b11111, 0b10000, 0b11110, 0b10000, 0b10000],
+        b'G' => [0b01111, 0b10000, 0b10011, 0b10001, 0b01110],
+        b'H' => [0b10001, 0b10001, 0b11111, 0b10001, 0b10001],
+        b'I' => [0b11111, 0b00100, 0b00100, 0b00100, 0b11111],
+        b'J' => [0b11111, 0b00010, 0b00010, 0b10010, 0b01100],
+        b'K' => [0b10010, 0b10100, 0b11000, 0b10100, 0b10010],
+        b'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        b'M' => [0b10001, 0b11011, 0b10101, 0b10001, 0b10001],
+        b'N' => [0b10001, 0b11001, 0b10101, 0b10011, 0b10001],
+        b'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b01110],
+        b'P' => [0b11110, 0b10001, 0b11110, 0b10000, 0b10000],
+        b'Q' => [0b01110, 0b10001, 0b10101, 0b10010, 0b01101],
+        b'R' => [0b11110, 0b10001, 0b11110, 0b10010, 0b10001],
+        b'S' => [0b01111, 0b10000, 0b01110, 0b00001, 0b11110],
+        b'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100],
+        b'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        b'V' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        b'W' => [0b10001, 0b10001, 0b10101, 0b11011, 0b10001],
+        b'X' => [0b10001, 0b01010, 0b00100, 0b01010, 0b10001],
+        b'Y' => [0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        b'Z' => [0b11111, 0b00010, 0b00100, 0b01000, 0b11111],
+        b'.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00100],
+        b',' => [0b00000, 0b00000, 0b00000, 0b00100, 0b01000],
+        b':' => [0b00000, 0b00100, 0b00000, 0b00100, 0b00000],
+        b'-' => [0b00000, 0b00000, 0b01110, 0b00000, 0b00000],
+        b'+' => [0b00000, 0b00100, 0b01110, 0b00100, 0b00000],
+        b'=' => [0b00000, 0b01110, 0b00000, 0b01110, 0b00000],
+        b'/' => [0b00001, 0b00010, 0b00100, 0b01000, 0b10000],
+        b'%' => [0b11001, 0b11010, 0b00100, 0b01011, 0b10011],
+        b'!' => [0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        _ => [0b01110, 0b10001, 0b00110, 0b00000, 0b00100],
+    }
+}
@@ -0,0 +1,72 @@
+use microbit::{hal::temp::Temp, pac::TEMP};
+
+// Number of units of a reading per degree: the readings are in quarters of a degree.
+const UNITS_PER_DEGREE: i32 = 4;
+// Change of temperature that makes the game one speed faster or slower, in degrees.
+const DEGREES_PER_SPEED: i32 = 1;
+
+// The temperature sensor of the nRF52833, inside the die of the microcontroller, so it
+// reads a few degrees above the ambient temperature. A measurement takes a few tens of
+// microseconds and runs in the background, so the sensor is polled: each poll reads the
+// measurement started by the previous one and starts the next.
+// Its readings are noisy in their lowest bits, so they are a source of entropy, and the
+// thermometer also computes how much the temperature nudges the speed of the game when
+// that mode is selected.
+pub struct Thermometer {
+    temp: Temp,
+    // Whether a measurement has been started and not read yet:
+    measuring: bool,
+    // Reading to which the later ones are compared to nudge the speed, or None if the
+    // next reading must be taken as the baseline:
+    baseline: Option<i32>,
+    // Number of speeds that the game is made faster (or slower, if negative):
+    nudge: i32,
+}
+
+impl Thermometer {
+    pub fn new(temp: TEMP) -> Self {
+        Self {
+            temp: Temp::new(temp),
+            measuring: false,
+            baseline: None,
+            nudge: 0,
+        }
+    }
+
+    // Read the last measurement and start the next one. Returns the temperature in
+    // quarters of a degree, or None if there is no measurement ready.
+    pub fn poll(&mut self) -> Option<i32> {
+        let reading = if self.measuring {
+            self.temp.read().ok().map(|reading| reading.to_bits())
+        } else {
+            None
+        };
+        if !self.measuring || reading.is_some() {
+            self.temp.start_measurement();
+            self.measuring = true;
+        }
+        reading
+    }
+
+    // Update the nudge of the speed with a new reading: one speed faster for every
+    // DEGREES_PER_SPEED above the baseline, and slower below it. Returns true if the
+    // nudge has changed.
+    pub fn update_nudge(&mut self, reading: i32) -> bool {
+        let baseline = *self.baseline.get_or_insert(reading);
+        let nudge = (reading - baseline) / (UNITS_PER_DEGREE * DEGREES_PER_SPEED);
+        let changed = nudge != self.nudge;
+        self.nudge = nudge;
+        changed
+    }
+
+    pub fn nudge(&self) -> i32 {
+        self.nudge
+    }
+
+    // Forget the baseline and the nudge, so that the speed is back to the selected one
+    // and the next reading becomes the new baseline.
+    pub fn reset_nudge(&mut self) {
+        self.baseline = None;
+        self.nudge = 0;
+    }
+}
@@ -0,0 +1,70 @@
+use crate::timing::LFCLK_HZ;
+use core::cell::RefCell;
+use cortex_m::interrupt::{CriticalSection, Mutex};
+use microbit::{
+    hal::rtc::{Rtc, RtcInterrupt},
+    pac::RTC2,
+};
+
+// Number of bits of the counter of a real time counter.
+const COUNTER_BITS: u32 = 24;
+
+// Real time counter that measures the time since the board started, and the number of
+// times that its counter has overflowed.
+static CLOCK: Mutex<RefCell<Option<Clock>>> = Mutex::new(RefCell::new(None));
+
+struct Clock {
+    counter: Rtc<RTC2>,
+    overflows: u32,
+}
+
+// Start measuring the uptime with the rtc2. It runs at the full frequency of the
+// low-frequency clock, without prescaler, so its 24 bit counter overflows every 512 s.
+// Every overflow raises the RTC2 interrupt, which must call on_overflow, and the
+// overflows and the counter together make a 56 bit count of the periods of the clock,
+// which doesn't overflow in the lifetime of the board. The other counters can't be used,
+// since the rtc1 is cleared every generation and the rtc0 is slower.
+pub fn start(rtc: RTC2) {
+    // A prescaler of 0 is always valid, so this can't fail.
+    let mut counter = Rtc::new(rtc, 0).unwrap();
+    counter.enable_event(RtcInterrupt::Overflow);
+    counter.enable_interrupt(RtcInterrupt::Overflow, None);
+    counter.enable_counter();
+    cortex_m::interrupt::free(|cs| {
+        CLOCK.borrow(cs).replace(Some(Clock {
+            counter,
+            overflows: 0,
+        }));
+    });
+}
+
+// Must be called from the RTC2 interrupt, to count the overflow of the counter.
+pub fn on_overflow(cs: &CriticalSection) {
+    if let Some(clock) = CLOCK.borrow(cs).borrow_mut().as_mut() {
+        if clock.counter.is_event_triggered(RtcInterrupt::Overflow) {
+            clock.counter.reset_event(RtcInterrupt::Overflow);
+            clock.overflows += 1;
+        }
+    }
+}
+
+// Returns the number of milliseconds since the uptime started to be measured, or 0 if
+// it hasn't yet. Every feature that times something longer than a display tick takes
+// the time from here, instead of counting its own ticks.
+pub fn now_ms(cs: &CriticalSection) -> u64 {
+    let clock = CLOCK.borrow(cs).borrow();
+    let Some(clock) = clock.as_ref() else {
+        return 0;
+    };
+    // Inside the critical section the interrupt can't count an overflow that has just
+    // happened, so if its event is set, the overflow is counted here, and the counter is
+    // read again in case it was read right before overflowing.
+    let mut overflows = clock.overflows as u64;
+    let mut counter = clock.counter.get_counter();
+    if clock.counter.is_event_triggered(RtcInterrupt::Overflow) {
+        overflows += 1;
+        counter = clock.counter.get_counter();
+    }
+    let periods = overflows << COUNTER_BITS | counter as u64;
+    periods / LFCLK_HZ as u64 * 1000 + periods % LFCLK_HZ as u64 * 1000 / LFCLK_HZ as u64
+}
@@ -0,0 +1,92 @@
+use microbit::{
+    hal::{
+        ppi::ConfigurablePpi,
+        rtc::{Rtc, RtcCompareReg, RtcInterrupt},
+    },
+    pac::RTC1,
+};
+
+// Frequency of the low-frequency clock that drives the real time counters.
+pub const LFCLK_HZ: u32 = 32768;
+
+// Prescaler of the real time counter used to poll the buttons. It gives a frequency of
+// 166.66 Hz, i.e., a period of approximately 6 ms.
+pub const BUTTON_PRESCALER: u32 = 196;
+// Time that a button must be read with the same value for the change to be accepted, in
+// milliseconds. It must be longer than the bounces of the buttons.
+pub const DEBOUNCE_MS: u32 = 20;
+// Time that a button must be kept pressed for the press to be considered a long press,
+// in milliseconds.
+pub const LONG_PRESS_MS: u64 = 1000;
+// Maximum time between two clicks of a button for them to be considered a double click,
+// in milliseconds. Short presses of the buttons that detect double clicks are delayed by
+// this time, so it shouldn't be too long.
+pub const DOUBLE_CLICK_MS: u64 = 300;
+// Time between two repeat events while a button is kept pressed after a long press, in
+// milliseconds.
+pub const REPEAT_INTERVAL_MS: u64 = 150;
+// Time that both buttons must be kept pressed to switch the board off once they are
+// released, in milliseconds.
+pub const POWER_OFF_HOLD_MS: u64 = 3000;
+// Time that both buttons must be kept pressed to restart the board instead, in
+// milliseconds.
+pub const RESET_HOLD_MS: u64 = 6000;
+
+// Frequency at which the image shown on the display is updated. Every display tick
+// the fade, the scrolling text and the blinking indicators can advance one step, so a
+// high value makes these effects smoother. It must divide LFCLK_HZ.
+pub const DISPLAY_TICK_HZ: u32 = 32;
+// Times between two generations of the game that can be selected, in milliseconds,
+// from the slowest to the fastest. They are independent from the display refresh rate,
+// so the game can evolve slowly while the display effects run smoothly.
+pub const GENERATION_PERIODS_MS: [u32; 5] = [2000, 1000, 500, 250, 125];
+// Index of the fastest generation period.
+pub const FASTEST_SPEED: usize = GENERATION_PERIODS_MS.len() - 1;
+// Index of the generation period used when the board starts (1000 ms).
+pub const DEFAULT_SPEED: usize = 1;
+
+// Prescaler of the real time counter used for the display. The frequency of an RTC is
+// given by: f [Hz] = 32768 / (prescaler + 1).
+pub const DISPLAY_PRESCALER: u32 = LFCLK_HZ / DISPLAY_TICK_HZ - 1;
+// Number of display ticks between two generations at the given speed, an index of
+// GENERATION_PERIODS_MS.
+pub const fn generation_period_ticks(speed: usize) -> u32 {
+    ms_to_ticks(GENERATION_PERIODS_MS[speed])
+}
+
+// Change the generation period of a running display counter, given its speed, an index
+// of GENERATION_PERIODS_MS. The counter is cleared too, otherwise it could already be
+// past the new compare value and it would take a whole overflow of the counter (more
+// than 8 minutes) to reach it. A compare event that was about to be handled is
+// discarded, so the next generation comes a full period after the change.
+pub fn set_generation_speed(display_counter: &mut Rtc<RTC1>, speed: usize) {
+    display_counter.clear_counter();
+    display_counter.reset_event(RtcInterrupt::Compare0);
+    // The periods are much shorter than the 24 bits of the counter, so this can't fail.
+    display_counter
+        .set_compare(RtcCompareReg::Compare0, generation_period_ticks(speed))
+        .ok();
+}
+
+// Connect the Compare0 event of the display counter to its clear task through a PPI
+// channel. The counter then starts the next generation period as soon as the current
+// one ends, instead of when the interrupt gets to clear it, so the time that the
+// interrupt waits behind other interrupts doesn't add up to the period. The interrupt
+// is still needed to advance the game.
+pub fn link_generation_clear<P: ConfigurablePpi>(mut channel: P) {
+    let rtc1 = unsafe { &*RTC1::ptr() };
+    channel.set_event_endpoint(&rtc1.events_compare[0]);
+    channel.set_task_endpoint(&rtc1.tasks_clear);
+    channel.enable();
+}
+
+// Convert a duration in milliseconds to the equivalent number of display ticks.
+pub const fn ms_to_ticks(ms: u32) -> u32 {
+    ms * DISPLAY_TICK_HZ / 1000
+}
+
+// Convert a duration in milliseconds to the equivalent number of polls of the buttons,
+// rounding up.
+pub const fn ms_to_polls(ms: u32) -> u32 {
+    (ms * LFCLK_HZ).div_ceil((BUTTON_PRESCALER + 1) * 1000)
+}
@@ -0,0 +1,56 @@
+use microbit::{
+    hal::gpio::{p1::P1_04, Floating, Input},
+    pac::P1,
+};
+
+// Pin number (in port 1) of the touch logo on the front of the board.
+const LOGO_PIN: usize = 4;
+// Maximum number of times the pin is read while it charges. If it's still low after
+// that, the logo is considered touched.
+const MAX_CHARGE_LOOPS: u32 = 1000;
+// Number of reads that the pin takes to charge when it's touched. Without a finger the
+// pin charges almost immediately, and with a finger it takes a few tens of reads. This
+// value is approximate and may need to be adjusted for each board.
+const TOUCHED_LOOPS: u32 = 20;
+
+// Capacitive sensor of the touch logo. The logo is a pad of copper connected to a pin,
+// and touching it adds the capacitance of the finger to the pin. To measure it, the
+// pin is discharged, driving it low, and then it's left to charge through its pull-up
+// resistor while the time it takes to read high is measured. The larger the
+// capacitance, the longer it takes to charge.
+pub struct TouchLogo {
+    // The pin is only kept so that nothing else can use it, it's configured directly
+    // through the registers of the port, like the display driver does.
+    _pin: P1_04<Input<Floating>>,
+}
+
+impl TouchLogo {
+    pub fn new(pin: P1_04<Input<Floating>>) -> Self {
+        Self { _pin: pin }
+    }
+
+    // Returns true if the logo is being touched.
+    pub fn is_touched(&mut self) -> bool {
+        self.charge_loops() >= TOUCHED_LOOPS
+    }
+
+    // Discharge the pin and count the number of reads until it's charged again.
+    fn charge_loops(&mut self) -> u32 {
+        let p1 = unsafe { &*P1::ptr() };
+
+        p1.outclr.write(|w| unsafe { w.bits(1 << LOGO_PIN) });
+        p1.pin_cnf[LOGO_PIN].write(|w| w.dir().output().input().disconnect());
+        // Give the pin some time to discharge completely.
+        cortex_m::asm::delay(64);
+
+        p1.pin_cnf[LOGO_PIN].write(|w| w.dir().input().input().connect().pull().pullup());
+        let mut loops = 0;
+        while p1.in_.read().bits() & (1 << LOGO_PIN) == 0 && loops < MAX_CHARGE_LOOPS {
+            loops += 1;
+        }
+
+        // Leave the pin as a floating input, like the rest of the time.
+        p1.pin_cnf[LOGO_PIN].write(|w| w.dir().input().input().connect().pull().disabled());
+        loops
+    }
+}
@@ -0,0 +1,57 @@
+// Brightness of the LEDs of the live cells. This is the maximum brightness supported
+// by the non-blocking display.
+const LIVE_BRIGHTNESS: u8 = 9;
+// Brightness of the LED of a cell right after it dies. The brightness of a dead cell
+// is then reduced by TRAIL_DECAY every generation until it reaches 0. With the values
+// below, a dead cell keeps glowing for three generations (5, 3, 1) before going dark.
+const TRAIL_START_BRIGHTNESS: u8 = 5;
+const TRAIL_DECAY: u8 = 2;
+
+// Keeps track of the brightness of the cells that have recently died, so that they
+// leave a fading "phosphor trail" behind them. This makes it much easier to follow
+// the movement of gliders and oscillators on the 5x5 matrix.
+pub struct DeathTrail {
+    brightness: [[u8; 5]; 5],
+}
+
+impl DeathTrail {
+    pub const fn new() -> Self {
+        Self {
+            brightness: [[0; 5]; 5],
+        }
+    }
+
+    // Update the trail after a generation. It takes the matrix of the game before and
+    // after the update: the cells that were alive before and are now dead start
+    // glowing, and the rest of dead cells fade a bit further.
+    pub fn update(&mut self, previous_matrix: [[bool; 5]; 5], current_matrix: [[bool; 5]; 5]) {
+        for (row_n, row) in current_matrix.into_iter().enumerate() {
+            for (col_n, alive) in row.into_iter().enumerate() {
+                let brightness = &mut self.brightness[row_n][col_n];
+                *brightness = match (previous_matrix[row_n][col_n], alive) {
+                    // The cell is alive, its brightness is set when building the image.
+                    (_, true) => 0,
+                    // The cell has just died:
+                    (true, false) => TRAIL_START_BRIGHTNESS,
+                    // The cell was already dead:
+                    (false, false) => brightness.saturating_sub(TRAIL_DECAY),
+                };
+            }
+        }
+    }
+
+    // Compute the brightness of the image that will be shown on the display, with the
+    // live cells at full brightness and the recently dead cells at their trail
+    // brightness.
+    pub fn brightness_matrix(&self, matrix: [[bool; 5]; 5]) -> [[u8; 5]; 5] {
+        let mut image_matrix = self.brightness;
+        for (row_n, row) in matrix.into_iter().enumerate() {
+            for (col_n, alive) in row.into_iter().enumerate() {
+                if alive {
+                    image_matrix[row_n][col_n] = LIVE_BRIGHTNESS;
+                }
+            }
+        }
+        image_matrix
+    }
+}
@@ -0,0 +1,32 @@
+use crate::timing::LFCLK_HZ;
+use microbit::{
+    hal::wdt::{count, handles::Hdl0, handles::Hdl1, Watchdog, WatchdogHandle},
+    pac::WDT,
+};
+
+// Time without being pet after which the watchdog resets the board, in seconds. It must
+// be longer than the slowest generation period (2 s), plus the erase of a page of flash.
+const TIMEOUT_S: u32 = 5;
+
+// The two handles of the watchdog, which must both be pet in time: one by the interrupt
+// that advances the game and one by the main loop.
+pub type GameHandle = WatchdogHandle<Hdl0>;
+pub type LoopHandle = WatchdogHandle<Hdl1>;
+
+// Start the watchdog, so that the board is reset if the game or the main loop stop, for
+// example because an interrupt or the main loop is stuck inside a critical section. It
+// keeps running while the CPU sleeps, but not while it's halted by the debugger. Once
+// started, the watchdog can't be stopped or configured until the board is reset, and a
+// soft reset doesn't reset it, so if it's already running its handles are recovered.
+pub fn start(wdt: WDT) -> Option<(GameHandle, LoopHandle)> {
+    let parts = match Watchdog::try_new(wdt) {
+        Ok(mut watchdog) => {
+            watchdog.set_lfosc_ticks(TIMEOUT_S * LFCLK_HZ);
+            watchdog.run_during_sleep(true);
+            watchdog.run_during_debug_halt(false);
+            watchdog.activate::<count::Two>()
+        }
+        Err(wdt) => Watchdog::try_recover::<count::Two>(wdt).ok()?,
+    };
+    Some(parts.handles)
+}
@@ -0,0 +1,118 @@
+use crate::color::{self, Rgb};
+use crate::my_board::Ws2812Pins;
+use embedded_hal::blocking::spi::Write;
+use microbit::{
+    hal::spim::{self, Frequency, Spim},
+    pac::SPIM2,
+};
+
+// Size of the panel, in LEDs per side. 8x8 and 16x16 panels are the most common.
+pub const PANEL_SIZE: usize = 8;
+// Whether the LEDs of the panel are wired in a zigzag, with every other row running
+// from right to left, as most 16x16 panels are. Otherwise all the rows run from left
+// to right.
+const SERPENTINE: bool = false;
+// The LEDs of the panel are very bright, so the colors are divided by this value.
+const DIMMING: u8 = 8;
+
+// Each bit sent to the LEDs is encoded as 4 bits of the SPI bus, which runs at 4 MHz,
+// i.e., 250 ns per bit. A 0 is a 250 ns high pulse followed by a 750 ns low level, and a
+// 1 is a 750 ns high pulse followed by a 250 ns low level.
+const SPI_ZERO: u8 = 0b1000;
+const SPI_ONE: u8 = 0b1110;
+// Each LED receives 24 bits (green, red and blue), so it needs 12 bytes of the bus.
+const BYTES_PER_LED: usize = 12;
+// After the data, the line must stay low for at least 50 us so the LEDs latch the new
+// colors. 32 bytes of zeros keep it low for 64 us.
+const RESET_BYTES: usize = 32;
+const BUFFER_SIZE: usize = PANEL_SIZE * PANEL_SIZE * BYTES_PER_LED + RESET_BYTES;
+
+// Panel of WS2812 ("NeoPixel") LEDs connected to pin 1 of the edge connector. The LEDs
+// have a strict single wire protocol, with pulses of a few hundred nanoseconds, which is
+// generated with the SPI master: only its MOSI line is used for the data, and the clock
+// goes to pin 2 of the edge connector, which must be left unconnected. The DMA of the
+// SPI master sends the whole frame without gaps between bytes.
+pub struct Ws2812 {
+    spim: Spim<SPIM2>,
+}
+
+impl Ws2812 {
+    pub fn new(spim2: SPIM2, pins: Ws2812Pins) -> Self {
+        let spim = Spim::new(
+            spim2,
+            spim::Pins {
+                sck: pins.sck.degrade(),
+                mosi: Some(pins.data.degrade()),
+                miso: None,
+            },
+            Frequency::M4,
+            spim::MODE_0,
+            0,
+        );
+        Self { spim }
+    }
+
+    // Show the colors on the panel. The colors are given row by row, as they are seen
+    // on the panel.
+    pub fn show(&mut self, colors: &[[Rgb; PANEL_SIZE]; PANEL_SIZE]) {
+        let mut buffer = [0; BUFFER_SIZE];
+        for (row_n, row) in colors.iter().enumerate() {
+            for (col_n, color) in row.iter().enumerate() {
+                let led = if SERPENTINE && row_n % 2 == 1 {
+                    row_n * PANEL_SIZE + PANEL_SIZE - 1 - col_n
+                } else {
+                    row_n * PANEL_SIZE + col_n
+                };
+                let bytes = &mut buffer[led * BYTES_PER_LED..(led + 1) * BYTES_PER_LED];
+                // The LEDs expect the components in green, red, blue order, with the
+                // most significant bit first.
+                for (component_n, component) in
+                    [color.green, color.red, color.blue].into_iter().enumerate()
+                {
+                    encode(
+                        component / DIMMING,
+                        &mut bytes[4 * component_n..4 * component_n + 4],
+                    );
+                }
+            }
+        }
+        // If the transfer fails the panel keeps showing the previous frame. The write of
+        // embedded-hal is used because it doesn't need a chip select pin.
+        Write::write(&mut self.spim, &buffer).ok();
+    }
+}
+
+// Encode a byte of color data as 4 bytes of the SPI bus, two bits per byte.
+fn encode(value: u8, bytes: &mut [u8]) {
+    for (byte_n, byte) in bytes.iter_mut().enumerate() {
+        let high_bit = value & (0x80 >> (2 * byte_n)) != 0;
+        let low_bit = value & (0x40 >> (2 * byte_n)) != 0;
+        let spi_bits = |bit| if bit { SPI_ONE } else { SPI_ZERO };
+        *byte = (spi_bits(high_bit) << 4) | spi_bits(low_bit);
+    }
+}
+
+// Compute the colors of the panel for a frame of the display. If the display is showing
+// the cells of the game, their ages are given and the cells are colored by age;
+// otherwise the frame is shown in a plain color. The 5x5 frame is scaled up as much as
+// it fits on the panel and centered on it.
+pub fn frame_colors(
+    frame: [[u8; 5]; 5],
+    ages: Option<[[u8; 5]; 5]>,
+) -> [[Rgb; PANEL_SIZE]; PANEL_SIZE] {
+    const SCALE: usize = PANEL_SIZE / 5;
+    const OFFSET: usize = (PANEL_SIZE - 5 * SCALE) / 2;
+
+    let mut colors = [[Rgb::new(0, 0, 0); PANEL_SIZE]; PANEL_SIZE];
+    for (row_n, row) in colors.iter_mut().enumerate().skip(OFFSET).take(5 * SCALE) {
+        for (col_n, color) in row.iter_mut().enumerate().skip(OFFSET).take(5 * SCALE) {
+            let (frame_row, frame_col) = ((row_n - OFFSET) / SCALE, (col_n - OFFSET) / SCALE);
+            let brightness = frame[frame_row][frame_col];
+            *color = match ages {
+                Some(ages) => color::cell_color(ages[frame_row][frame_col], brightness),
+                None => color::plain_color(brightness),
+            };
+        }
+    }
+    colors
+}